@@ -1,22 +1,64 @@
 extern crate clap;
 
-use burl::parser::{from_get_url, parse_toml};
-use burl::BenchClient;
+use burl::parser::{from_get_url, parse_spec};
+use burl::stats::TestOutcome;
+use burl::{BenchClient, BenchClientConfig, BurlError};
+use burl_reporter::{EnvironmentRun, EnvironmentsReport, ReplayedRun};
 // use burl_reporter::
-use clap::{Parser, Subcommand};
-use env_logger::Env;
-use log::{error, info, trace};
-use std::error::Error;
+use clap::{Parser, Subcommand, ValueEnum};
+use env_logger::{fmt::Formatter, Env};
+use log::kv::{Key, Value, VisitSource};
+use log::{error, info, trace, warn, Record};
+use serde_json::{json, Map};
+use std::{error::Error, fmt::Display, io::Write, num::NonZeroUsize, path::Path};
 
 const LOG_LEVEL: &str = "LOG_LEVEL";
 const DEFAULT_LEVEL: &str = "INFO";
 
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum LogFormat {
+    /// Plain text lines.
+    #[default]
+    Text,
+    /// One JSON object per line, e.g. for log aggregators.
+    Json,
+}
+
 #[derive(Subcommand, Debug)]
 enum BenchRunnerArg {
-    /// Read in a `specs.toml` file at the specified location `file_path`.
+    /// Read in a `specs.toml` file at the specified location `file_path`;
+    /// YAML and JSON specs are read by their file extension.
     FromToml,
     Get,
     // TODO: further: Put, etc
+    /// Send a single request to `url` (or the one of the specs at `file_name`) and print
+    /// its status, headers, body and timing, without benchmarking.
+    Smoke,
+    /// Regenerate the report in `report_directory` from its data, without benchmarking again.
+    Replay,
+    /// Compare the runs of two report directories in a new report.
+    CompareDirs {
+        /// The report directory of the baseline run, e.g. of release A.
+        baseline_directory: String,
+        /// The report directory of the compared run, e.g. of release B.
+        current_directory: String,
+        /// The directory of the comparison report.<br>
+        /// Default value: 'comparison' in the directory of the compared run
+        #[clap(short, long)]
+        output_directory: Option<String>,
+    },
+    /// Benchmark two environments of the same endpoint at the same time, e.g. staging and prod,
+    /// and compare them in a single report.
+    CompareEnvs {
+        /// The specs file of the baseline environment, e.g. prod.
+        baseline_file: String,
+        /// The specs file of the compared environment, e.g. staging.
+        current_file: String,
+        /// The directory of the comparison report.<br>
+        /// Default value: 'environments' in current dir
+        #[clap(short, long)]
+        output_directory: Option<String>,
+    },
 }
 
 /// CLI to run the burl benchmarker.
@@ -32,23 +74,210 @@ struct CliArgs {
     file_name: Option<String>,
     #[clap(short, long)]
     url: Option<String>,
+    /// The directory of the report to replay.
+    #[clap(short, long)]
+    report_directory: Option<String>,
+    /// The format of the logs.
+    #[clap(long, value_enum, default_value_t)]
+    log_format: LogFormat,
+    /// Continue the previous run in the `report_directory` of the specs, e.g. an interrupted
+    /// one: its samples are merged with the new ones into a combined report.
+    #[clap(long = "continue")]
+    continue_run: bool,
+    /// The number of worker threads of the async runtime, e.g. to match the concurrency of the
+    /// benchmark.<br>
+    /// Default value: the number of CPU cores
+    #[clap(long)]
+    runtime_threads: Option<NonZeroUsize>,
+}
+
+/// Collects the key-values of a log record as JSON fields.
+struct JsonFields<'a>(&'a mut Map<String, serde_json::Value>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), log::kv::Error> {
+        let value = match (value.to_u64(), value.to_f64(), value.to_bool()) {
+            (Some(n), _, _) => json!(n),
+            (None, Some(x), _) => json!(x),
+            (None, None, Some(b)) => json!(b),
+            _ => json!(value.to_string()),
+        };
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+/// The record as a JSON object, with its key-values (e.g. the run label) as fields.
+fn json_line(record: &Record, timestamp: impl Display) -> String {
+    let mut fields = Map::new();
+    fields.insert("timestamp".to_string(), json!(timestamp.to_string()));
+    fields.insert("level".to_string(), json!(record.level().as_str()));
+    fields.insert("target".to_string(), json!(record.target()));
+    fields.insert("message".to_string(), json!(record.args().to_string()));
+    // NOTE: the fields above are kept if the record has keys of the same name
+    let mut kv_fields = Map::new();
+    let _ = record.key_values().visit(&mut JsonFields(&mut kv_fields));
+    for (key, value) in kv_fields {
+        fields.entry(key).or_insert(value);
+    }
+    serde_json::Value::Object(fields).to_string()
+}
+
+fn json_format(buf: &mut Formatter, record: &Record) -> std::io::Result<()> {
+    let line = json_line(record, buf.timestamp());
+    writeln!(buf, "{}", line)
+}
+
+fn logger(log_format: LogFormat, log_level: &str) -> env_logger::Builder {
+    let mut builder = env_logger::Builder::from_env(Env::default().default_filter_or(log_level));
+    if log_format == LogFormat::Json {
+        builder.format(json_format);
+    }
+    builder
 }
 
 const DEFAULT_TOML: &str = "specs.toml";
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    let log_level = std::env::var(LOG_LEVEL).unwrap_or_else(|_| DEFAULT_LEVEL.to_string());
-    env_logger::Builder::from_env(Env::default().default_filter_or(&log_level)).init();
+/// Loads the previous run from the `report_directory` of the specs, to be continued.
+fn prior_run(specs: &BenchClientConfig) -> Result<ReplayedRun, BurlError> {
+    let report_directory =
+        specs
+            .report_directory
+            .as_ref()
+            .ok_or_else(|| BurlError::InvalidConfig {
+                issue: "Continuing a run requires the `report_directory` of the previous run"
+                    .to_string(),
+            })?;
+    let prior_run = ReplayedRun::load(Path::new(report_directory))?;
+
+    let fingerprint = burl_reporter::config_fingerprint;
+    if fingerprint(&prior_run.config) != fingerprint(specs) {
+        warn!("The config of the continued run differs from the current one");
+    }
+    Ok(prior_run)
+}
+
+/// The multi-threaded runtime, with the given number of worker threads if set.
+fn runtime(worker_threads: Option<NonZeroUsize>) -> std::io::Result<tokio::runtime::Runtime> {
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    if let Some(worker_threads) = worker_threads {
+        builder.worker_threads(worker_threads.get());
+    }
+    builder.enable_all().build()
+}
 
+fn main() -> Result<(), Box<dyn Error>> {
     let args = CliArgs::parse();
+    runtime(args.runtime_threads)?.block_on(run(args))
+}
+
+async fn run(args: CliArgs) -> Result<(), Box<dyn Error>> {
+    let log_level = std::env::var(LOG_LEVEL).unwrap_or_else(|_| DEFAULT_LEVEL.to_string());
+    logger(args.log_format, &log_level).init();
+
+    if let BenchRunnerArg::Replay = args.cmd {
+        match args.report_directory {
+            Some(report_directory) => {
+                let replayed_run = ReplayedRun::load(Path::new(&report_directory))?;
+                replayed_run.report_factory().create_report()?;
+            }
+            None => error!("Report directory parameter required."),
+        }
+        return Ok(());
+    }
+
+    if let BenchRunnerArg::Smoke = args.cmd {
+        let specs = match (args.url, args.file_name) {
+            (Some(url), _) => Some(from_get_url(url)),
+            (None, Some(file_name)) => parse_spec(&file_name),
+            (None, None) => None,
+        };
+        match specs {
+            Some(specs) => {
+                let smoke = BenchClient::init(&specs)?.smoke().await?;
+                println!("{}", smoke);
+                if !smoke.is_success() {
+                    std::process::exit(1);
+                }
+            }
+            None => error!("URL or specs file parameter required."),
+        }
+        return Ok(());
+    }
+
+    if let BenchRunnerArg::CompareDirs {
+        baseline_directory,
+        current_directory,
+        output_directory,
+    } = &args.cmd
+    {
+        let output_directory = match output_directory {
+            Some(output_directory) => Path::new(output_directory).to_path_buf(),
+            None => Path::new(current_directory).join("comparison"),
+        };
+        let run = ReplayedRun::load_comparison(
+            Path::new(baseline_directory),
+            Path::new(current_directory),
+            &output_directory,
+        )?;
+        run.report_factory().create_report()?;
+        return Ok(());
+    }
+
+    if let BenchRunnerArg::CompareEnvs {
+        baseline_file,
+        current_file,
+        output_directory,
+    } = &args.cmd
+    {
+        let (Some(baseline_specs), Some(current_specs)) =
+            (parse_spec(baseline_file), parse_spec(current_file))
+        else {
+            error!("Unable to parse the specifications");
+            return Ok(());
+        };
+        let (baseline_bencher, current_bencher) = (
+            BenchClient::init(&baseline_specs)?,
+            BenchClient::init(&current_specs)?,
+        );
+
+        info!(
+            "Starting the benchmarks of {} and {}",
+            baseline_specs.run_label(),
+            current_specs.run_label()
+        );
+        let (baseline_run, current_run) =
+            tokio::join!(baseline_bencher.run(), current_bencher.run());
+        let (Some(baseline_run), Some(current_run)) = (baseline_run, current_run) else {
+            error!("The benchmark of an environment failed");
+            std::process::exit(1);
+        };
+
+        let start_time = baseline_run.start_time.min(current_run.start_time);
+        let end_time = baseline_run.end_time.max(current_run.end_time);
+        let report = EnvironmentsReport::new(
+            EnvironmentRun {
+                config: &baseline_specs,
+                stats_processor: baseline_run.stats_processor,
+            },
+            EnvironmentRun {
+                config: &current_specs,
+                stats_processor: current_run.stats_processor,
+            },
+            start_time,
+            end_time,
+        );
+        let output_directory = output_directory.as_deref().unwrap_or("environments");
+        report.create_report(Path::new(output_directory))?;
+        return Ok(());
+    }
 
     if let Some(specs) = match args.cmd {
         BenchRunnerArg::FromToml => {
-            trace!("Parsing TOML");
+            trace!("Parsing the specs");
             let file_name = args.file_name.unwrap_or_else(|| DEFAULT_TOML.to_string());
 
-            let specs = parse_toml(&file_name);
+            let specs = parse_spec(&file_name);
             if specs.is_none() {
                 error!("Unable to parse the specifications");
             }
@@ -62,13 +291,98 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 None
             }
         }
+        BenchRunnerArg::Replay
+        | BenchRunnerArg::Smoke
+        | BenchRunnerArg::CompareDirs { .. }
+        | BenchRunnerArg::CompareEnvs { .. } => None,
     } {
-        trace!("Initializing runner with {:?}", &specs);
+        trace!("Initializing runner with {:?}", specs.redacted());
         let bencher = BenchClient::init(&specs)?;
-        if let Some(run_summary) = bencher.run().await {
-            if let Some(stats) = &run_summary.stats() {
-                info!("{}", stats);
+        let prior_run = match args.continue_run {
+            true => Some(prior_run(&specs)?),
+            false => None,
+        };
+        let run_label = specs.run_label();
+        let fingerprint = burl_reporter::config_fingerprint(&specs);
+        info!(
+            run = run_label.as_str(),
+            fingerprint = fingerprint.as_str(),
+            n_runs = specs.n_runs(),
+            n_threads = specs.n_threads();
+            "Starting the benchmark (config fingerprint {})",
+            fingerprint
+        );
+        if let Some(mut run_summary) = bencher.run().await {
+            if let Some(prior_run) = prior_run {
+                run_summary.start_time = prior_run.start_time;
+                let prior_samples = prior_run.into_sample_results_by_thread();
+                info!(
+                    "Continuing the previous run with its {} samples",
+                    prior_samples.values().map(Vec::len).sum::<usize>()
+                );
+                run_summary.stats_processor = run_summary
+                    .stats_processor
+                    .with_prior_samples(&specs, prior_samples);
+            }
+            info!(
+                run = run_label.as_str(),
+                n_samples = run_summary.stats_processor.sample_results_by_thread().values().map(Vec::len).sum::<usize>(),
+                n_failed = run_summary.stats_processor.n_failed();
+                "Finished the benchmark"
+            );
+            let stats = run_summary.valid_stats();
+            if let Ok(stats) = &stats {
+                info!("{}", stats.display(specs.display_precision()));
+                if let Some(target) = specs.target_mean() {
+                    let outcome = match stats.target_test(target, specs.alpha()) {
+                        Some(TestOutcome::Improved { p_value }) => {
+                            format!("significantly below (p-value {})", p_value)
+                        }
+                        Some(TestOutcome::Regressed { p_value }) => {
+                            format!("significantly above (p-value {})", p_value)
+                        }
+                        Some(TestOutcome::Inconclusive) => "inconclusive".to_string(),
+                        None => "could not be determined".to_string(),
+                    };
+                    info!(
+                        "Mean vs. the target of {} {}s (alpha = {}): {}",
+                        target,
+                        stats.scale,
+                        specs.alpha(),
+                        outcome
+                    );
+                }
+            }
+            if let Some(run_variance) = &run_summary.run_variance {
+                info!("{}", run_variance);
+            }
+            if specs.honor_retry_after() {
+                let (n_throttled, throttled_duration) = run_summary.stats_processor.throttling();
+                info!(
+                    "Throttled {} times by the server, waiting {:.3} secs in total",
+                    n_throttled,
+                    throttled_duration.as_secs_f64()
+                );
             }
+            if let Some(target_rps) = specs.target_rps() {
+                if let Some(accuracy) = run_summary
+                    .stats_processor
+                    .rate_accuracy(target_rps, specs.partial_rps_bucket())
+                {
+                    info!("{}", accuracy);
+                }
+            }
+
+            let throughput = specs.min_rps().map(|min_rps| {
+                let throughput = run_summary.stats_processor.check_min_rps(min_rps);
+                if let Ok(achieved_rps) = &throughput {
+                    info!(
+                        "Overall throughput of {:.1} requests/s (min {})",
+                        achieved_rps, min_rps
+                    );
+                }
+                throughput
+            });
 
             let report_summary = burl_reporter::ReportFactory::new(
                 run_summary.start_time,
@@ -80,8 +394,66 @@ async fn main() -> Result<(), Box<dyn Error>> {
             if let Err(err) = report_summary.create_report() {
                 error!("Report creation failed: {}", err);
             }
+
+            // NOTE: a run without any valid samples (or below the `min_rps`) fails, e.g. in CI
+            if let Err(err) = stats.and(throughput.transpose()) {
+                error!("The benchmark failed: {}", err);
+                std::process::exit(1);
+            }
         }
     }
     trace!("Finished");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    #[test]
+    fn json_logs() {
+        let args = CliArgs::try_parse_from(["burl-cli", "--log-format", "json", "get"]).unwrap();
+        assert_eq!(args.log_format, LogFormat::Json);
+        let args = CliArgs::try_parse_from(["burl-cli", "get"]).unwrap();
+        assert_eq!(args.log_format, LogFormat::Text);
+
+        let fields: &[(&str, &dyn log::kv::ToValue)] = &[("run", &"release-a"), ("n_failed", &3)];
+        let line = json_line(
+            &Record::builder()
+                .args(format_args!("Finished the benchmark"))
+                .level(Level::Info)
+                .target("burl_cli")
+                .key_values(&fields)
+                .build(),
+            "2024-01-01T00:00:00Z",
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(json["level"], "INFO");
+        assert_eq!(json["message"], "Finished the benchmark");
+        assert_eq!(json["timestamp"], "2024-01-01T00:00:00Z");
+        assert_eq!(json["run"], "release-a");
+        assert_eq!(json["n_failed"], 3);
+    }
+
+    #[test]
+    fn runtime_threads() {
+        let args =
+            CliArgs::try_parse_from(["burl-cli", "--runtime-threads", "3", "from-toml"]).unwrap();
+        let configured = runtime(args.runtime_threads).unwrap();
+        assert_eq!(configured.metrics().num_workers(), 3);
+
+        let args = CliArgs::try_parse_from(["burl-cli", "from-toml"]).unwrap();
+        assert!(args.runtime_threads.is_none());
+        assert!(
+            runtime(args.runtime_threads)
+                .unwrap()
+                .metrics()
+                .num_workers()
+                > 0
+        );
+
+        assert!(CliArgs::try_parse_from(["burl-cli", "--runtime-threads", "0", "get"]).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+use crate::stats_helpers::StatisticalTester;
+use burl::stats::{percentile, PrimaryMetric, StatsSummary, TestOutcome};
+use serde::Serialize;
+use std::fmt;
+
+/// The min number of historical runs to derive a band from.
+const MIN_HISTORY_RUNS: usize = 3;
+
+/// The change of a metric of the current run relative to the baseline.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricDelta {
+    pub current: f64,
+    pub baseline: f64,
+    /// `current - baseline`
+    pub absolute: f64,
+    /// The change relative to the baseline in %, or `None` if the baseline is zero.
+    pub percentage: Option<f64>,
+}
+
+impl MetricDelta {
+    pub fn new(current: f64, baseline: f64) -> Self {
+        let absolute = current - baseline;
+        let percentage = (baseline != 0.0).then(|| 100.0 * absolute / baseline);
+        Self {
+            current,
+            baseline,
+            absolute,
+            percentage,
+        }
+    }
+}
+
+/// The comparison of the current run against a baseline, for programmatic consumers.
+#[derive(Debug, Serialize)]
+pub struct ComparisonReport {
+    pub mean: MetricDelta,
+    pub median: MetricDelta,
+    pub p95: MetricDelta,
+    pub p99: MetricDelta,
+    /// Only available if both runs measured their requests per second.
+    pub rps: Option<MetricDelta>,
+    /// The statistic of the headline verdict, see `primary_metric`.
+    pub primary_metric: PrimaryMetric,
+    pub primary_outcome: Option<TestOutcome>,
+    pub analytic_outcome: Option<TestOutcome>,
+    pub permutation_outcome: Option<TestOutcome>,
+}
+
+impl ComparisonReport {
+    /// Compares the summaries; returns `None` if they differ in their time scales.
+    pub fn compare(
+        current_stats: &StatsSummary,
+        baseline_stats: &StatsSummary,
+        n_bootstrap_samples: usize,
+        alpha: f64,
+    ) -> Option<Self> {
+        let tester = StatisticalTester::try_new(current_stats, baseline_stats)?;
+        Some(Self::from_tester(&tester, n_bootstrap_samples, alpha))
+    }
+
+    pub(crate) fn from_tester(
+        tester: &StatisticalTester,
+        n_bootstrap_samples: usize,
+        alpha: f64,
+    ) -> Self {
+        let (current_stats, baseline_stats) = (tester.current_stats, tester.baseline_stats);
+        let levels = [0.95, 0.99];
+        let current_percentiles = current_stats.percentiles(&levels);
+        let baseline_percentiles = baseline_stats.percentiles(&levels);
+        let percentile_delta =
+            |idx: usize| MetricDelta::new(current_percentiles[idx].1, baseline_percentiles[idx].1);
+
+        Self {
+            mean: MetricDelta::new(current_stats.mean, baseline_stats.mean),
+            median: MetricDelta::new(current_stats.median, baseline_stats.median),
+            p95: percentile_delta(0),
+            p99: percentile_delta(1),
+            rps: current_stats
+                .mean_rps
+                .zip(baseline_stats.mean_rps)
+                .map(|(current, baseline)| MetricDelta::new(current, baseline)),
+            primary_metric: tester.primary_metric,
+            primary_outcome: tester.primary_test(n_bootstrap_samples, alpha),
+            analytic_outcome: tester.analytic_test(alpha),
+            permutation_outcome: tester.performance_test(n_bootstrap_samples, alpha),
+        }
+    }
+}
+
+/// The mean of the current run against the band of the means of historical runs,
+/// which is less noisy than a comparison against a single baseline.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistoryBand {
+    pub n_runs: usize,
+    pub current_mean: f64,
+    /// The median of the means of the historical runs.
+    pub median: f64,
+    /// The interquartile range of the means of the historical runs.
+    pub iqr: f64,
+    /// `Q3 + 1.5 * IQR` of the means of the historical runs.
+    pub upper_fence: f64,
+    /// The current mean is above the upper fence.
+    pub regressed: bool,
+}
+
+impl HistoryBand {
+    /// The band of the historical runs in the time scale of the current one; `None` if there are
+    /// fewer than 3 of them.
+    pub fn new(current_stats: &StatsSummary, history: &[StatsSummary]) -> Option<Self> {
+        let mut means: Vec<f64> = history
+            .iter()
+            .filter(|stats| stats.scale == current_stats.scale)
+            .map(|stats| stats.mean)
+            .collect();
+        if means.len() < MIN_HISTORY_RUNS {
+            return None;
+        }
+        means.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let n = means.len() as f64;
+        let (q1, q3) = (percentile(&means, 0.25, n), percentile(&means, 0.75, n));
+        let upper_fence = q3 + 1.5 * (q3 - q1);
+        Some(Self {
+            n_runs: means.len(),
+            current_mean: current_stats.mean,
+            median: percentile(&means, 0.5, n),
+            iqr: q3 - q1,
+            upper_fence,
+            regressed: current_stats.mean > upper_fence,
+        })
+    }
+}
+
+impl fmt::Display for HistoryBand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Mean of {} vs. the last {} runs (median {}, IQR {}, upper fence {}): {}",
+            self.current_mean,
+            self.n_runs,
+            self.median,
+            self.iqr,
+            self.upper_fence,
+            match self.regressed {
+                true => "regressed",
+                false => "within the band",
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use burl::DurationScale;
+    use std::collections::HashMap;
+
+    fn summary(durations: Vec<f64>) -> StatsSummary {
+        StatsSummary::calculate(
+            DurationScale::Milli,
+            0,
+            0,
+            durations,
+            HashMap::new(),
+            HashMap::new(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn percentage_deltas() {
+        let baseline = summary((1..=100).map(|d| d as f64).collect());
+        let current = summary((1..=100).map(|d| 1.1 * d as f64).collect());
+
+        let report = ComparisonReport::compare(&current, &baseline, 100, 0.05).unwrap();
+
+        assert!((report.mean.absolute - 5.05).abs() < 1e-9);
+        for delta in [&report.mean, &report.median, &report.p95, &report.p99] {
+            assert!((delta.percentage.unwrap() - 10.0).abs() < 1e-9);
+        }
+        // the rps are derived from the mean duration
+        let rps = report.rps.unwrap();
+        assert!((rps.percentage.unwrap() - (100.0 / 1.1 - 100.0)).abs() < 1e-9);
+        assert!(report.analytic_outcome.is_some());
+
+        assert_eq!(MetricDelta::new(1.0, 0.0).percentage, None);
+        assert_eq!(MetricDelta::new(45.0, 50.0).percentage, Some(-10.0));
+    }
+
+    #[test]
+    fn different_scales() {
+        let baseline = summary(vec![1.0, 2.0]);
+        let mut current = summary(vec![1.0, 2.0]);
+        current.scale = DurationScale::Micro;
+
+        assert!(ComparisonReport::compare(&current, &baseline, 10, 0.05).is_none());
+    }
+}
@@ -0,0 +1,148 @@
+use burl::stats::{
+    confidence_interval, percentile, standard_deviation, sum, BootstrapSampler, StatsSummary,
+};
+use burl::DurationScale;
+use serde::Serialize;
+
+/// The scale factor of the median absolute deviation as an estimate of the std deviation,
+/// as used by criterion.
+const MAD_SCALE: f64 = 1.4826;
+
+#[derive(Serialize, Debug)]
+pub(crate) struct ConfidenceInterval {
+    confidence_level: f64,
+    lower_bound: f64,
+    upper_bound: f64,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct Estimate {
+    confidence_interval: ConfidenceInterval,
+    point_estimate: f64,
+    standard_error: f64,
+}
+
+/// The shape of the `estimates.json` of `cargo criterion`, with all values in nanoseconds.
+/// The slope is only estimated by criterion for linear sampling, hence always `null`.
+#[derive(Serialize, Debug)]
+pub(crate) struct Estimates {
+    mean: Estimate,
+    median: Estimate,
+    median_abs_dev: Estimate,
+    slope: Option<Estimate>,
+    std_dev: Estimate,
+}
+
+fn sort(samples: &mut [f64]) {
+    samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+}
+
+fn mean(samples: &mut [f64]) -> f64 {
+    sum(samples) / samples.len() as f64
+}
+
+fn median(samples: &mut [f64]) -> f64 {
+    sort(samples);
+    percentile(samples, 0.5, samples.len() as f64)
+}
+
+fn median_abs_dev(samples: &mut [f64]) -> f64 {
+    let median = median(samples);
+    let mut deviations: Vec<f64> = samples.iter().map(|s| (s - median).abs()).collect();
+    self::median(&mut deviations) * MAD_SCALE
+}
+
+fn std_dev(samples: &mut [f64]) -> f64 {
+    let mean = mean(samples);
+    standard_deviation(samples, mean).unwrap_or(0.0)
+}
+
+/// The estimate of the statistic with the confidence interval and standard error of its
+/// bootstrap distribution (over `n_samples` resamples of size `n_draws`).
+fn estimate(
+    durations: &[f64],
+    statistic: fn(&mut [f64]) -> f64,
+    n_draws: usize,
+    n_samples: usize,
+    alpha: f64,
+) -> Estimate {
+    let point_estimate = statistic(&mut durations.to_vec());
+    let distribution =
+        BootstrapSampler::new(durations).sample_statistics(n_draws, n_samples, statistic);
+    let (lower_bound, upper_bound) =
+        confidence_interval(&distribution, alpha).unwrap_or((point_estimate, point_estimate));
+    let standard_error = standard_deviation(&distribution, sum(&distribution) / n_samples as f64);
+
+    Estimate {
+        confidence_interval: ConfidenceInterval {
+            confidence_level: 1.0 - alpha,
+            lower_bound,
+            upper_bound,
+        },
+        point_estimate,
+        standard_error: standard_error.unwrap_or(0.0),
+    }
+}
+
+/// Maps the stats onto the estimates of criterion, e.g. for tooling which ingests its output.
+pub(crate) fn criterion_estimates(
+    stats: &StatsSummary,
+    n_draws: usize,
+    n_samples: usize,
+    alpha: f64,
+) -> Estimates {
+    let to_nanos = DurationScale::Nano.factor(&stats.scale);
+    let durations: Vec<f64> = stats.durations.iter().map(|d| d * to_nanos).collect();
+    let estimate = |statistic| estimate(&durations, statistic, n_draws, n_samples, alpha);
+
+    Estimates {
+        mean: estimate(mean),
+        median: estimate(median),
+        median_abs_dev: estimate(median_abs_dev),
+        slope: None,
+        std_dev: estimate(std_dev),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn criterion_estimates() {
+        let durations = (0..200).map(|idx| (idx % 13) as f64 + 10.0).collect();
+        let stats = StatsSummary::calculate(
+            DurationScale::Milli,
+            0,
+            0,
+            durations,
+            HashMap::new(),
+            HashMap::new(),
+        )
+        .unwrap();
+
+        let estimates = super::criterion_estimates(&stats, 100, 200, 0.05);
+        let json = serde_json::to_value(&estimates).unwrap();
+
+        let mut fields: Vec<&String> = json.as_object().unwrap().keys().collect();
+        fields.sort();
+        assert_eq!(
+            fields,
+            vec!["mean", "median", "median_abs_dev", "slope", "std_dev"]
+        );
+        assert!(json["slope"].is_null());
+        for field in ["mean", "median", "median_abs_dev", "std_dev"] {
+            let estimate = &json[field];
+            assert!(estimate["point_estimate"].is_f64());
+            assert!(estimate["standard_error"].is_f64());
+            let interval = &estimate["confidence_interval"];
+            assert_eq!(interval["confidence_level"], 0.95);
+            assert!(interval["lower_bound"].as_f64() <= estimate["point_estimate"].as_f64());
+            assert!(interval["upper_bound"].as_f64() >= estimate["point_estimate"].as_f64());
+        }
+        // in nanoseconds
+        let mean = json["mean"]["point_estimate"].as_f64().unwrap();
+        assert!((mean - stats.mean * 1e6).abs() < 1e-3);
+    }
+}
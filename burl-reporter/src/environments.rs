@@ -0,0 +1,384 @@
+use crate::comparison::ComparisonReport;
+use crate::html_report::{SummaryComponent, WarningsComponent};
+use crate::plots::{BoxPlotComponent, HistogramComponent, QQPlotComponent, TimeSeriesComponent};
+use crate::report::{
+    setup_components, setup_report_structure, write_index, write_or_update, FORMAT,
+};
+use crate::stats_helpers::StatisticalTester;
+use crate::ComponentWriter;
+use burl::stats::{StatsProcessor, StatsSummary};
+use burl::{BenchClientConfig, BurlError, BurlResult, Warnings};
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::Serialize;
+use std::path::Path;
+
+/// The run of one of the environments benchmarked side by side, e.g. staging or prod.
+pub struct EnvironmentRun<'a> {
+    pub config: &'a BenchClientConfig,
+    pub stats_processor: StatsProcessor,
+}
+
+#[derive(Serialize)]
+struct EnvironmentStats<'a> {
+    label: &'a str,
+    url: &'a str,
+    stats: &'a StatsSummary,
+}
+
+/// The stats of both environments and the verdict, e.g. for dashboards.
+#[derive(Serialize)]
+struct EnvironmentsExport<'a> {
+    start_time: String,
+    end_time: String,
+    baseline: EnvironmentStats<'a>,
+    current: EnvironmentStats<'a>,
+    verdict: Option<&'a ComparisonReport>,
+    /// The warnings of both runs and of the comparison.
+    warnings: Vec<String>,
+}
+
+/// The run labels of the environments, made distinct if they coincide.
+fn labels(baseline: &BenchClientConfig, current: &BenchClientConfig) -> (String, String) {
+    let (baseline, current) = (baseline.run_label(), current.run_label());
+    match baseline == current {
+        true => (
+            format!("{} (baseline)", baseline),
+            format!("{} (current)", current),
+        ),
+        false => (baseline, current),
+    }
+}
+
+/// A single report of two environments benchmarked at the same time, e.g. staging and prod of
+/// the same endpoint, with overlaid plots and the verdict of the current against the baseline one.
+/// The statistical settings (e.g. the `alpha`) are the ones of the current environment.
+pub struct EnvironmentsReport<'a> {
+    baseline: EnvironmentRun<'a>,
+    current: EnvironmentRun<'a>,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    /// The warnings of both runs, by their label, and of the comparison, see `report_warnings`.
+    warnings: Warnings,
+}
+
+impl<'a> EnvironmentsReport<'a> {
+    pub fn new(
+        baseline: EnvironmentRun<'a>,
+        current: EnvironmentRun<'a>,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Self {
+        let (baseline_label, current_label) = labels(baseline.config, current.config);
+        let run_warnings = [(baseline_label, &baseline), (current_label, &current)]
+            .iter()
+            .flat_map(|(label, run)| {
+                run.stats_processor
+                    .warnings()
+                    .iter()
+                    .map(move |warning| format!("{}: {}", label, warning))
+            })
+            .collect();
+        Self {
+            baseline,
+            current,
+            start_time,
+            end_time,
+            warnings: Warnings::new(run_warnings),
+        }
+    }
+
+    /// The warnings of the runs and the ones logged so far, in their order.
+    pub fn warnings(&self) -> Vec<String> {
+        self.warnings.to_vec()
+    }
+
+    fn labels(&self) -> (String, String) {
+        labels(self.baseline.config, self.current.config)
+    }
+
+    fn stats(run: &EnvironmentRun) -> BurlResult<StatsSummary> {
+        run.stats_processor
+            .stats_summary()
+            .ok_or(BurlError::NoValidSamples {
+                n_failed: run.stats_processor.n_failed(),
+            })
+    }
+
+    /// Logs the verdict of the current against the baseline environment.
+    fn compare(
+        &self,
+        current_stats: &StatsSummary,
+        baseline_stats: &StatsSummary,
+    ) -> Option<ComparisonReport> {
+        let config = self.current.config;
+        let Some(tester) = StatisticalTester::try_new(current_stats, baseline_stats) else {
+            self.warnings.warn(
+                "The environments cannot be compared due to different time scales".to_string(),
+            );
+            return None;
+        };
+        let tester = tester
+            .with_min_samples(config.min_samples())
+            .with_primary_metric(config.primary_metric());
+        if let Some(warning) = tester.sample_size_warning(config.max_sample_ratio()) {
+            self.warnings.warn(warning);
+            if config.refuse_unbalanced_comparison() {
+                return None;
+            }
+        }
+        let (n_bootstrap_samples, alpha) = (config.n_bootstrap_samples(), config.alpha());
+
+        let (baseline_label, current_label) = self.labels();
+        info!(
+            "Performance of {} (vs. {}, alpha = {}):\n{}",
+            current_label,
+            baseline_label,
+            alpha,
+            tester.verdict(n_bootstrap_samples, alpha)
+        );
+        Some(ComparisonReport::from_tester(
+            &tester,
+            n_bootstrap_samples,
+            alpha,
+        ))
+    }
+
+    fn box_plot(
+        &self,
+        current_stats: &StatsSummary,
+        baseline_stats: &StatsSummary,
+    ) -> BoxPlotComponent {
+        let (baseline_label, current_label) = self.labels();
+        let mut box_plot = BoxPlotComponent::new();
+        box_plot.add_runs(&[
+            (baseline_label, baseline_stats.durations.clone()),
+            (current_label, current_stats.durations.clone()),
+        ]);
+        box_plot
+    }
+
+    fn histogram(
+        &self,
+        current_stats: &StatsSummary,
+        baseline_stats: &StatsSummary,
+    ) -> HistogramComponent {
+        let (baseline_label, current_label) = self.labels();
+        let mut histogram = HistogramComponent::new();
+        histogram.set_bins(
+            current_stats.min.min(baseline_stats.min),
+            current_stats.max.max(baseline_stats.max),
+        );
+        histogram.add_runs(&[
+            (baseline_label, baseline_stats.durations.clone()),
+            (current_label, current_stats.durations.clone()),
+        ]);
+        histogram
+    }
+
+    /// The measured (not discarded) samples of all threads of the run, ordered by their start.
+    fn time_series(run: &EnvironmentRun) -> Vec<(f64, f64)> {
+        let mut time_series: Vec<(f64, f64)> = run
+            .stats_processor
+            .sample_results_by_thread()
+            .values()
+            .flatten()
+            .filter(|sr| !sr.discarded)
+            .map(|sr| sr.as_timeseries_point())
+            .collect();
+        time_series.sort_by(|a, b| a.0.total_cmp(&b.0));
+        time_series
+    }
+
+    fn time_series_plot(&self) -> TimeSeriesComponent {
+        let (baseline_label, current_label) = self.labels();
+        let mut time_series_plot = TimeSeriesComponent::new();
+        time_series_plot.add_runs(&[
+            (baseline_label, Self::time_series(&self.baseline)),
+            (current_label, Self::time_series(&self.current)),
+        ]);
+        time_series_plot
+    }
+
+    fn qq_plot(
+        &self,
+        current_stats: &StatsSummary,
+        baseline_stats: &StatsSummary,
+    ) -> QQPlotComponent {
+        let n_points = self.current.config.n_qq_points();
+        let mut qq_plot = QQPlotComponent::new();
+        qq_plot.add_current(&current_stats.normal_qq_curve(n_points));
+        qq_plot.add_baseline(&baseline_stats.normal_qq_curve(n_points));
+        qq_plot.add_reference_line();
+        qq_plot
+    }
+
+    /// Creates the report in the `report_directory`, with the stats of both environments and
+    /// the verdict in `environments.json`; the verdict is `None` if their time scales differ.
+    pub fn create_report(&self, report_directory: &Path) -> BurlResult<Option<ComparisonReport>> {
+        let baseline_stats = Self::stats(&self.baseline)?;
+        let current_stats = Self::stats(&self.current)?;
+        let config = self.current.config;
+
+        let data_dir = setup_report_structure(report_directory)?;
+        let verdict = self.compare(&current_stats, &baseline_stats);
+        let (baseline_label, current_label) = self.labels();
+        let export = EnvironmentsExport {
+            start_time: format!("{}", self.start_time.format(FORMAT)),
+            end_time: format!("{}", self.end_time.format(FORMAT)),
+            baseline: EnvironmentStats {
+                label: &baseline_label,
+                url: &self.baseline.config.url,
+                stats: &baseline_stats,
+            },
+            current: EnvironmentStats {
+                label: &current_label,
+                url: &self.current.config.url,
+                stats: &current_stats,
+            },
+            verdict: verdict.as_ref(),
+            warnings: self.warnings(),
+        };
+        write_or_update(&export, data_dir.join("environments.json"))?;
+        if config.report_warnings() {
+            write_or_update(&self.warnings(), data_dir.join("warnings.json"))?;
+        }
+
+        let components_dir = setup_components(report_directory)?;
+        let mut summary = SummaryComponent::new(config.display_precision())
+            .with_primary_metric(config.primary_metric());
+        summary.add_current(&current_stats);
+        summary.add_baseline(baseline_stats.clone());
+        summary.compile(
+            config.alpha(),
+            config.n_bootstrap_samples(),
+            config.min_samples(),
+        );
+        summary.write(&components_dir.join("summary.html"))?;
+        self.box_plot(&current_stats, &baseline_stats)
+            .write(&components_dir.join("durations_distribution.html"))?;
+        self.histogram(&current_stats, &baseline_stats)
+            .write(&components_dir.join("durations_histogram.html"))?;
+        self.time_series_plot()
+            .write(&components_dir.join("durations_timeseries.html"))?;
+        self.qq_plot(&current_stats, &baseline_stats)
+            .write(&components_dir.join("qq_plot.html"))?;
+        if config.report_warnings() {
+            WarningsComponent::new(&self.warnings())
+                .write(&components_dir.join("warnings.html"))?;
+        }
+        write_index(report_directory)?;
+
+        Ok(verdict)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use burl::stats::TestOutcome;
+    use burl::BenchClient;
+    use serde_json::json;
+    use std::time::Duration;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn environment(label: &str, delay_millis: u64) -> (MockServer, BenchClientConfig) {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(delay_millis)))
+            .mount(&server)
+            .await;
+        let config: BenchClientConfig = serde_json::from_value(json!({
+            "url": server.uri(),
+            "method": "Get",
+            "label": label,
+            "n_runs": 30,
+        }))
+        .unwrap();
+        (server, config)
+    }
+
+    /// The names of the traces of the plot.
+    fn trace_names(plot: &plotly::Plot) -> Vec<String> {
+        let json: serde_json::Value = serde_json::from_str(&plot.to_json()).unwrap();
+        json["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|trace| trace["name"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn environments_side_by_side() {
+        let (_prod, prod_config) = environment("prod", 5).await;
+        let (_staging, staging_config) = environment("staging", 40).await;
+
+        let start_time = Utc::now();
+        let (prod_client, staging_client) = (
+            BenchClient::init(&prod_config).unwrap(),
+            BenchClient::init(&staging_config).unwrap(),
+        );
+        let (prod_run, staging_run) = tokio::join!(prod_client.run(), staging_client.run());
+        let report = EnvironmentsReport::new(
+            EnvironmentRun {
+                config: &prod_config,
+                stats_processor: prod_run
+                    .unwrap()
+                    .stats_processor
+                    .with_warnings(vec!["3 of 4 prewarm requests failed".to_string()]),
+            },
+            EnvironmentRun {
+                config: &staging_config,
+                stats_processor: staging_run.unwrap().stats_processor,
+            },
+            start_time,
+            Utc::now(),
+        );
+
+        let (prod_stats, staging_stats) = (
+            EnvironmentsReport::stats(&report.baseline).unwrap(),
+            EnvironmentsReport::stats(&report.current).unwrap(),
+        );
+        let overlaid = ["prod", "staging"];
+        assert_eq!(
+            trace_names(&report.box_plot(&staging_stats, &prod_stats)),
+            overlaid
+        );
+        assert_eq!(
+            trace_names(&report.histogram(&staging_stats, &prod_stats)),
+            overlaid
+        );
+        assert_eq!(trace_names(&report.time_series_plot()), overlaid);
+
+        let dir = tempfile::tempdir().unwrap();
+        let verdict = report.create_report(dir.path()).unwrap().unwrap();
+        assert!(matches!(
+            verdict.permutation_outcome,
+            Some(TestOutcome::Regressed { .. })
+        ));
+        assert!(verdict.mean.absolute > 0.0);
+
+        let export: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(dir.path().join("data").join("environments.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(export["baseline"]["label"], "prod");
+        assert_eq!(export["current"]["label"], "staging");
+        // the warnings of the runs by their label
+        assert_eq!(
+            export["warnings"][0],
+            "prod: 3 of 4 prewarm requests failed"
+        );
+        let warnings = std::fs::read_to_string(dir.path().join("data").join("warnings.json"));
+        assert!(warnings
+            .unwrap()
+            .contains("prod: 3 of 4 prewarm requests failed"));
+        assert!(dir
+            .path()
+            .join("components")
+            .join("durations_distribution.html")
+            .exists());
+    }
+}
@@ -1,16 +1,27 @@
-use crate::{stats_helpers::StatisticalTester, ComponentWriter};
-use burl::stats::{StatsSummary, TestOutcome};
+use crate::{report::escape_html, stats_helpers::StatisticalTester, ComponentWriter};
+use burl::stats::{
+    format_bytes, format_delta, format_value, percentile, PrimaryMetric, StatsSummary, TestOutcome,
+};
 use std::{fs, path::Path};
 
+/// The 95th percentile of the durations.
+fn p95(stats: &StatsSummary) -> f64 {
+    percentile(&stats.durations, 0.95, stats.durations.len() as f64)
+}
+
+/// The throughput in human-readable units, e.g. `1.00 MiB/s`.
+fn format_throughput(stats: &StatsSummary, precision: Option<usize>) -> String {
+    match stats.bytes_per_sec() {
+        Some(throughput) => format!("{}/s", format_bytes(throughput, precision)),
+        None => "-".to_string(),
+    }
+}
+
 fn test_outcome_html(test_outcome: &TestOutcome) -> String {
     match test_outcome {
-        TestOutcome::Improved { p_value } => {
-            format!("<font color='green'>improved (p-value {})</font>", p_value)
-        }
-        TestOutcome::Regressed { p_value } => {
-            format!("<font color='red'>regressed (p-value {})</font>", p_value)
-        }
-        TestOutcome::Inconclusive => "inconclusive (no significant change)".to_string(),
+        TestOutcome::Improved { .. } => format!("<font color='green'>{}</font>", test_outcome),
+        TestOutcome::Regressed { .. } => format!("<font color='red'>{}</font>", test_outcome),
+        TestOutcome::Inconclusive => test_outcome.to_string(),
     }
 }
 
@@ -19,6 +30,10 @@ pub struct SummaryComponent<'a> {
     html: String,
     current_stats: Option<&'a StatsSummary>,
     baseline_stats: Option<StatsSummary>,
+    /// The number of decimals of the displayed values.
+    precision: Option<usize>,
+    /// The statistic of the headline verdict against the baseline.
+    primary_metric: PrimaryMetric,
 }
 
 impl<'a> ComponentWriter for SummaryComponent<'a> {
@@ -26,39 +41,78 @@ impl<'a> ComponentWriter for SummaryComponent<'a> {
         fs::write(file, &self.html)?;
         Ok(())
     }
+
+    /// The summary is a page of its own, hence embedded as an iframe to keep its styles apart.
+    fn inline_html(&self, id: &'static str) -> String {
+        let srcdoc = self.html.replace('&', "&amp;").replace('"', "&quot;");
+        format!(
+            r#"<iframe id="{}" srcdoc="{}" seamless width="600" height="600" frameBorder="0"></iframe>"#,
+            id, srcdoc
+        )
+    }
 }
 
 impl<'a> SummaryComponent<'a> {
-    pub fn new() -> Self {
+    pub fn new(precision: Option<usize>) -> Self {
         Self {
             html: include_str!("./templates/summary_template.html").to_string(),
             current_stats: None,
             baseline_stats: None,
+            precision,
+            primary_metric: PrimaryMetric::default(),
         }
     }
 
-    fn update_current(&mut self, stats: &StatsSummary) {
+    pub fn with_primary_metric(mut self, primary_metric: PrimaryMetric) -> Self {
+        self.primary_metric = primary_metric;
+        self
+    }
+
+    /// Fills in the values of the current run, each followed by its change relative to
+    /// the baseline if given (in the same scale).
+    fn update_current(&mut self, stats: &StatsSummary, baseline: Option<&StatsSummary>) {
+        let baseline = baseline.filter(|baseline| baseline.scale == stats.scale);
         self.html = self
             .html
             .replace("$SCALE$", stats.scale.clone().to_string().as_str());
 
-        let mut replace_key_value =
-            |(key, v): (&str, f64)| self.html = self.html.replace(key, v.to_string().as_str());
-
         // TODO: add JS to summary template instead
-        replace_key_value(("$TOTAL_BYTES$", stats.total_bytes as f64));
-        replace_key_value(("$N_OK$", stats.n_ok as f64));
-        replace_key_value(("$N_FAILED$", stats.n_errors as f64));
-        replace_key_value(("$N_THREADS$", stats.stats_by_thread.len() as f64));
-        replace_key_value(("$TOTAL_DURATION$", stats.total_duration));
-        replace_key_value(("$MEAN$", stats.mean));
-        replace_key_value(("$RPS$", stats.mean_rps.unwrap_or(f64::NAN)));
-        replace_key_value(("$STDEV$", stats.std.unwrap_or(f64::NAN)));
-        replace_key_value(("$MIN$", stats.min));
-        replace_key_value(("$MAX$", stats.max));
-        replace_key_value(("$Q1$", stats.quartile_fst));
-        replace_key_value(("$Q2$", stats.median));
-        replace_key_value(("$Q3$", stats.quartile_trd));
+        self.html = self
+            .html
+            .replace(
+                "$TOTAL_BYTES$",
+                &format_bytes(stats.total_bytes as f64, self.precision),
+            )
+            .replace("$THROUGHPUT$", &format_throughput(stats, self.precision))
+            .replace("$N_OK$", &stats.n_ok.to_string())
+            .replace("$N_FAILED$", &stats.n_errors.to_string())
+            .replace("$N_THREADS$", &stats.stats_by_thread.len().to_string());
+
+        let precision = self.precision;
+        let mut replace_key_value =
+            |(key, v, baseline_value): (&str, f64, fn(&StatsSummary) -> f64)| {
+                let delta = baseline
+                    .map(|baseline| format_delta(v, baseline_value(baseline)))
+                    .unwrap_or_default();
+                let value = format!("{}{}", format_value(v, precision), delta);
+                self.html = self.html.replace(key, value.as_str())
+            };
+        replace_key_value(("$TOTAL_DURATION$", stats.total_duration, |bl| {
+            bl.total_duration
+        }));
+        replace_key_value(("$MEAN$", stats.mean, |bl| bl.mean));
+        replace_key_value(("$RPS$", stats.mean_rps.unwrap_or(f64::NAN), |bl| {
+            bl.mean_rps.unwrap_or(f64::NAN)
+        }));
+        replace_key_value(("$STDEV$", stats.std.unwrap_or(f64::NAN), |bl| {
+            bl.std.unwrap_or(f64::NAN)
+        }));
+        replace_key_value(("$MIN$", stats.min, |bl| bl.min));
+        replace_key_value(("$MAX$", stats.max, |bl| bl.max));
+        replace_key_value(("$Q1$", stats.quartile_fst, |bl| bl.quartile_fst));
+        replace_key_value(("$Q2$", stats.median, |bl| bl.median));
+        replace_key_value(("$Q3$", stats.quartile_trd, |bl| bl.quartile_trd));
+        replace_key_value(("$P95$", p95(stats), p95));
     }
 
     fn update_baseline(
@@ -70,10 +124,20 @@ impl<'a> SummaryComponent<'a> {
     ) {
         self.html = self
             .html
-            .replace("$SCALE_BASELINE$", stats.scale.clone().to_string().as_str());
+            .replace("$SCALE_BASELINE$", stats.scale.clone().to_string().as_str())
+            .replace("$ALPHA$", &alpha.to_string())
+            .replace("$PRIMARY_METRIC$", &self.primary_metric.to_string());
 
         match stats_tester {
             Some(tester) => {
+                let primary_outcome_disp = match tester.primary_test(n_bootstrap_samples, alpha) {
+                    Some(outcome) => test_outcome_html(&outcome),
+                    None => "could not be determined".to_string(),
+                };
+                self.html = self
+                    .html
+                    .replace("$PRIMARY_OUTCOME$", primary_outcome_disp.as_str());
+
                 let performance_outcome_disp = match tester.analytic_test(alpha) {
                     Some(outcome) => test_outcome_html(&outcome),
                     None => "could not be determined".to_string(),
@@ -94,6 +158,10 @@ impl<'a> SummaryComponent<'a> {
             }
 
             None => {
+                self.html = self.html.replace(
+                    "$PRIMARY_OUTCOME$",
+                    "cannot be compared due to different time scales",
+                );
                 self.html = self.html.replace(
                     "$PERFORMANCE_OUTCOME$",
                     "cannot be compared due to different time scales",
@@ -105,14 +173,28 @@ impl<'a> SummaryComponent<'a> {
             }
         }
 
-        let mut replace_key_value =
-            |(key, v): (&str, f64)| self.html = self.html.replace(key, v.to_string().as_str());
-
         // TODO: add JS to summary template instead
-        replace_key_value(("$TOTAL_BYTES_BASELINE$", stats.total_bytes as f64));
-        replace_key_value(("$N_OK_BASELINE$", stats.n_ok as f64));
-        replace_key_value(("$N_FAILED_BASELINE$", stats.n_errors as f64));
-        replace_key_value(("$N_THREADS_BASELINE$", stats.stats_by_thread.len() as f64));
+        self.html = self
+            .html
+            .replace(
+                "$TOTAL_BYTES_BASELINE$",
+                &format_bytes(stats.total_bytes as f64, self.precision),
+            )
+            .replace(
+                "$THROUGHPUT_BASELINE$",
+                &format_throughput(&stats, self.precision),
+            )
+            .replace("$N_OK_BASELINE$", &stats.n_ok.to_string())
+            .replace("$N_FAILED_BASELINE$", &stats.n_errors.to_string())
+            .replace(
+                "$N_THREADS_BASELINE$",
+                &stats.stats_by_thread.len().to_string(),
+            );
+
+        let precision = self.precision;
+        let mut replace_key_value = |(key, v): (&str, f64)| {
+            self.html = self.html.replace(key, format_value(v, precision).as_str())
+        };
         replace_key_value(("$TOTAL_DURATION_BASELINE$", stats.total_duration));
         replace_key_value(("$MEAN_BASELINE$", stats.mean));
         replace_key_value(("$RPS_BASELINE$", stats.mean_rps.unwrap_or(f64::NAN)));
@@ -122,6 +204,7 @@ impl<'a> SummaryComponent<'a> {
         replace_key_value(("$Q1_BASELINE$", stats.quartile_fst));
         replace_key_value(("$Q2_BASELINE$", stats.median));
         replace_key_value(("$Q3_BASELINE$", stats.quartile_trd));
+        replace_key_value(("$P95_BASELINE$", p95(&stats)));
     }
 
     pub fn add_current(&mut self, stats: &'a StatsSummary) {
@@ -134,23 +217,68 @@ impl<'a> SummaryComponent<'a> {
         self.baseline_stats = Some(stats);
     }
 
-    pub fn compile(&mut self, _alpha: f64, _n_bootstrap_samples: usize) {
+    pub fn compile(&mut self, alpha: f64, n_bootstrap_samples: usize, min_samples: usize) {
         if let Some(stats) = self.current_stats {
-            self.update_current(stats);
-
-            // if let Some(baseline_stats) = &self.baseline_stats {
-            //     let stats_tester = StatisticalTester::try_new(stats, &baseline_stats);
-            //     self.update_baseline(
-            //         baseline_stats.clone(),
-            //         stats_tester,
-            //         alpha,
-            //         n_bootstrap_samples,
-            //     );
-            // }
+            let baseline_stats = self.baseline_stats.take();
+            self.update_current(stats, baseline_stats.as_ref());
+
+            if let Some(baseline_stats) = baseline_stats {
+                let stats_tester =
+                    StatisticalTester::try_new(stats, &baseline_stats).map(|tester| {
+                        tester
+                            .with_min_samples(min_samples)
+                            .with_primary_metric(self.primary_metric)
+                    });
+                self.update_baseline(
+                    baseline_stats.clone(),
+                    stats_tester,
+                    alpha,
+                    n_bootstrap_samples,
+                );
+                self.baseline_stats = Some(baseline_stats);
+            }
         }
     }
 }
 
+/// The list of the warnings of the run, see `report_warnings`.
+pub struct WarningsComponent {
+    items: String,
+}
+
+impl ComponentWriter for WarningsComponent {
+    fn write(&self, file: &Path) -> burl::BurlResult<()> {
+        let html = format!(
+            "<!DOCTYPE html>\n<html>\n\n<head>\n  <meta charset=\"utf-8\" />\n  \
+             <style type=\"text/css\">body {{ font: 14px Tahoma; }}</style>\n</head>\n\n\
+             <body>\n{}\n</body>\n\n</html>\n",
+            self.items
+        );
+        fs::write(file, html)?;
+        Ok(())
+    }
+
+    fn inline_html(&self, id: &'static str) -> String {
+        format!(r#"<div id="{}">{}</div>"#, id, self.items)
+    }
+}
+
+impl WarningsComponent {
+    pub fn new(warnings: &[String]) -> Self {
+        let items = match warnings.is_empty() {
+            true => "  <p>No warnings.</p>".to_string(),
+            false => {
+                let items: Vec<String> = warnings
+                    .iter()
+                    .map(|warning| format!("    <li>{}</li>", escape_html(warning)))
+                    .collect();
+                format!("  <ul>\n{}\n  </ul>", items.join("\n"))
+            }
+        };
+        Self { items }
+    }
+}
+
 // pub(crate) fn write_baseline_summary_html(
 //     stats: &StatsSummary,
 //     baseline_stats: &StatsSummary,
@@ -263,3 +391,127 @@ impl<'a> SummaryComponent<'a> {
 // fs::write(file, template)?;
 // Ok(())
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn display_precision() {
+        let stats = StatsSummary::calculate(
+            burl::DurationScale::Milli,
+            0,
+            1_000,
+            vec![16.7654, 12.0, 13.33333],
+            HashMap::new(),
+            HashMap::new(),
+        )
+        .unwrap();
+
+        let mut summary = SummaryComponent::new(Some(1));
+        summary.add_current(&stats);
+        summary.compile(0.05, 100, 2);
+
+        assert!(summary.html.contains("16.8"));
+        assert!(!summary.html.contains("16.76"));
+        // counts are not affected
+        assert!(summary.html.contains("1000"));
+        assert!(!summary.html.contains("1000.0"));
+    }
+
+    #[test]
+    fn baseline_alpha() {
+        let stats = StatsSummary::calculate(
+            burl::DurationScale::Milli,
+            0,
+            0,
+            vec![16.7654, 12.0, 13.33333, 14.1],
+            HashMap::new(),
+            HashMap::new(),
+        )
+        .unwrap();
+
+        let mut summary = SummaryComponent::new(None);
+        summary.add_current(&stats);
+        summary.add_baseline(stats.clone());
+        summary.compile(0.01, 100, 2);
+
+        assert!(summary.html.contains("alpha = 0.01"));
+        assert!(!summary.html.contains("$ALPHA$"));
+    }
+
+    #[test]
+    fn baseline_deltas() {
+        let durations: Vec<f64> = (1..=100).map(|d| d as f64).collect();
+        let summary_of = |durations: Vec<f64>| {
+            StatsSummary::calculate(
+                burl::DurationScale::Milli,
+                0,
+                0,
+                durations,
+                HashMap::new(),
+                HashMap::new(),
+            )
+            .unwrap()
+        };
+        let stats = summary_of(durations.iter().map(|d| d * 1.1).collect());
+        let baseline_stats = summary_of(durations);
+
+        let mut summary = SummaryComponent::new(Some(2));
+        summary.add_current(&stats);
+        summary.add_baseline(baseline_stats.clone());
+        summary.compile(0.05, 100, 2);
+
+        // mean and 95th percentile, each with its change next to the current value
+        let mean = format_value(stats.mean, Some(2));
+        assert!(summary
+            .html
+            .contains(&format!("<td>{} (+10.0% vs baseline)</td>", mean)));
+        let p95 = format_value(p95(&stats), Some(2));
+        assert!(summary
+            .html
+            .contains(&format!("<td>{} (+10.0% vs baseline)</td>", p95)));
+        assert!(!summary.html.contains("$P95"));
+
+        // no deltas without a baseline
+        let mut summary = SummaryComponent::new(Some(2));
+        summary.add_current(&stats);
+        summary.compile(0.05, 100, 2);
+        assert!(summary.html.contains(&format!("<td>{}</td>", p95)));
+        assert!(!summary.html.contains("vs baseline"));
+    }
+
+    #[test]
+    fn baseline_comparison() {
+        let summary_of = |scale: burl::DurationScale, factor: f64| {
+            let durations = (0..200).map(|d| (10 + d % 7) as f64 * factor).collect();
+            StatsSummary::calculate(scale, 0, 0, durations, HashMap::new(), HashMap::new()).unwrap()
+        };
+        let stats = summary_of(burl::DurationScale::Milli, 1.5);
+
+        let mut summary = SummaryComponent::new(Some(2));
+        summary.add_current(&stats);
+        summary.add_baseline(summary_of(burl::DurationScale::Milli, 1.0));
+        summary.compile(0.05, 100, 2);
+        // the headline verdict on the mean and the verdicts of both tests, with all values
+        // of the baseline filled in
+        assert!(summary.html.contains("[Primary: mean] <font color='red'>"));
+        assert_eq!(summary.html.matches("<font color='red'>").count(), 3);
+        assert!(!summary.html.contains('$'));
+
+        let mut summary = SummaryComponent::new(Some(2)).with_primary_metric(PrimaryMetric::P95);
+        summary.add_current(&stats);
+        summary.add_baseline(summary_of(burl::DurationScale::Milli, 1.0));
+        summary.compile(0.05, 100, 2);
+        assert!(summary.html.contains("[Primary: p95] <font color='red'>"));
+
+        let mut summary = SummaryComponent::new(Some(2));
+        summary.add_current(&stats);
+        summary.add_baseline(summary_of(burl::DurationScale::Micro, 1.0));
+        summary.compile(0.05, 100, 2);
+        assert!(summary
+            .html
+            .contains("cannot be compared due to different time scales"));
+    }
+}
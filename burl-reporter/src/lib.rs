@@ -1,12 +1,18 @@
+mod comparison;
+mod criterion;
+mod environments;
 mod html_report;
 mod plots;
+mod prometheus;
 mod report;
 mod stats_helpers;
 
 use std::path::Path;
 
 use burl::BurlResult;
-pub use report::ReportFactory;
+pub use comparison::{ComparisonReport, MetricDelta};
+pub use environments::{EnvironmentRun, EnvironmentsReport};
+pub use report::{config_fingerprint, ReplayedRun, ReportFactory};
 
 // pub trait ComponentCreator {
 //     fn init() -> Self;
@@ -19,4 +25,6 @@ pub trait ComponentBuilder<Content> {
 
 pub trait ComponentWriter {
     fn write(&self, file: &Path) -> BurlResult<()>;
+    /// The component as an HTML element with the given id, for embedding it into a page.
+    fn inline_html(&self, id: &'static str) -> String;
 }
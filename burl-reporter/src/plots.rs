@@ -1,14 +1,15 @@
-use crate::{ComponentWriter};
-use burl::stats::{ThreadStats};
+use crate::ComponentWriter;
+use burl::stats::ThreadStats;
 use burl::ThreadIdx;
 use plotly::box_plot::{BoxMean, BoxPoints};
-use plotly::common::{Line, LineShape, Marker, Mode, Title};
+use plotly::common::{Line, LineShape, Marker, MarkerSymbol, Mode, Side, Title};
 use plotly::histogram::{Bins, HistNorm};
 use plotly::layout::{Axis, BarMode};
 use plotly::{BoxPlot, Histogram, Layout, NamedColor, Plot, Rgb, Scatter};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
 use std::ops::Deref;
-use std::path::{Path};
+use std::path::Path;
 
 // impl ComponentWriter for Plot {
 //     fn write(&self, file: PathBuf) -> burl::BurlResult<()> {
@@ -31,6 +32,46 @@ where
         self.deref().to_html(file);
         Ok(())
     }
+
+    /// NOTE: the plotly library has to be included in the page, see `plotly_js`.
+    fn inline_html(&self, id: &'static str) -> String {
+        self.deref().to_inline_html(id)
+    }
+}
+
+/// The plotly.js library, as embedded by plotly into standalone HTML files.
+pub(crate) fn plotly_js() -> String {
+    let mut plot = Plot::new();
+    plot.use_local_plotly();
+    let mut html = Vec::new();
+    plot.write_html(&mut html);
+    let html = String::from_utf8_lossy(&html);
+
+    // the library is the script starting with its license header
+    html.find("/**")
+        .and_then(|start| {
+            let library = &html[start..];
+            library
+                .find("</script>")
+                .map(|end| library[..end].to_string())
+        })
+        .unwrap_or_default()
+}
+
+/// The copy of plotly.js in the components directory, see `offline_plots`.
+pub(crate) const PLOTLY_JS_FILE: &str = "plotly.min.js";
+
+/// Writes the plot as a page which loads the copy of plotly.js next to it, rather than
+/// plotly.js (and MathJax) from their CDNs.
+pub(crate) fn write_offline(plot: &Plot, file: &Path) -> burl::BurlResult<()> {
+    let html = format!(
+        "<html>\n<head>\n<meta charset=\"utf-8\"/>\n<script src=\"./{}\"></script>\n</head>\n\
+         <body>\n{}\n</body>\n</html>\n",
+        PLOTLY_JS_FILE,
+        plot.to_inline_html("plotly-html-element")
+    );
+    fs::write(file, html)?;
+    Ok(())
 }
 
 // impl<T> ComponentWriter for T
@@ -43,9 +84,25 @@ where
 //     }
 // }
 
+/// Writes a plot as a static image, returning whether the image backend succeeded.
+pub(crate) type ImageExporter = fn(&Plot, &Path) -> bool;
+
+/// Writes the plot as a PNG image with kaleido, see the `static-images` feature.
+/// NOTE: plotly panics if kaleido fails, e.g. if its binary is missing.
+#[cfg(feature = "static-images")]
+pub(crate) fn save_png(plot: &Plot, file: &Path) -> bool {
+    let save = || plot.save(file, plotly::ImageFormat::PNG, 1024, 680, 1.0);
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(save)).is_ok()
+}
+
+/// Without the `static-images` feature, no image backend is available.
+#[cfg(not(feature = "static-images"))]
+pub(crate) fn save_png(_plot: &Plot, _file: &Path) -> bool {
+    false
+}
+
 /// https://github.com/igiagkiozis/plotly/blob/master/examples/statistical_charts/src/main.rs///
 /// https://igiagkiozis.github.io/plotly/content/recipes/statistical_charts/box_plots.html
-
 fn rgb_color(thread_idx: usize, n_threads: usize) -> Rgb {
     let min = 50;
     let max = 255;
@@ -110,8 +167,8 @@ impl BoxPlotComponent {
         self.plot.set_layout(layout);
     }
 
-    pub fn add_total(&mut self, durations: &Vec<f64>) {
-        let trace_durations_box_plot = BoxPlot::new(durations.clone())
+    pub fn add_total(&mut self, durations: &[f64]) {
+        let trace_durations_box_plot = BoxPlot::new(durations.to_owned())
             .name("total")
             .jitter(0.7)
             .marker(Marker::new().color(Rgb::new(7, 40, 89)).size(6))
@@ -136,6 +193,39 @@ impl BoxPlotComponent {
             self.plot.add_trace(thread_durations_box_plot);
         }
     }
+
+    /// Adds a trace per label, e.g. of the labeled payloads, ordered by the label.
+    pub fn add_labels(&mut self, stats_by_label: &HashMap<String, ThreadStats>) {
+        let labels: BTreeMap<_, _> = stats_by_label.iter().collect();
+        for (idx, (label, label_stats)) in labels.into_iter().enumerate() {
+            let label_color = rgb_color(idx, stats_by_label.len());
+            let label_durations_box_plot = BoxPlot::new(label_stats.durations.clone())
+                .name(label.as_str())
+                .jitter(0.7)
+                .marker(Marker::new().color(label_color).size(6))
+                .box_mean(BoxMean::StandardDeviation)
+                .box_points(BoxPoints::All)
+                .line(Line::new().width(2.0));
+
+            self.plot.add_trace(label_durations_box_plot);
+        }
+    }
+
+    /// Adds a trace per run, e.g. of the environments benchmarked side by side, in the given order.
+    pub fn add_runs(&mut self, durations_by_run: &[(String, Vec<f64>)]) {
+        for (idx, (run, durations)) in durations_by_run.iter().enumerate() {
+            let run_color = rgb_color(idx, durations_by_run.len());
+            let run_durations_box_plot = BoxPlot::new(durations.clone())
+                .name(run.as_str())
+                .jitter(0.7)
+                .marker(Marker::new().color(run_color).size(6))
+                .box_mean(BoxMean::StandardDeviation)
+                .box_points(BoxPoints::All)
+                .line(Line::new().width(2.0));
+
+            self.plot.add_trace(run_durations_box_plot);
+        }
+    }
 }
 
 pub struct HistogramComponent {
@@ -168,8 +258,8 @@ impl HistogramComponent {
         self.bins = Some(bins)
     }
 
-    pub fn add_total(&mut self, durations: &Vec<f64>) {
-        let total_histogram = Histogram::new(durations.clone())
+    pub fn add_total(&mut self, durations: &[f64]) {
+        let total_histogram = Histogram::new(durations.to_owned())
             .hist_norm(HistNorm::Probability)
             .name("total")
             .marker(Marker::new().color(NamedColor::Blue));
@@ -197,6 +287,24 @@ impl HistogramComponent {
             }
         }
     }
+
+    /// Adds an overlaid trace per run, e.g. of the environments benchmarked side by side.
+    pub fn add_runs(&mut self, durations_by_run: &[(String, Vec<f64>)]) {
+        for (idx, (run, durations)) in durations_by_run.iter().enumerate() {
+            let run_color = rgb_color(idx, durations_by_run.len());
+            let run_hist = Histogram::new(durations.clone())
+                .name(run.as_str())
+                .hist_norm(HistNorm::Probability)
+                .opacity(0.5)
+                .marker(Marker::new().color(run_color));
+
+            if let Some(bins) = &self.bins {
+                self.plot.add_trace(run_hist.x_bins(bins.clone()));
+            } else {
+                self.plot.add_trace(run_hist);
+            }
+        }
+    }
 }
 
 impl Deref for HistogramComponent {
@@ -291,10 +399,27 @@ impl TimeSeriesComponent {
                 Axis::new()
                     .title(Title::new("request durations"))
                     .zero_line(true),
+            )
+            .y_axis2(
+                Axis::new()
+                    .title(Title::new("requests in flight"))
+                    .overlaying("y")
+                    .side(Side::Right),
             );
         self.plot.set_layout(ts_layout);
     }
 
+    /// Adds the number of requests in flight over all threads (on the right axis).
+    pub fn add_in_flight(&mut self, in_flight: &[(f64, usize)]) {
+        let (times, counts): (Vec<f64>, Vec<usize>) = in_flight.iter().copied().unzip();
+        let trace_in_flight = Scatter::new(times, counts)
+            .name("in flight")
+            .mode(Mode::Lines)
+            .line(Line::new().shape(LineShape::Hv).color(NamedColor::Gray))
+            .y_axis("y2");
+        self.plot.add_trace(trace_in_flight);
+    }
+
     pub fn add(&mut self, ts_by_thread: &HashMap<ThreadIdx, Vec<(f64, f64)>>) {
         for (thread_idx, ts) in ts_by_thread.iter() {
             let mut ts_dates: Vec<f64> = Vec::with_capacity(ts.len());
@@ -315,6 +440,40 @@ impl TimeSeriesComponent {
             self.plot.add_trace(trace_ts);
         }
     }
+
+    /// Adds the time series of each run (over all of its threads), e.g. of the environments
+    /// benchmarked side by side.
+    pub fn add_runs(&mut self, ts_by_run: &[(String, Vec<(f64, f64)>)]) {
+        for (idx, (run, ts)) in ts_by_run.iter().enumerate() {
+            let (ts_dates, ts_values): (Vec<f64>, Vec<f64>) = ts.iter().copied().unzip();
+            let trace_ts = Scatter::new(ts_dates, ts_values)
+                .name(run.as_str())
+                .mode(Mode::Markers)
+                .marker(Marker::new().color(rgb_color(idx, ts_by_run.len())));
+            self.plot.add_trace(trace_ts);
+        }
+    }
+
+    /// Adds the warmup points of each thread, e.g. the samples excluded by `discard_first_secs`,
+    /// as grayed markers apart from the measured points.
+    pub fn add_warmup(&mut self, ts_by_thread: &HashMap<ThreadIdx, Vec<(f64, f64)>>) {
+        let ts_by_thread: BTreeMap<_, _> = ts_by_thread
+            .iter()
+            .filter(|(_, ts)| !ts.is_empty())
+            .collect();
+        for (thread_idx, ts) in ts_by_thread {
+            let (ts_dates, ts_values): (Vec<f64>, Vec<f64>) = ts.iter().copied().unzip();
+            let trace_warmup = Scatter::new(ts_dates, ts_values)
+                .name(format!("{} (warmup)", thread_idx).as_str())
+                .mode(Mode::Markers)
+                .marker(
+                    Marker::new()
+                        .color(NamedColor::LightGray)
+                        .symbol(MarkerSymbol::CirleOpen),
+                );
+            self.plot.add_trace(trace_warmup);
+        }
+    }
 }
 
 pub struct QQPlotComponent {
@@ -355,7 +514,7 @@ impl QQPlotComponent {
         self.plot.set_layout(layout);
     }
 
-    pub fn add_current(&mut self, qq_curve: &Vec<(f64, f64)>) {
+    pub fn add_current(&mut self, qq_curve: &[(f64, f64)]) {
         let mut x_percentiles: Vec<f64> = Vec::with_capacity(qq_curve.len());
         let mut y_percentiles = Vec::with_capacity(qq_curve.len());
 
@@ -374,7 +533,7 @@ impl QQPlotComponent {
         self.plot.add_trace(qq_trace);
     }
 
-    pub fn add_baseline(&mut self, qq_curve: &Vec<(f64, f64)>) {
+    pub fn add_baseline(&mut self, qq_curve: &[(f64, f64)]) {
         let mut x_percentiles: Vec<f64> = Vec::with_capacity(qq_curve.len());
         let mut y_percentiles = Vec::with_capacity(qq_curve.len());
 
@@ -700,3 +859,27 @@ impl QQPlotComponent {
 //         plot.show();
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warmup_time_series() {
+        let measured = HashMap::from([(0, vec![(3.0, 10.0), (4.0, 11.0)])]);
+        let warmup = HashMap::from([(0, vec![(1.0, 30.0), (2.0, 20.0)]), (1, vec![])]);
+
+        let mut time_series = TimeSeriesComponent::new();
+        time_series.add(&measured);
+        time_series.add_warmup(&warmup);
+
+        let plot: serde_json::Value = serde_json::from_str(&time_series.to_json()).unwrap();
+        let traces = plot["data"].as_array().unwrap();
+        assert_eq!(traces.len(), 2);
+        assert_eq!(traces[0]["mode"], "lines+markers");
+        assert_eq!(traces[1]["mode"], "markers");
+        assert_eq!(traces[1]["name"], "0 (warmup)");
+        assert_eq!(traces[1]["marker"]["color"], "lightgray");
+        assert_ne!(traces[0]["marker"], traces[1]["marker"]);
+    }
+}
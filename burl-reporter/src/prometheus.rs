@@ -0,0 +1,107 @@
+use burl::stats::StatsSummary;
+use burl::DurationScale;
+
+const QUANTILES: [f64; 5] = [0.5, 0.75, 0.9, 0.95, 0.99];
+
+/// Escapes a label value according to the Prometheus text exposition format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Renders the key metrics of a run in the Prometheus text format,
+/// e.g. for pushing them into a pushgateway.
+pub(crate) fn prometheus_metrics(stats: &StatsSummary, run_label: &str) -> String {
+    let run = escape_label(run_label);
+    // latencies are reported in seconds
+    let to_secs = 1.0 / stats.scale.factor(&DurationScale::Secs);
+
+    let mut metrics = String::new();
+    let mut line = |l: String| {
+        metrics.push_str(&l);
+        metrics.push('\n');
+    };
+
+    line("# HELP burl_latency_seconds The request latencies.".to_string());
+    line("# TYPE burl_latency_seconds summary".to_string());
+    for (level, percentile) in stats.percentiles(&QUANTILES) {
+        line(format!(
+            "burl_latency_seconds{{run=\"{}\",quantile=\"{}\"}} {}",
+            run,
+            level / 100.0,
+            percentile * to_secs
+        ));
+    }
+    line(format!(
+        "burl_latency_seconds_sum{{run=\"{}\"}} {}",
+        run,
+        stats.total_duration * to_secs
+    ));
+    line(format!(
+        "burl_latency_seconds_count{{run=\"{}\"}} {}",
+        run, stats.n_ok
+    ));
+
+    line("# HELP burl_requests_total The number of sent requests.".to_string());
+    line("# TYPE burl_requests_total counter".to_string());
+    line(format!(
+        "burl_requests_total{{run=\"{}\"}} {}",
+        run,
+        stats.n_ok + stats.n_errors
+    ));
+
+    line("# HELP burl_errors_total The number of failed requests.".to_string());
+    line("# TYPE burl_errors_total counter".to_string());
+    line(format!(
+        "burl_errors_total{{run=\"{}\"}} {}",
+        run, stats.n_errors
+    ));
+
+    if let Some(rps) = stats.mean_rps {
+        line("# HELP burl_requests_per_second The mean number of requests per second.".to_string());
+        line("# TYPE burl_requests_per_second gauge".to_string());
+        line(format!(
+            "burl_requests_per_second{{run=\"{}\"}} {}",
+            run, rps
+        ));
+    }
+
+    metrics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn prometheus_metrics() {
+        let durations = (1..=100).map(|d| d as f64).collect();
+        let stats = StatsSummary::calculate(
+            DurationScale::Milli,
+            3,
+            0,
+            durations,
+            HashMap::new(),
+            HashMap::new(),
+        )
+        .unwrap();
+
+        let metrics = super::prometheus_metrics(&stats, "release \"A\"");
+
+        for line in metrics.lines().filter(|l| !l.starts_with('#')) {
+            let (series, value) = line.rsplit_once(' ').unwrap();
+            assert!(value.parse::<f64>().is_ok(), "invalid value in: {}", line);
+            assert!(series.starts_with("burl_"));
+            assert!(series.contains("{run=\"release \\\"A\\\"\""));
+            assert!(series.ends_with('}'));
+        }
+
+        assert!(metrics
+            .contains("burl_latency_seconds{run=\"release \\\"A\\\"\",quantile=\"0.95\"} 0.095"));
+        assert!(metrics.contains("burl_requests_total{run=\"release \\\"A\\\"\"} 103"));
+        assert!(metrics.contains("burl_errors_total{run=\"release \\\"A\\\"\"} 3"));
+    }
+}
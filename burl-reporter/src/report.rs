@@ -1,30 +1,43 @@
-use crate::html_report::SummaryComponent;
+use crate::comparison::{ComparisonReport, HistoryBand};
+use crate::criterion::criterion_estimates;
+use crate::html_report::{SummaryComponent, WarningsComponent};
 use crate::plots::{
-    BootstrapHistogramComponent, BoxPlotComponent, HistogramComponent, QQPlotComponent,
-    TimeSeriesComponent,
+    plotly_js, save_png, write_offline, BootstrapHistogramComponent, BoxPlotComponent,
+    HistogramComponent, ImageExporter, PlotComponent, QQPlotComponent, TimeSeriesComponent,
+    PLOTLY_JS_FILE,
+};
+use crate::prometheus::prometheus_metrics;
+use crate::stats_helpers::{
+    average_concurrency, downsample_lttb, estimated_memory_footprint, in_flight_curve,
+    memory_warning, start_skew, StatisticalTester, MEMORY_WARNING_BYTES, START_SKEW_WARNING_SHARE,
 };
 use crate::ComponentWriter;
-use burl::sampling::SampleResult;
+use burl::sampling::{ResolvedRequest, SampleResult};
 use burl::stats::{StatsProcessor, StatsSummary};
-use burl::{BenchClientConfig, BurlError, BurlResult, ThreadIdx};
-use chrono::{DateTime, Utc};
-use log::{info, warn};
-use serde::Serialize;
+use burl::{BenchClientConfig, BurlError, BurlResult, ThreadIdx, Warnings};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
-    fs,
+    collections::{BTreeMap, HashMap},
+    fs, panic,
     path::{Path, PathBuf},
+    thread,
 };
 
 const COMPONENTS_DIR: &str = "components";
-const DATA_DIR: &str = "data";
-const FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+pub(crate) const DATA_DIR: &str = "data";
+pub(crate) const FORMAT: &str = "%Y-%m-%d %H:%M:%S";
 const HIST_PATH: &str = "hist";
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 struct ReportMeta {
     start_time: String,
     end_time: String,
+    /// See `config_fingerprint`; missing in reports of older versions.
+    #[serde(default)]
+    fingerprint: String,
+    /// The config with the secrets redacted, see `BenchClientConfig::redacted`.
     config: BenchClientConfig,
 }
 
@@ -33,11 +46,138 @@ impl<'a> From<&ReportFactory<'a>> for ReportMeta {
         Self {
             start_time: format!("{}", rs.start_time.format(FORMAT)),
             end_time: format!("{}", rs.end_time.format(FORMAT)),
-            config: rs.config.clone(),
+            fingerprint: config_fingerprint(rs.config),
+            config: rs.config.redacted(),
         }
     }
 }
 
+/// A stable hash (64-bit FNV-1a, in hex) of the serialized config, e.g. to check whether
+/// two reports were created with the same config. The fields are serialized in the order
+/// of their declaration, hence the hash is independent of the order in the specs file.
+/// The secrets are redacted before (see `BenchClientConfig::redacted`), such that the hash
+/// of the config stored in a report matches the one of the config it was created with.
+pub fn config_fingerprint(config: &BenchClientConfig) -> String {
+    const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    // NOTE: the config has no maps or non-string keys, hence it always serializes
+    let serialized = serde_json::to_vec(&config.redacted()).unwrap_or_default();
+    let hash = serialized.iter().fold(FNV_OFFSET, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(FNV_PRIME)
+    });
+    format!("{:016x}", hash)
+}
+
+/// The consolidated run, e.g. for dashboards: unlike the separate `stats.json` and `meta.json`,
+/// it contains the failures and the verdict against the baseline.
+#[derive(Serialize)]
+struct RunExport<'a> {
+    start_time: String,
+    end_time: String,
+    /// The config with the secrets redacted.
+    config: BenchClientConfig,
+    stats: Option<&'a StatsSummary>,
+    /// The number of failed requests by the kind of failure.
+    failures: BTreeMap<String, usize>,
+    verdict: Option<&'a ComparisonReport>,
+    /// The warnings of the run and the report, e.g. an unbalanced comparison.
+    warnings: Vec<String>,
+}
+
+/// The `(theoretical, empirical)` percentiles of the QQ curves, e.g. for external plotting.
+#[derive(Serialize)]
+struct QQData {
+    current: Vec<(f64, f64)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    baseline: Option<Vec<(f64, f64)>>,
+}
+
+fn parse_time(time: &str) -> BurlResult<DateTime<Utc>> {
+    let naive =
+        NaiveDateTime::parse_from_str(time, FORMAT).map_err(|err| BurlError::InvalidConfig {
+            issue: format!("Invalid time '{}' in the report meta data: {}", time, err),
+        })?;
+    Ok(Utc.from_utc_datetime(&naive))
+}
+
+/// A run restored from the data dumped into a report directory, e.g. to regenerate
+/// the report after a failure, without benchmarking again.
+pub struct ReplayedRun {
+    pub config: BenchClientConfig,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    sample_results_by_thread: HashMap<ThreadIdx, Vec<SampleResult>>,
+    /// Set if the run is compared against another report directory, see `load_comparison`.
+    is_comparison: bool,
+}
+
+impl ReplayedRun {
+    /// Loads `meta.json` and `samples.json` from the data directory of the report.
+    pub fn load(report_directory: &Path) -> BurlResult<Self> {
+        let data_dir = report_directory.join(DATA_DIR);
+        let meta: ReportMeta = read_data(&data_dir.join("meta.json"))?;
+        let sample_results_by_thread = read_data(&data_dir.join("samples.json"))?;
+
+        let mut config = meta.config;
+        config.report_directory = Some(report_directory.to_string_lossy().to_string());
+
+        Ok(Self {
+            config,
+            start_time: parse_time(&meta.start_time)?,
+            end_time: parse_time(&meta.end_time)?,
+            sample_results_by_thread,
+            is_comparison: false,
+        })
+    }
+
+    /// Loads the run of the `current_directory` to compare it against the run of the
+    /// `baseline_directory`, in a new report (with the data of the run) in the `output_directory`.
+    pub fn load_comparison(
+        baseline_directory: &Path,
+        current_directory: &Path,
+        output_directory: &Path,
+    ) -> BurlResult<Self> {
+        let baseline_data_dir = baseline_directory.join(DATA_DIR);
+        if !baseline_data_dir.join("stats.json").exists() {
+            return Err(BurlError::InvalidConfig {
+                issue: format!(
+                    "The baseline directory {:?} contains no stats",
+                    baseline_directory.as_os_str()
+                ),
+            });
+        }
+
+        let mut run = Self::load(current_directory)?;
+        run.config.baseline_path = Some(baseline_data_dir.to_string_lossy().to_string());
+        run.config.report_directory = Some(output_directory.to_string_lossy().to_string());
+        run.is_comparison = true;
+        Ok(run)
+    }
+
+    /// The dumped samples of the run, e.g. to continue it, see `StatsProcessor::with_prior_samples`.
+    pub fn into_sample_results_by_thread(self) -> HashMap<ThreadIdx, Vec<SampleResult>> {
+        self.sample_results_by_thread
+    }
+
+    /// The factory regenerating the report; the dumped data is kept as is.
+    pub fn report_factory(&self) -> ReportFactory<'_> {
+        let stats_processor = StatsProcessor::from_sample_results(
+            &self.config,
+            self.sample_results_by_thread.clone(),
+        );
+        let mut report_factory = ReportFactory::new(
+            self.start_time,
+            self.end_time,
+            &self.config,
+            stats_processor,
+        );
+        // NOTE: a comparison is a new report, which needs the data of the run
+        report_factory.replay = !self.is_comparison;
+        report_factory
+    }
+}
+
 fn create_dir(dir: &Path) -> BurlResult<()> {
     if dir.exists() && dir.is_dir() {
         return Ok(());
@@ -69,17 +209,58 @@ fn hist_results(from_dir: &PathBuf) -> BurlResult<()> {
     Ok(())
 }
 
+/// The stats of the last `n_runs` runs archived in the `hist` directory of the data directory,
+/// oldest first; archives without (valid) stats are skipped.
+fn archived_stats(data_dir: &Path, n_runs: usize) -> BurlResult<Vec<StatsSummary>> {
+    let hist_dir = data_dir.join(HIST_PATH);
+    if !hist_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut runs = Vec::new();
+    for entry in fs::read_dir(&hist_dir)? {
+        let entry = entry?;
+        if entry.path().is_dir() {
+            runs.push(entry.path());
+        }
+    }
+    // NOTE: the archive directories are named by their time
+    runs.sort();
+
+    Ok(runs
+        .iter()
+        .filter_map(|run| read_data::<Option<StatsSummary>>(&run.join("stats.json")).ok())
+        .flatten()
+        .rev()
+        .take(n_runs)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect())
+}
+
 fn read_data<D: serde::de::DeserializeOwned>(file: &PathBuf) -> BurlResult<D> {
     let file_data = fs::read_to_string(file)?;
     let data: D = serde_json::from_str(&file_data)?;
     Ok(data)
 }
 
-fn setup_report_structure(path: &Path) -> Result<(PathBuf, PathBuf), BurlError> {
+pub(crate) fn setup_report_structure(path: &Path) -> Result<PathBuf, BurlError> {
     if !path.exists() {
         fs::create_dir(path)?;
     }
 
+    let data_dir = Path::new(&path).join(DATA_DIR);
+    if !data_dir.exists() {
+        fs::create_dir(&data_dir)?;
+    }
+
+    info!("Creating report in {:?}", path.as_os_str());
+    Ok(data_dir)
+}
+
+/// Sets up the `report.html`, which includes the component files of the components directory.
+pub(crate) fn setup_components(path: &Path) -> Result<PathBuf, BurlError> {
     let report_file = path.join("report.html");
     if !report_file.exists() {
         let template = include_str!("./templates/report_template.html");
@@ -90,14 +271,124 @@ fn setup_report_structure(path: &Path) -> Result<(PathBuf, PathBuf), BurlError>
     if !components_dir.exists() {
         fs::create_dir(&components_dir)?;
     }
+    Ok(components_dir)
+}
 
-    let data_dir = Path::new(&path).join(DATA_DIR);
-    if !data_dir.exists() {
-        fs::create_dir(&data_dir)?;
+/// The names of the files in the directory, sorted; empty if it does not exist.
+fn file_names(dir: &Path) -> BurlResult<Vec<String>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
     }
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.path().is_file() {
+            names.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
 
-    info!("Creating report in {:?}", path.as_os_str());
-    Ok((components_dir, data_dir))
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn link_item(href: &str, text: &str) -> String {
+    format!(
+        r#"    <li><a href="{}">{}</a></li>"#,
+        escape_html(href),
+        escape_html(text)
+    )
+}
+
+/// Writes the `index.html` of the report directory, linking the report, each of the
+/// components and data files which are present and the runs archived in `data/hist`.
+pub(crate) fn write_index(path: &Path) -> BurlResult<()> {
+    let mut current_links = Vec::new();
+    if path.join("report.html").exists() {
+        current_links.push(link_item("./report.html", "report"));
+    }
+    for name in file_names(&path.join(COMPONENTS_DIR))? {
+        if name == PLOTLY_JS_FILE {
+            continue;
+        }
+        let href = format!("./{}/{}", COMPONENTS_DIR, name);
+        current_links.push(link_item(&href, &name));
+    }
+
+    let data_links: Vec<String> = file_names(&path.join(DATA_DIR))?
+        .iter()
+        .map(|name| link_item(&format!("./{}/{}", DATA_DIR, name), name))
+        .collect();
+
+    let hist_dir = path.join(DATA_DIR).join(HIST_PATH);
+    let mut runs = Vec::new();
+    if hist_dir.is_dir() {
+        for entry in fs::read_dir(&hist_dir)? {
+            let entry = entry?;
+            if entry.path().is_dir() {
+                runs.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+    // NOTE: the archive directories are named by their time, hence the latest comes first
+    runs.sort_by(|a, b| b.cmp(a));
+
+    let mut hist_links = Vec::new();
+    for run in runs.iter() {
+        let run_dir = hist_dir.join(run);
+        let title = match read_data::<ReportMeta>(&run_dir.join("meta.json")) {
+            Ok(meta) => format!("{} ({})", meta.config.run_label(), meta.start_time),
+            Err(_) => run.clone(),
+        };
+        let files: Vec<String> = file_names(&run_dir)?
+            .iter()
+            .map(|name| {
+                format!(
+                    r#"<a href="./{}/{}/{}/{}">{}</a>"#,
+                    DATA_DIR,
+                    HIST_PATH,
+                    escape_html(run),
+                    escape_html(name),
+                    escape_html(name)
+                )
+            })
+            .collect();
+        hist_links.push(format!(
+            "    <li>{}: {}</li>",
+            escape_html(&title),
+            files.join(", ")
+        ));
+    }
+
+    let html = include_str!("./templates/index_template.html")
+        .replace("$CURRENT_LINKS$", &current_links.join("\n"))
+        .replace("$DATA_LINKS$", &data_links.join("\n"))
+        .replace("$HIST_LINKS$", &hist_links.join("\n"));
+    fs::write(path.join("index.html"), html)?;
+    Ok(())
+}
+
+/// The samples ordered by thread, so that the dumps are reproducible, and optionally
+/// by their start within each thread (see `sort_samples`).
+fn ordered_samples(
+    sample_results_by_thread: &HashMap<ThreadIdx, Vec<SampleResult>>,
+    sort: bool,
+) -> BTreeMap<ThreadIdx, Vec<&SampleResult>> {
+    sample_results_by_thread
+        .iter()
+        .map(|(thread_idx, samples)| {
+            let mut samples: Vec<&SampleResult> = samples.iter().collect();
+            if sort {
+                samples.sort_by(|a, b| a.measurement_start.total_cmp(&b.measurement_start));
+            }
+            (*thread_idx, samples)
+        })
+        .collect()
 }
 
 fn serialize<D: Serialize>(data: &D) -> BurlResult<String> {
@@ -106,17 +397,29 @@ fn serialize<D: Serialize>(data: &D) -> BurlResult<String> {
 }
 
 /// Serializes the data, creates or updates the file and its contents.
-fn write_or_update<D: Serialize>(serializable_data: &D, file: PathBuf) -> BurlResult<()> {
+pub(crate) fn write_or_update<D: Serialize>(
+    serializable_data: &D,
+    file: PathBuf,
+) -> BurlResult<()> {
     let json = serialize(serializable_data)?;
     fs::write(file, json)?;
     Ok(())
 }
 
+/// Creates a report component and writes it to the given file.
+type ComponentJob<'s> = Box<dyn Fn(&Path) -> BurlResult<()> + Send + Sync + 's>;
+
 pub struct ReportFactory<'a> {
     config: &'a BenchClientConfig,
     stats_processor: StatsProcessor,
     start_time: DateTime<Utc>,
     end_time: DateTime<Utc>,
+    /// Set when regenerating the report of a `ReplayedRun`.
+    replay: bool,
+    /// Writes the PNG versions of the plots, see `static_images`.
+    image_exporter: ImageExporter,
+    /// The warnings logged during the run and while creating the report, see `report_warnings`.
+    warnings: Warnings,
 }
 
 impl<'a> ReportFactory<'a> {
@@ -126,14 +429,43 @@ impl<'a> ReportFactory<'a> {
         config: &'a BenchClientConfig,
         stats_processor: StatsProcessor,
     ) -> Self {
+        // NOTE: the warnings of the run precede the ones of the report
+        let warnings = Warnings::new(stats_processor.warnings().to_vec());
         Self {
             config,
             stats_processor,
             start_time,
             end_time,
+            replay: false,
+            image_exporter: save_png,
+            warnings,
         }
     }
 
+    /// The warnings logged so far, in their order.
+    pub fn warnings(&self) -> Vec<String> {
+        self.warnings.to_vec()
+    }
+
+    /// Writes the plot, followed by a PNG version of it if `static_images` is configured.
+    fn write_plot(&self, plot: impl PlotComponent, file: &Path) -> BurlResult<()> {
+        match self.config.offline_plots() {
+            true => write_offline(&plot, file)?,
+            false => plot.write(file)?,
+        }
+        if self.config.static_images() {
+            let image_file = file.with_extension("png");
+            if !(self.image_exporter)(&plot, &image_file) {
+                self.warnings.warn(format!(
+                    "Could not write the static image {:?}: burl-reporter requires the \
+                     `static-images` feature and the kaleido backend",
+                    image_file
+                ));
+            }
+        }
+        Ok(())
+    }
+
     fn dump_data(
         &self,
         dir: PathBuf,
@@ -146,7 +478,8 @@ impl<'a> ReportFactory<'a> {
 
         if stats_file.exists() | meta_file.exists() | samples_file.exists() {
             if let Err(err) = hist_results(&dir) {
-                warn!("Overwriting existing baseline results: {}", err);
+                self.warnings
+                    .warn(format!("Overwriting existing baseline results: {}", err));
             }
         }
 
@@ -155,7 +488,15 @@ impl<'a> ReportFactory<'a> {
         // creates or updates the files and its contents
         write_or_update(stats, stats_file)?;
         write_or_update(&report_meta, meta_file)?;
-        write_or_update(&sample_results_by_thread, samples_file)?;
+        match ResolvedRequest::from_config(self.config) {
+            Ok(request) => write_or_update(&request, dir.join("request.json"))?,
+            Err(err) => self.warnings.warn(format!(
+                "Could not resolve the request for the report: {}",
+                err
+            )),
+        }
+        let ordered_samples = ordered_samples(sample_results_by_thread, self.config.sort_samples());
+        write_or_update(&ordered_samples, samples_file)?;
 
         Ok(())
     }
@@ -163,24 +504,26 @@ impl<'a> ReportFactory<'a> {
     fn baseline_results(&self, data_dir: &Path) -> Option<StatsSummary> {
         let baseline_dir = match &self.config.baseline_path {
             Some(p) => PathBuf::new().join(p),
+            // NOTE: when replaying, the data directory contains the replayed run itself
+            None if self.replay => return None,
             None => data_dir.to_path_buf(),
         };
 
         if !baseline_dir.exists() {
-            warn!(
+            self.warnings.warn(format!(
                 "Specified baseline directory does not exist: {:?}",
                 baseline_dir.as_os_str()
-            );
+            ));
             return None;
         }
 
         let results_file = &baseline_dir.join("stats.json");
 
         if !results_file.exists() {
-            warn!(
+            self.warnings.warn(format!(
                 "Expected file does not exist: {:?}",
                 results_file.as_os_str()
-            );
+            ));
             return None;
         }
 
@@ -188,51 +531,140 @@ impl<'a> ReportFactory<'a> {
         baseline_results
     }
 
-    fn create_components(
+    fn summary_component<'s>(
+        &self,
+        stats: &'s StatsSummary,
+        baseline_stats: Option<&StatsSummary>,
+    ) -> SummaryComponent<'s> {
+        let mut summary = SummaryComponent::new(self.config.display_precision())
+            .with_primary_metric(self.config.primary_metric());
+        summary.add_current(stats);
+        if let Some(bl_stats) = baseline_stats {
+            summary.add_baseline(bl_stats.clone());
+        }
+        summary.compile(
+            self.config.alpha(),
+            self.config.n_bootstrap_samples(),
+            self.config.min_samples(),
+        );
+        summary
+    }
+
+    fn box_plot(stats: &StatsSummary) -> BoxPlotComponent {
+        let mut box_plot = BoxPlotComponent::new();
+        box_plot.add_total(&stats.durations);
+        if stats.stats_by_thread.len() > 1 {
+            box_plot.add_threads(&stats.stats_by_thread);
+        }
+        if !stats.stats_by_label.is_empty() {
+            box_plot.add_labels(&stats.stats_by_label);
+        }
+        box_plot
+    }
+
+    /// The time series of all threads, with the discarded (warmup) samples grayed out,
+    /// downsampled to the `max_time_series_points` if configured.
+    fn time_series_plot(
         &self,
-        components_dir: Option<PathBuf>,
-        current_stats: &Option<StatsSummary>,
-        baseline_stats: Option<StatsSummary>,
         sample_results_by_thread: &HashMap<ThreadIdx, Vec<SampleResult>>,
-    ) -> BurlResult<()> {
-        let stats = match current_stats {
-            Some(stats) => stats,
-            None => {
-                return Ok(());
-            }
+    ) -> TimeSeriesComponent {
+        let time_series_where = |discarded: bool| -> HashMap<ThreadIdx, Vec<(f64, f64)>> {
+            sample_results_by_thread
+                .iter()
+                .map(|(thread_idx, sample_results)| {
+                    let ts = sample_results
+                        .iter()
+                        .filter(|sr| sr.discarded == discarded)
+                        .map(|sr| sr.as_timeseries_point())
+                        .collect();
+                    (*thread_idx, ts)
+                })
+                .collect()
         };
+        let time_series = time_series_where(false);
+        let warmup_time_series = time_series_where(true);
+
+        let mut in_flight = in_flight_curve(
+            time_series
+                .values()
+                .chain(warmup_time_series.values())
+                .flatten(),
+        );
 
-        let mut summary = SummaryComponent::new();
-        let mut box_plot = BoxPlotComponent::new();
         let mut time_series_plot = TimeSeriesComponent::new();
-        let mut histogram = HistogramComponent::new();
-        let mut qq_plot = QQPlotComponent::new();
-        let mut bs_histogram = BootstrapHistogramComponent::new();
+        match self.config.max_time_series_points() {
+            Some(max_points) => {
+                let max_points_per_thread = max_points / time_series.len().max(1);
+                let downsample = |time_series: HashMap<ThreadIdx, Vec<(f64, f64)>>| {
+                    time_series
+                        .into_iter()
+                        .map(|(thread_idx, ts)| {
+                            (thread_idx, downsample_lttb(&ts, max_points_per_thread))
+                        })
+                        .collect()
+                };
+                time_series_plot.add(&downsample(time_series));
+                time_series_plot.add_warmup(&downsample(warmup_time_series));
 
-        let time_series = sample_results_by_thread
-            .iter()
-            .map(|(thread_idx, sample_results)| {
-                let ts = sample_results
+                let in_flight_points: Vec<(f64, f64)> = in_flight
                     .iter()
-                    .map(|sr| sr.as_timeseries_point())
+                    .map(|(time, count)| (*time, *count as f64))
                     .collect();
-                (*thread_idx, ts)
-            })
-            .collect();
-
-        time_series_plot.add(&time_series);
+                in_flight = downsample_lttb(&in_flight_points, max_points)
+                    .into_iter()
+                    .map(|(time, count)| (time, count as usize))
+                    .collect();
+            }
+            None => {
+                time_series_plot.add(&time_series);
+                time_series_plot.add_warmup(&warmup_time_series);
+            }
+        }
+        time_series_plot.add_in_flight(&in_flight);
+        time_series_plot
+    }
 
-        summary.add_current(&stats);
-        box_plot.add_total(&stats.durations);
+    fn histogram(stats: &StatsSummary) -> HistogramComponent {
+        let mut histogram = HistogramComponent::new();
         histogram.set_bins(stats.min, stats.max);
         histogram.add_total(&stats.durations);
-        qq_plot.add_current(&stats.normal_qq_curve());
-
         if stats.stats_by_thread.len() > 1 {
-            box_plot.add_threads(&stats.stats_by_thread);
             histogram.add_threads(&stats.stats_by_thread);
         }
+        histogram
+    }
+
+    fn qq_plot(
+        &self,
+        stats: &StatsSummary,
+        baseline_stats: Option<&StatsSummary>,
+    ) -> QQPlotComponent {
+        let mut qq_plot = QQPlotComponent::new();
+        qq_plot.add_current(&stats.normal_qq_curve(self.config.n_qq_points()));
+        if let Some(bl_stats) = baseline_stats {
+            qq_plot.add_baseline(&bl_stats.normal_qq_curve(self.config.n_qq_points()));
+        }
+        qq_plot.add_reference_line();
+        qq_plot
+    }
+
+    /// Writes the QQ curves of the current run and the baseline into `qq.json`.
+    fn write_qq_data(
+        &self,
+        data_dir: &Path,
+        stats: &StatsSummary,
+        baseline_stats: Option<&StatsSummary>,
+    ) -> BurlResult<()> {
+        let n_points = self.config.n_qq_points();
+        let qq_data = QQData {
+            current: stats.normal_qq_curve(n_points),
+            baseline: baseline_stats.map(|bl_stats| bl_stats.normal_qq_curve(n_points)),
+        };
+        write_or_update(&qq_data, data_dir.join("qq.json"))
+    }
 
+    fn bootstrap_histogram(&self, stats: &StatsSummary) -> BootstrapHistogramComponent {
+        let mut bs_histogram = BootstrapHistogramComponent::new();
         if let (bootstrap_means, Some((lower_bound, upper_bound))) = stats.bootstrap_summary(
             self.config.n_bootstrap_draw_size(),
             self.config.n_bootstrap_samples(),
@@ -241,50 +673,347 @@ impl<'a> ReportFactory<'a> {
             bs_histogram.add_total(&bootstrap_means);
             bs_histogram.add_confidence_interval(lower_bound, upper_bound);
         }
+        bs_histogram
+    }
 
-        if let Some(bl_stats) = baseline_stats {
-            qq_plot.add_baseline(&bl_stats.normal_qq_curve());
-            summary.add_baseline(bl_stats.clone());
+    fn create_components(
+        &self,
+        components_dir: Option<PathBuf>,
+        current_stats: &Option<StatsSummary>,
+        baseline_stats: Option<StatsSummary>,
+        sample_results_by_thread: &HashMap<ThreadIdx, Vec<SampleResult>>,
+    ) -> BurlResult<()> {
+        let stats = match current_stats {
+            Some(stats) => stats,
+            None => {
+                return Ok(());
+            }
+        };
+        let baseline_stats = baseline_stats.as_ref();
+
+        let dir = match &components_dir {
+            Some(dir) => dir,
+            None => {
+                Self::box_plot(stats).show();
+                self.time_series_plot(sample_results_by_thread).show();
+                Self::histogram(stats).show();
+                return Ok(());
+            }
+        };
+
+        if self.config.offline_plots() {
+            fs::write(dir.join(PLOTLY_JS_FILE), plotly_js())?;
         }
 
-        summary.compile(self.config.alpha(), self.config.n_bootstrap_samples());
-        qq_plot.add_reference_line();
+        // NOTE: plots are not `Send`, hence each component is created and written on its own thread.
+        // Every component writes to a distinct file, so that the writes cannot race.
+        let warnings = self.warnings();
+        let mut components: Vec<(&str, ComponentJob)> = vec![
+            (
+                "summary.html",
+                Box::new(|file| self.summary_component(stats, baseline_stats).write(file)),
+            ),
+            (
+                "durations_distribution.html",
+                Box::new(|file| self.write_plot(Self::box_plot(stats), file)),
+            ),
+            (
+                "durations_timeseries.html",
+                Box::new(|file| {
+                    self.write_plot(self.time_series_plot(sample_results_by_thread), file)
+                }),
+            ),
+            (
+                "durations_histogram.html",
+                Box::new(|file| self.write_plot(Self::histogram(stats), file)),
+            ),
+            (
+                "qq_plot.html",
+                Box::new(|file| self.write_plot(self.qq_plot(stats, baseline_stats), file)),
+            ),
+            (
+                "bootstrap_histogram.html",
+                Box::new(|file| self.write_plot(self.bootstrap_histogram(stats), file)),
+            ),
+        ];
+        if self.config.report_warnings() {
+            components.push((
+                "warnings.html",
+                Box::new(|file| WarningsComponent::new(&warnings).write(file)),
+            ));
+        }
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = components
+                .iter()
+                .map(|(file_name, job)| scope.spawn(move || job(&dir.join(file_name))))
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|panic| panic::resume_unwind(panic))
+                })
+                .collect::<BurlResult<Vec<()>>>()
+        })?;
+
+        Ok(())
+    }
 
-        match &components_dir {
-            Some(dir) => {
-                summary.write(&dir.join("summary.html"))?;
-                box_plot.write(&dir.join("durations_distribution.html"))?;
-                time_series_plot.write(&dir.join("durations_timeseries.html"))?;
-                histogram.write(&dir.join("durations_histogram.html"))?;
-                qq_plot.write(&dir.join("qq_plot.html"))?;
-                bs_histogram.write(&dir.join("bootstrap_histogram.html"))?;
+    /// Writes the report as a standalone `report.html`, with all components and plotly inlined.
+    fn create_single_file_report(
+        &self,
+        path: &Path,
+        stats: &StatsSummary,
+        baseline_stats: Option<&StatsSummary>,
+        sample_results_by_thread: &HashMap<ThreadIdx, Vec<SampleResult>>,
+    ) -> BurlResult<()> {
+        let components = [
+            (
+                "$SUMMARY$",
+                self.summary_component(stats, baseline_stats)
+                    .inline_html("summary"),
+            ),
+            (
+                "$DURATIONS_DISTRIBUTION$",
+                Self::box_plot(stats).inline_html("durations_distribution"),
+            ),
+            (
+                "$DURATIONS_HISTOGRAM$",
+                Self::histogram(stats).inline_html("durations_histogram"),
+            ),
+            (
+                "$QQ_PLOT$",
+                self.qq_plot(stats, baseline_stats).inline_html("qq_plot"),
+            ),
+            (
+                "$DURATIONS_TIMESERIES$",
+                self.time_series_plot(sample_results_by_thread)
+                    .inline_html("durations_timeseries"),
+            ),
+            (
+                "$BOOTSTRAP_HISTOGRAM$",
+                self.bootstrap_histogram(stats)
+                    .inline_html("bootstrap_histogram"),
+            ),
+            (
+                "$WARNINGS$",
+                match self.config.report_warnings() {
+                    true => format!(
+                        "<h1>WARNINGS</h1>\n  {}",
+                        WarningsComponent::new(&self.warnings()).inline_html("warnings")
+                    ),
+                    false => String::new(),
+                },
+            ),
+        ];
+
+        let mut html = include_str!("./templates/single_file_report_template.html").to_string();
+        for (key, component) in components.iter() {
+            html = html.replace(key, component);
+        }
+        // NOTE: inserted last, so that the large library is not searched for the other keys
+        html = html.replace("$PLOTLY_JS$", &plotly_js());
+
+        fs::write(path.join("report.html"), html)?;
+        Ok(())
+    }
+
+    /// Logs the verdict and writes the comparison against the baseline into `comparison.json`.
+    fn compare_to_baseline(
+        &self,
+        data_dir: &Path,
+        current_stats: &StatsSummary,
+        baseline_stats: &StatsSummary,
+    ) -> BurlResult<Option<ComparisonReport>> {
+        let tester = StatisticalTester::try_new(current_stats, baseline_stats).map(|tester| {
+            tester
+                .with_min_samples(self.config.min_samples())
+                .with_primary_metric(self.config.primary_metric())
+        });
+        if let Some(warning) = tester
+            .as_ref()
+            .and_then(|tester| tester.sample_size_warning(self.config.max_sample_ratio()))
+        {
+            self.warnings.warn(warning);
+            if self.config.refuse_unbalanced_comparison() {
+                return Ok(None);
+            }
+        }
+        match tester {
+            Some(tester) => {
+                let (n_bootstrap_samples, alpha) =
+                    (self.config.n_bootstrap_samples(), self.config.alpha());
+                info!(
+                    "Summary of the current run (vs. the baseline):{}",
+                    current_stats
+                        .display(self.config.display_precision())
+                        .with_baseline(Some(baseline_stats))
+                );
+                info!(
+                    "Performance of the current run (vs. the baseline, alpha = {}):\n{}",
+                    alpha,
+                    tester.verdict(n_bootstrap_samples, alpha)
+                );
+                let comparison = ComparisonReport::from_tester(&tester, n_bootstrap_samples, alpha);
+                write_or_update(&comparison, data_dir.join("comparison.json"))?;
+                Ok(Some(comparison))
             }
             None => {
-                box_plot.show();
-                time_series_plot.show();
-                histogram.show();
+                self.warnings.warn(
+                    "The baseline cannot be compared due to different time scales".to_string(),
+                );
+                Ok(None)
             }
         }
+    }
 
-        Ok(())
+    /// Compares the current run against the last `n_runs` archived runs; see `history_runs`.
+    fn compare_to_history(
+        &self,
+        data_dir: &Path,
+        current_stats: &StatsSummary,
+        n_runs: usize,
+    ) -> BurlResult<Option<HistoryBand>> {
+        let history = archived_stats(data_dir, n_runs)?;
+        let Some(band) = HistoryBand::new(current_stats, &history) else {
+            self.warnings.warn(format!(
+                "Too few archived runs ({}) to compare against the history",
+                history.len()
+            ));
+            return Ok(None);
+        };
+
+        match band.regressed {
+            true => self.warnings.warn(band.to_string()),
+            false => info!("{}", band),
+        }
+        write_or_update(&band, data_dir.join("history.json"))?;
+        Ok(Some(band))
     }
 
     pub fn create_report(&self) -> Result<(), BurlError> {
         let current_results: Option<StatsSummary> = self.stats_processor.stats_summary();
         let sample_results_by_thread = self.stats_processor.sample_results_by_thread();
 
+        let n_samples = sample_results_by_thread.values().map(Vec::len).sum();
+        info!(
+            "The {} samples take about {} KB of memory in the report",
+            n_samples,
+            estimated_memory_footprint(n_samples) / (1 << 10)
+        );
+        if let Some(warning) = memory_warning(n_samples, MEMORY_WARNING_BYTES) {
+            self.warnings.warn(warning);
+        }
+        if let Some(skew) = start_skew(&sample_results_by_thread) {
+            let scale = self.config.duration_scale();
+            info!(
+                "The threads started within {} {}s (from {} to {}) of a run of {} {}s",
+                skew.spread(),
+                scale,
+                skew.first,
+                skew.last,
+                skew.run_duration,
+                scale
+            );
+            // NOTE: deterministic runs sample the threads one after another
+            if skew.is_large(START_SKEW_WARNING_SHARE) && !self.config.deterministic() {
+                self.warnings.warn(format!(
+                    "The threads did not start concurrently (a spread of {:.0}% of the run), \
+                     e.g. due to the contention for connections; consider `prewarm_connections`",
+                    skew.spread() / skew.run_duration * 100.0
+                ));
+            }
+        }
+        let measured_requests: Vec<(f64, f64)> = sample_results_by_thread
+            .values()
+            .flatten()
+            .filter(|sr| !sr.discarded)
+            .map(|sr| sr.as_timeseries_point())
+            .collect();
+        if let Some(concurrency) = average_concurrency(&in_flight_curve(&measured_requests)) {
+            info!(
+                "The average concurrency was {:.2} requests in flight (of {} configured threads)",
+                concurrency,
+                self.config.n_threads()
+            );
+        }
+
         if let Some(report_path) = &self.config.report_directory {
             let path = Path::new(report_path);
-            let (components_dir, data_dir) = setup_report_structure(path)?;
+            let data_dir = setup_report_structure(path)?;
 
             let baseline_results: Option<StatsSummary> = self.baseline_results(&data_dir);
-            self.dump_data(data_dir, &current_results, &sample_results_by_thread)?;
-            self.create_components(
-                Some(components_dir),
-                &current_results,
-                baseline_results,
-                &sample_results_by_thread,
-            )?;
+            if !self.replay {
+                self.dump_data(
+                    data_dir.clone(),
+                    &current_results,
+                    &sample_results_by_thread,
+                )?;
+            }
+            let comparison = match (&current_results, &baseline_results) {
+                (Some(current), Some(baseline)) => {
+                    self.compare_to_baseline(&data_dir, current, baseline)?
+                }
+                _ => None,
+            };
+            if let (Some(n_runs), Some(stats)) = (self.config.history_runs, &current_results) {
+                self.compare_to_history(&data_dir, stats, n_runs)?;
+            }
+            if self.config.report_warnings() {
+                write_or_update(&self.warnings(), data_dir.join("warnings.json"))?;
+            }
+            if self.config.run_json() {
+                let run = RunExport {
+                    start_time: format!("{}", self.start_time.format(FORMAT)),
+                    end_time: format!("{}", self.end_time.format(FORMAT)),
+                    config: self.config.redacted(),
+                    stats: current_results.as_ref(),
+                    failures: self
+                        .stats_processor
+                        .failures_by_kind()
+                        .into_iter()
+                        .map(|(kind, count)| (kind.to_string(), count))
+                        .collect(),
+                    verdict: comparison.as_ref(),
+                    warnings: self.warnings(),
+                };
+                write_or_update(&run, data_dir.join("run.json"))?;
+            }
+            if let Some(stats) = &current_results {
+                self.write_qq_data(&data_dir, stats, baseline_results.as_ref())?;
+            }
+            if let (true, Some(stats)) = (self.config.prometheus_export(), &current_results) {
+                let metrics = prometheus_metrics(stats, &self.config.run_label());
+                fs::write(data_dir.join("metrics.prom"), metrics)?;
+            }
+            if let (true, Some(stats)) = (self.config.criterion_export(), &current_results) {
+                let estimates = criterion_estimates(
+                    stats,
+                    self.config.n_bootstrap_draw_size(),
+                    self.config.n_bootstrap_samples(),
+                    self.config.alpha(),
+                );
+                write_or_update(&estimates, data_dir.join("estimates.json"))?;
+            }
+            if !self.config.single_file_report() {
+                self.create_components(
+                    Some(setup_components(path)?),
+                    &current_results,
+                    baseline_results,
+                    &sample_results_by_thread,
+                )?;
+            } else if let Some(stats) = &current_results {
+                self.create_single_file_report(
+                    path,
+                    stats,
+                    baseline_results.as_ref(),
+                    &sample_results_by_thread,
+                )?;
+            }
+            write_index(path)?;
         } else {
             self.create_components(None, &current_results, None, &sample_results_by_thread)?;
         }
@@ -293,6 +1022,641 @@ impl<'a> ReportFactory<'a> {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use burl::stats::ThreadStats;
+    use burl::StatsConfig;
+
+    fn stats_summary(durations: Vec<f64>) -> StatsSummary {
+        let mut stats_by_thread = HashMap::new();
+        stats_by_thread.insert(0, ThreadStats::default());
+        StatsSummary::calculate(
+            burl::DurationScale::Milli,
+            0,
+            0,
+            durations,
+            HashMap::new(),
+            stats_by_thread,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn create_components() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = BenchClientConfig::new("http://localhost".to_string());
+        let report = ReportFactory::new(
+            Utc::now(),
+            Utc::now(),
+            &config,
+            StatsProcessor::new(burl::DurationScale::Milli, Vec::new()),
+        );
+
+        let stats = stats_summary((0..200).map(|idx| (idx % 13) as f64).collect());
+        report
+            .create_components(
+                Some(dir.path().to_path_buf()),
+                &Some(stats.clone()),
+                Some(stats),
+                &HashMap::new(),
+            )
+            .unwrap();
+
+        for file_name in [
+            "summary.html",
+            "durations_distribution.html",
+            "durations_timeseries.html",
+            "durations_histogram.html",
+            "qq_plot.html",
+            "bootstrap_histogram.html",
+        ] {
+            let file = dir.path().join(file_name);
+            assert!(file.exists(), "{} is missing", file_name);
+            assert!(fs::metadata(file).unwrap().len() > 0);
+        }
+    }
+
+    #[test]
+    fn offline_plots() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = BenchClientConfig::new("http://localhost".to_string());
+        config.offline_plots = Some(true);
+        let report = ReportFactory::new(
+            Utc::now(),
+            Utc::now(),
+            &config,
+            StatsProcessor::new(burl::DurationScale::Milli, Vec::new()),
+        );
+
+        let stats = stats_summary((0..200).map(|idx| (idx % 13) as f64).collect());
+        report
+            .create_components(
+                Some(dir.path().to_path_buf()),
+                &Some(stats),
+                None,
+                &HashMap::new(),
+            )
+            .unwrap();
+
+        let library = fs::read_to_string(dir.path().join(PLOTLY_JS_FILE)).unwrap();
+        assert!(library.contains("plotly.js"));
+        for file_name in file_names(dir.path()).unwrap() {
+            if !file_name.ends_with(".html") {
+                continue;
+            }
+            let html = fs::read_to_string(dir.path().join(&file_name)).unwrap();
+            assert!(!html.contains("https://"), "{} loads from a CDN", file_name);
+            if !["summary.html", "warnings.html"].contains(&file_name.as_str()) {
+                assert!(html.contains(r#"<script src="./plotly.min.js"></script>"#));
+            }
+        }
+    }
+
+    /// A successful sample started at `start` of the run.
+    fn sample_result(start: f64, duration: f64) -> SampleResult {
+        SampleResult {
+            measurement_start: start,
+            measurement_end: start + duration,
+            duration,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sorted_samples() {
+        let sample = |start: f64| sample_result(start, 5.0);
+        // e.g. the samples of a continued run, appended to the prior ones
+        let threads = [
+            (2, vec![30.0, 10.0]),
+            (0, vec![20.0, 0.0, 10.0]),
+            (1, vec![5.0]),
+        ];
+        let samples_by_thread = |order: &[usize]| -> HashMap<ThreadIdx, Vec<SampleResult>> {
+            order
+                .iter()
+                .map(|idx| {
+                    let (thread_idx, starts) = &threads[*idx];
+                    (
+                        *thread_idx,
+                        starts.iter().map(|start| sample(*start)).collect(),
+                    )
+                })
+                .collect()
+        };
+        let dump = |samples: &HashMap<ThreadIdx, Vec<SampleResult>>| {
+            serialize(&ordered_samples(samples, true)).unwrap()
+        };
+
+        let samples = samples_by_thread(&[0, 1, 2]);
+        let json = dump(&samples);
+        assert_eq!(json, dump(&samples_by_thread(&[2, 1, 0])));
+        assert_eq!(json, dump(&samples_by_thread(&[1, 2, 0])));
+
+        let dumped: BTreeMap<ThreadIdx, Vec<SampleResult>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(dumped.keys().copied().collect::<Vec<_>>(), [0, 1, 2]);
+        let starts = |thread_idx: ThreadIdx| -> Vec<f64> {
+            dumped[&thread_idx]
+                .iter()
+                .map(|sample| sample.measurement_start)
+                .collect()
+        };
+        assert_eq!(starts(0), [0.0, 10.0, 20.0]);
+        assert_eq!(starts(2), [10.0, 30.0]);
+
+        // unless sorted, the samples of each thread keep their order
+        let unsorted = ordered_samples(&samples, false);
+        assert_eq!(unsorted[&0][0].measurement_start, 20.0);
+    }
+
+    /// Creates a report of 3 threads with 100 samples each in the directory.
+    fn create_run_report(dir: &Path, duration_offset: f64) -> StatsSummary {
+        let mut config = BenchClientConfig::new("http://localhost".to_string());
+        config.report_directory = Some(dir.to_string_lossy().to_string());
+        config.run_json = Some(true);
+
+        let sample = |thread_idx: usize, idx: usize| {
+            let start = (idx * 20) as f64;
+            let duration = ((idx * 7 + thread_idx) % 13) as f64 + 10.0 + duration_offset;
+            SampleResult {
+                content_length: Some(10),
+                ..sample_result(start, duration)
+            }
+        };
+        let sample_results_by_thread = (0..3)
+            .map(|thread_idx| {
+                let samples = (0..100).map(|idx| sample(thread_idx, idx)).collect();
+                (thread_idx, samples)
+            })
+            .collect();
+        let stats_processor =
+            StatsProcessor::from_sample_results(&config, sample_results_by_thread);
+        let stats = stats_processor.stats_summary().unwrap();
+        ReportFactory::new(Utc::now(), Utc::now(), &config, stats_processor)
+            .create_report()
+            .unwrap();
+        stats
+    }
+
+    #[test]
+    fn replay_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let stats = create_run_report(dir.path(), 0.0);
+
+        // regenerate the report from the dumped samples
+        let components_dir = dir.path().join(COMPONENTS_DIR);
+        fs::remove_dir_all(&components_dir).unwrap();
+        let replayed_run = ReplayedRun::load(dir.path()).unwrap();
+        let report_factory = replayed_run.report_factory();
+        report_factory.create_report().unwrap();
+        assert!(components_dir.join("summary.html").exists());
+        assert!(components_dir.join("qq_plot.html").exists());
+
+        let replayed_stats = report_factory.stats_processor.stats_summary().unwrap();
+        assert_eq!(
+            serialize(&replayed_stats).unwrap(),
+            serialize(&stats).unwrap()
+        );
+        // the dumped data is kept
+        assert!(!dir.path().join(DATA_DIR).join(HIST_PATH).exists());
+    }
+
+    #[test]
+    fn report_index() {
+        let dir = tempfile::tempdir().unwrap();
+        create_run_report(dir.path(), 0.0);
+        // the second run archives the data of the first one
+        create_run_report(dir.path(), 5.0);
+
+        let index = fs::read_to_string(dir.path().join("index.html")).unwrap();
+        assert!(index.contains(r#"href="./report.html""#));
+        let components = file_names(&dir.path().join(COMPONENTS_DIR)).unwrap();
+        assert!(components.contains(&"qq_plot.html".to_string()));
+        for name in components {
+            assert!(
+                index.contains(&format!(r#"href="./components/{}""#, name)),
+                "{} is not linked",
+                name
+            );
+        }
+        assert!(index.contains(r#"href="./data/stats.json""#));
+
+        let hist_dir = dir.path().join(DATA_DIR).join(HIST_PATH);
+        let runs: Vec<_> = fs::read_dir(&hist_dir).unwrap().collect();
+        assert_eq!(runs.len(), 1);
+        let run = runs[0].as_ref().unwrap().file_name();
+        let run = run.to_string_lossy();
+        assert!(index.contains(&format!(r#"href="./data/hist/{}/stats.json""#, run)));
+        assert!(index.contains("http://localhost ("));
+    }
+
+    #[test]
+    fn resolved_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = BenchClientConfig::new("http://localhost:8080/items?id=1".to_string());
+        config.report_directory = Some(dir.path().to_string_lossy().to_string());
+        config.bearer_token = Some("secret-token".to_string());
+        config.headers = Some(vec![(
+            "Cookie".to_string(),
+            "session=secret-session".to_string(),
+        )]);
+        config.run_json = Some(true);
+        ReportFactory::new(
+            Utc::now(),
+            Utc::now(),
+            &config,
+            StatsProcessor::new(burl::DurationScale::Milli, Vec::new()),
+        )
+        .create_report()
+        .unwrap();
+
+        let file = dir.path().join(DATA_DIR).join("request.json");
+        let request: ResolvedRequest = read_data(&file).unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.url, "http://localhost:8080/items?id=1");
+        assert!(request
+            .headers
+            .contains(&("authorization".to_string(), "<redacted>".to_string())));
+        assert!(!fs::read_to_string(file).unwrap().contains("secret-token"));
+        assert_eq!(request.body_sha256, None);
+
+        // nor are the secrets of the config written to the meta data
+        for file in ["meta.json", "run.json"] {
+            let content = fs::read_to_string(dir.path().join(DATA_DIR).join(file)).unwrap();
+            assert!(!content.contains("secret-token") && !content.contains("secret-session"));
+        }
+    }
+
+    /// The files in the directory and its subdirectories.
+    fn files_in(dir: &Path) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                files.extend(files_in(&path));
+            } else {
+                files.push(path);
+            }
+        }
+        files
+    }
+
+    #[test]
+    fn redacted_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = BenchClientConfig::new("http://localhost:8080".to_string());
+        config.report_directory = Some(dir.path().to_string_lossy().to_string());
+        config.bearer_token = Some("s3cr3t-token".to_string());
+        config.headers = Some(vec![
+            ("Authorization".to_string(), "Basic s3cr3t-auth".to_string()),
+            ("Cookie".to_string(), "session=s3cr3t-session".to_string()),
+        ]);
+        config.run_json = Some(true);
+
+        // the second report archives the data of the first one
+        for _ in 0..2 {
+            let samples = (0..20)
+                .map(|idx| sample_result((idx * 20) as f64, (idx % 7) as f64 + 10.0))
+                .collect();
+            let stats_processor =
+                StatsProcessor::from_sample_results(&config, HashMap::from([(0, samples)]));
+            ReportFactory::new(Utc::now(), Utc::now(), &config, stats_processor)
+                .create_report()
+                .unwrap();
+        }
+
+        let files = files_in(&dir.path().join(DATA_DIR));
+        for name in ["meta.json", "request.json", "samples.json"] {
+            assert!(files.contains(&dir.path().join(DATA_DIR).join(name)));
+        }
+        for file in files {
+            let content = fs::read_to_string(&file).unwrap();
+            for secret in ["s3cr3t-token", "s3cr3t-auth", "s3cr3t-session"] {
+                assert!(!content.contains(secret), "{:?} contains {}", file, secret);
+            }
+        }
+    }
+
+    #[test]
+    fn history_band() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join(DATA_DIR);
+        // archived runs with means of 10 to 12, besides an outdated one of 100
+        for (idx, offset) in [90.0, 0.0, 1.0, 2.0, 0.5, 1.5].iter().enumerate() {
+            let run_dir = data_dir
+                .join(HIST_PATH)
+                .join(format!("2024-01-0{}__12_00_00", idx + 1));
+            create_dir(&run_dir).unwrap();
+            let stats = stats_summary(
+                (0..100)
+                    .map(|idx| 9.0 + offset + (idx % 3) as f64)
+                    .collect(),
+            );
+            write_or_update(&Some(stats), run_dir.join("stats.json")).unwrap();
+        }
+        assert_eq!(archived_stats(&data_dir, 5).unwrap().len(), 5);
+        assert_eq!(archived_stats(&data_dir, 10).unwrap().len(), 6);
+
+        let config = BenchClientConfig::new("http://localhost".to_string());
+        let report = ReportFactory::new(
+            Utc::now(),
+            Utc::now(),
+            &config,
+            StatsProcessor::new(burl::DurationScale::Milli, Vec::new()),
+        );
+
+        let usual = stats_summary((0..100).map(|idx| 10.0 + (idx % 3) as f64).collect());
+        let band = report
+            .compare_to_history(&data_dir, &usual, 5)
+            .unwrap()
+            .unwrap();
+        assert_eq!(band.n_runs, 5);
+        assert!((band.median - 10.99).abs() < 1e-9);
+        assert!(!band.regressed);
+
+        let spike = stats_summary((0..100).map(|idx| 15.0 + (idx % 3) as f64).collect());
+        let band = report
+            .compare_to_history(&data_dir, &spike, 5)
+            .unwrap()
+            .unwrap();
+        assert!(band.regressed);
+        assert!(band.upper_fence < 15.0);
+        assert!(data_dir.join("history.json").exists());
+
+        // the band is robust to the outlying outdated run
+        let band = report
+            .compare_to_history(&data_dir, &spike, 6)
+            .unwrap()
+            .unwrap();
+        assert_eq!(band.n_runs, 6);
+        assert!(band.regressed);
+    }
+
+    #[test]
+    fn static_images() {
+        let dir = tempfile::tempdir().unwrap();
+        create_run_report(dir.path(), 0.0);
+        let components_dir = dir.path().join(COMPONENTS_DIR);
+        assert!(!components_dir.join("qq_plot.png").exists());
+
+        let mut replayed_run = ReplayedRun::load(dir.path()).unwrap();
+        replayed_run.config.static_images = Some(true);
+        let mut report_factory = replayed_run.report_factory();
+        report_factory.image_exporter = |_, file| fs::write(file, b"png").is_ok();
+        report_factory.create_report().unwrap();
+
+        for plot in ["durations_distribution", "durations_timeseries", "qq_plot"] {
+            assert!(components_dir.join(plot).with_extension("png").exists());
+        }
+        assert!(!components_dir.join("summary.png").exists());
+    }
+
+    #[test]
+    fn compare_report_dirs() {
+        let (release_a, release_b) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        create_run_report(release_a.path(), 0.0);
+        create_run_report(release_b.path(), 5.0);
+
+        let output = tempfile::tempdir().unwrap();
+        let comparison_dir = output.path().join("comparison");
+        let run = ReplayedRun::load_comparison(release_a.path(), release_b.path(), &comparison_dir)
+            .unwrap();
+        run.report_factory().create_report().unwrap();
+
+        let summary =
+            fs::read_to_string(comparison_dir.join(COMPONENTS_DIR).join("summary.html")).unwrap();
+        assert!(summary.contains("regressed"));
+        let comparison: serde_json::Value =
+            read_data(&comparison_dir.join(DATA_DIR).join("comparison.json")).unwrap();
+        assert!(comparison["permutation_outcome"]["Regressed"].is_object());
+        assert!(comparison["mean"]["absolute"].as_f64().unwrap() > 4.9);
+        assert!(comparison_dir.join(DATA_DIR).join("samples.json").exists());
+
+        // the compared directories are not modified
+        assert!(!release_b
+            .path()
+            .join(DATA_DIR)
+            .join("comparison.json")
+            .exists());
+        assert!(
+            ReplayedRun::load_comparison(output.path(), release_b.path(), output.path()).is_err()
+        );
+    }
+
+    #[test]
+    fn qq_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = BenchClientConfig::new("http://localhost".to_string());
+        config.stats_config = Some(StatsConfig {
+            n_qq_points: Some(25),
+            ..Default::default()
+        });
+        let report = ReportFactory::new(
+            Utc::now(),
+            Utc::now(),
+            &config,
+            StatsProcessor::new(burl::DurationScale::Milli, Vec::new()),
+        );
+
+        let stats = stats_summary((0..200).map(|idx| (idx % 13) as f64).collect());
+        report.write_qq_data(dir.path(), &stats, None).unwrap();
+        let qq: serde_json::Value = read_data(&dir.path().join("qq.json")).unwrap();
+        let current = qq["current"].as_array().unwrap();
+        assert_eq!(current.len(), 25);
+        assert!(current
+            .iter()
+            .all(|point| point.as_array().unwrap().len() == 2));
+        assert!(qq.get("baseline").is_none());
+
+        report
+            .write_qq_data(dir.path(), &stats, Some(&stats))
+            .unwrap();
+        let qq: serde_json::Value = read_data(&dir.path().join("qq.json")).unwrap();
+        assert_eq!(qq["baseline"].as_array().unwrap().len(), 25);
+    }
+
+    #[test]
+    fn config_fingerprint() {
+        let config: BenchClientConfig =
+            serde_json::from_str(r#"{ "url": "http://localhost", "n_runs": 10, "method": "Get" }"#)
+                .unwrap();
+        let reordered: BenchClientConfig =
+            serde_json::from_str(r#"{ "method": "Get", "n_runs": 10, "url": "http://localhost" }"#)
+                .unwrap();
+        let fingerprint = super::config_fingerprint(&config);
+        assert_eq!(fingerprint.len(), 16);
+        assert_eq!(fingerprint, super::config_fingerprint(&config.clone()));
+        assert_eq!(fingerprint, super::config_fingerprint(&reordered));
+
+        let mut changed = config.clone();
+        changed.allow_get_body = Some(true);
+        assert_ne!(fingerprint, super::config_fingerprint(&changed));
+
+        // the same for the stored (redacted) config, e.g. of a continued run
+        let mut with_secret = config.clone();
+        with_secret.bearer_token = Some("s3cr3t".to_string());
+        assert_eq!(
+            super::config_fingerprint(&with_secret),
+            super::config_fingerprint(&with_secret.redacted())
+        );
+
+        // embedded in the meta data of the report
+        let dir = tempfile::tempdir().unwrap();
+        create_run_report(dir.path(), 0.0);
+        let meta: serde_json::Value =
+            read_data(&dir.path().join(DATA_DIR).join("meta.json")).unwrap();
+        let config: BenchClientConfig = serde_json::from_value(meta["config"].clone()).unwrap();
+        assert_eq!(meta["fingerprint"], super::config_fingerprint(&config));
+    }
+
+    #[test]
+    fn unbalanced_comparison() {
+        let dir = tempfile::tempdir().unwrap();
+        let current = stats_summary((0..10).map(|idx| 12.0 + (idx % 3) as f64).collect());
+        let baseline = stats_summary((0..1_000).map(|idx| 10.0 + (idx % 3) as f64).collect());
+
+        let mut config = BenchClientConfig::new("http://localhost".to_string());
+        let report = |config: &BenchClientConfig| {
+            ReportFactory::new(
+                Utc::now(),
+                Utc::now(),
+                config,
+                StatsProcessor::new(burl::DurationScale::Milli, Vec::new()),
+            )
+            .compare_to_baseline(dir.path(), &current, &baseline)
+            .unwrap()
+        };
+        // warned only by default
+        assert!(report(&config).is_some());
+        fs::remove_file(dir.path().join("comparison.json")).unwrap();
+
+        config.stats_config = Some(StatsConfig {
+            refuse_unbalanced_comparison: Some(true),
+            ..Default::default()
+        });
+        assert!(report(&config).is_none());
+        assert!(!dir.path().join("comparison.json").exists());
+    }
+
+    #[test]
+    fn report_warnings() {
+        let (dir, baseline_dir) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let mut config = BenchClientConfig::new("http://localhost".to_string());
+        config.report_directory = Some(dir.path().to_string_lossy().to_string());
+        config.baseline_path = Some(baseline_dir.path().to_string_lossy().to_string());
+        config.run_json = Some(true);
+        let sample = |idx: usize| sample_result((idx * 20) as f64, 12.0 + (idx % 3) as f64);
+        let stats_processor = |n_samples: usize| {
+            let samples = HashMap::from([(0, (0..n_samples).map(sample).collect())]);
+            StatsProcessor::from_sample_results(&config, samples)
+        };
+        let baseline = stats_processor(1_000).stats_summary();
+        write_or_update(&baseline, baseline_dir.path().join("stats.json")).unwrap();
+
+        let run_warning = "The max run time is reached during the warmup".to_string();
+        let current = stats_processor(10).with_warnings(vec![run_warning.clone()]);
+        let report = ReportFactory::new(Utc::now(), Utc::now(), &config, current);
+        report.create_report().unwrap();
+
+        // the warning of the run, followed by the unbalanced comparison of 10 vs. 1000 samples
+        let warnings: Vec<String> =
+            read_data(&dir.path().join(DATA_DIR).join("warnings.json")).unwrap();
+        assert_eq!(warnings, report.warnings());
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0], run_warning);
+        assert!(
+            warnings[1].contains("10 samples vs. 1000"),
+            "{}",
+            warnings[1]
+        );
+        let run: serde_json::Value =
+            read_data(&dir.path().join(DATA_DIR).join("run.json")).unwrap();
+        assert_eq!(run["warnings"][1], warnings[1].as_str());
+        let html =
+            fs::read_to_string(dir.path().join(COMPONENTS_DIR).join("warnings.html")).unwrap();
+        assert!(html.contains("<li>The current run has 10 samples"));
+
+        // a missing baseline
+        let mut missing_baseline = config.clone();
+        missing_baseline.baseline_path =
+            Some(dir.path().join("missing").to_string_lossy().to_string());
+        let current = stats_processor(10);
+        let report = ReportFactory::new(Utc::now(), Utc::now(), &missing_baseline, current);
+        report.create_report().unwrap();
+        let warnings: Vec<String> =
+            read_data(&dir.path().join(DATA_DIR).join("warnings.json")).unwrap();
+        assert!(warnings
+            .iter()
+            .any(|warning| warning.starts_with("Specified baseline directory does not exist")));
+    }
+
+    #[test]
+    fn run_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let run_file = dir.path().join(DATA_DIR).join("run.json");
+        create_run_report(dir.path(), 0.0);
+        let run: serde_json::Value = read_data(&run_file).unwrap();
+        assert!(run["verdict"].is_null());
+
+        // the second run is compared against the first as its baseline
+        let stats = create_run_report(dir.path(), 5.0);
+        let run: serde_json::Value = read_data(&run_file).unwrap();
+        for section in [
+            "start_time",
+            "end_time",
+            "config",
+            "stats",
+            "failures",
+            "verdict",
+        ] {
+            assert!(run.get(section).is_some(), "{} is missing", section);
+        }
+        assert_eq!(run["config"]["url"], "http://localhost");
+        assert_eq!(run["stats"]["n_ok"], stats.n_ok);
+        assert!(run["failures"].as_object().unwrap().is_empty());
+        assert!(run["verdict"]["permutation_outcome"]["Regressed"].is_object());
+    }
+
+    #[test]
+    fn single_file_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = BenchClientConfig::new("http://localhost".to_string());
+        let report = ReportFactory::new(
+            Utc::now(),
+            Utc::now(),
+            &config,
+            StatsProcessor::new(burl::DurationScale::Milli, Vec::new()),
+        );
+
+        let stats = stats_summary((0..200).map(|idx| (idx % 13) as f64).collect());
+        report
+            .create_single_file_report(dir.path(), &stats, Some(&stats), &HashMap::new())
+            .unwrap();
+
+        let html = fs::read_to_string(dir.path().join("report.html")).unwrap();
+        let library = plotly_js();
+        assert!(library.contains("plotly.js"));
+        assert!(html.contains(&library));
+        let html = html.replace(&library, "");
+        assert!(html.contains("Plotly.newPlot('qq_plot'"));
+        assert!(html.contains(r#"<iframe id="summary" srcdoc=""#));
+        for key in [
+            "$PLOTLY_JS$",
+            "$SUMMARY$",
+            "$QQ_PLOT$",
+            "$BOOTSTRAP_HISTOGRAM$",
+            "$WARNINGS$",
+        ] {
+            assert!(!html.contains(key), "{} is not replaced", key);
+        }
+        // no references to component or data files
+        assert!(!html.contains(COMPONENTS_DIR));
+        assert!(!html.contains(" src="));
+        assert!(!html.contains(" href="));
+        assert!(!dir.path().join(COMPONENTS_DIR).exists());
+    }
+}
+
 // TODO: rename to Html? and to TableComponent below?
 // pub trait ReportComponent {
 //     type Content;
@@ -1,8 +1,18 @@
-use burl::stats::{AnalyticTester, NormalParams, PermutationTester, StatsSummary, TestOutcome};
+use burl::sampling::SampleResult;
+use burl::stats::{
+    AnalyticTester, BootstrapPercentileTester, PermutationTester, PrimaryMetric, StatsSummary,
+    TestOutcome, MIN_SAMPLES, NORMALITY_ALPHA,
+};
+use burl::ThreadIdx;
+use std::collections::HashMap;
 
 pub(crate) struct StatisticalTester<'a> {
     pub(crate) current_stats: &'a StatsSummary,
     pub(crate) baseline_stats: &'a StatsSummary,
+    /// The minimal number of samples of each run for the analytic test.
+    min_samples: usize,
+    /// The statistic of the headline verdict, see `primary_test`.
+    pub(crate) primary_metric: PrimaryMetric,
 }
 
 impl<'a> StatisticalTester<'a> {
@@ -16,9 +26,39 @@ impl<'a> StatisticalTester<'a> {
         Some(Self {
             current_stats,
             baseline_stats,
+            min_samples: MIN_SAMPLES,
+            primary_metric: PrimaryMetric::default(),
         })
     }
 
+    pub(crate) fn with_min_samples(mut self, min_samples: usize) -> Self {
+        self.min_samples = min_samples;
+        self
+    }
+
+    pub(crate) fn with_primary_metric(mut self, primary_metric: PrimaryMetric) -> Self {
+        self.primary_metric = primary_metric;
+        self
+    }
+
+    /// The headline verdict on the primary metric: the analytic test for the mean,
+    /// the bootstrap comparison for a percentile.
+    pub(crate) fn primary_test(
+        &self,
+        n_bootstrap_samples: usize,
+        alpha: f64,
+    ) -> Option<TestOutcome> {
+        match self.primary_metric.percentile_level() {
+            None => self.analytic_test(alpha),
+            Some(level) => BootstrapPercentileTester::new(
+                &self.current_stats.durations,
+                &self.baseline_stats.durations,
+                level,
+            )
+            .test(n_bootstrap_samples, alpha),
+        }
+    }
+
     pub(crate) fn performance_test(
         &self,
         n_bootstrap_samples: usize,
@@ -31,10 +71,463 @@ impl<'a> StatisticalTester<'a> {
         permutation_tester.test(n_bootstrap_samples, alpha)
     }
 
+    /// Skipped (`None`) if a run has less than `min_samples` samples or no std.
     pub(crate) fn analytic_test(&self, alpha: f64) -> Option<TestOutcome> {
-        let current_normal = NormalParams::from(self.current_stats);
-        let baseline_normal = NormalParams::from(self.baseline_stats);
+        if self.current_stats.n_ok < self.min_samples || self.baseline_stats.n_ok < self.min_samples
+        {
+            return None;
+        }
+        let current_normal = self.current_stats.normal_params()?;
+        let baseline_normal = self.baseline_stats.normal_params()?;
         let analytic_test = AnalyticTester::new(&baseline_normal, &current_normal);
         analytic_test.test(alpha)
     }
+
+    /// A warning if the sample sizes of the runs differ by more than the `max_ratio` (either way),
+    /// as the tests assume runs of comparable power.
+    pub(crate) fn sample_size_warning(&self, max_ratio: f64) -> Option<String> {
+        let (n_current, n_baseline) = (self.current_stats.n_ok, self.baseline_stats.n_ok);
+        let ratio = n_current.max(n_baseline) as f64 / n_current.min(n_baseline).max(1) as f64;
+        (ratio > max_ratio).then(|| {
+            format!(
+                "The current run has {} samples vs. {} of the baseline (a ratio of {:.1} above {}); \
+                 the verdict is unreliable",
+                n_current, n_baseline, ratio, max_ratio
+            )
+        })
+    }
+
+    /// The outcomes of both tests as plain text, e.g. for the console.
+    pub(crate) fn verdict(&self, n_bootstrap_samples: usize, alpha: f64) -> String {
+        let display = |outcome: Option<TestOutcome>| match outcome {
+            Some(outcome) => outcome.to_string(),
+            None => "could not be determined".to_string(),
+        };
+        let mut verdict = format!(
+            "[Primary: {}] {}\n[Analytical Test] {}\n[Permutation Test] {}",
+            self.primary_metric,
+            display(self.primary_test(n_bootstrap_samples, alpha)),
+            display(self.analytic_test(alpha)),
+            display(self.performance_test(n_bootstrap_samples, alpha))
+        );
+        if let Some(note) = self.normality_note() {
+            verdict.push('\n');
+            verdict.push_str(&note);
+        }
+        verdict
+    }
+
+    /// A recommendation to trust the permutation test, if the durations of a run are not normal.
+    pub(crate) fn normality_note(&self) -> Option<String> {
+        let runs = [
+            ("current", self.current_stats),
+            ("baseline", self.baseline_stats),
+        ];
+        let non_normal: Vec<String> = runs
+            .iter()
+            .filter_map(|(run, stats)| {
+                let normality = stats.normality_test()?;
+                (!normality.is_normal(NORMALITY_ALPHA)).then(|| format!("{} ({})", run, normality))
+            })
+            .collect();
+        (!non_normal.is_empty()).then(|| {
+            format!(
+                "[Normality] the durations of the {} are not normal; prefer the permutation test",
+                non_normal.join(" and ")
+            )
+        })
+    }
+}
+
+/// The copies of the durations held per sample, e.g. in the stats and each of the plots.
+const DURATION_COPIES: usize = 8;
+/// The estimated footprint above which the report warns, see `memory_warning`.
+pub(crate) const MEMORY_WARNING_BYTES: usize = 1 << 30;
+
+/// A rough estimate of the memory (in bytes) of the collected samples and their report.
+pub(crate) fn estimated_memory_footprint(n_samples: usize) -> usize {
+    let bytes_per_sample =
+        std::mem::size_of::<SampleResult>() + DURATION_COPIES * std::mem::size_of::<f64>();
+    n_samples * bytes_per_sample
+}
+
+/// A warning if the estimated memory footprint of the samples exceeds the `threshold` (in bytes).
+pub(crate) fn memory_warning(n_samples: usize, threshold: usize) -> Option<String> {
+    let footprint = estimated_memory_footprint(n_samples);
+    (footprint > threshold).then(|| {
+        format!(
+            "The {} samples take about {} MB of memory in the report; \
+             consider fewer `n_runs` or splitting the benchmark, as all samples are kept in memory",
+            n_samples,
+            footprint / (1 << 20)
+        )
+    })
+}
+
+/// The share of the run above which the spread of the thread starts is warned about.
+pub(crate) const START_SKEW_WARNING_SHARE: f64 = 0.1;
+
+/// The spread of the first measurement starts of the threads, e.g. to validate that the
+/// threads sampled concurrently rather than serialized by the contention for connections.
+#[derive(Debug, PartialEq)]
+pub(crate) struct StartSkew {
+    /// The first start of the earliest thread.
+    pub(crate) first: f64,
+    /// The first start of the latest thread.
+    pub(crate) last: f64,
+    /// The time from the first start until the last end of a request over all threads.
+    pub(crate) run_duration: f64,
+}
+
+impl StartSkew {
+    pub(crate) fn spread(&self) -> f64 {
+        self.last - self.first
+    }
+
+    /// Whether the spread exceeds the `share` of the run duration.
+    pub(crate) fn is_large(&self, share: f64) -> bool {
+        self.spread() > share * self.run_duration
+    }
+}
+
+/// The skew of the thread starts; `None` for less than two threads with samples.
+pub(crate) fn start_skew(
+    sample_results_by_thread: &HashMap<ThreadIdx, Vec<SampleResult>>,
+) -> Option<StartSkew> {
+    let first_starts: Vec<f64> = sample_results_by_thread
+        .values()
+        .filter_map(|samples| {
+            samples
+                .iter()
+                .map(|sample| sample.measurement_start)
+                .min_by(f64::total_cmp)
+        })
+        .collect();
+    if first_starts.len() < 2 {
+        return None;
+    }
+
+    let first = first_starts.iter().copied().fold(f64::INFINITY, f64::min);
+    let last = first_starts
+        .iter()
+        .copied()
+        .fold(f64::NEG_INFINITY, f64::max);
+    let end = sample_results_by_thread
+        .values()
+        .flatten()
+        .map(|sample| sample.measurement_end)
+        .fold(f64::NEG_INFINITY, f64::max);
+    Some(StartSkew {
+        first,
+        last,
+        run_duration: end - first,
+    })
+}
+
+/// The number of requests in flight over all threads, as steps `(time, n_in_flight)` at
+/// each start or end of a request. The requests are given by their `(start, duration)`.
+pub(crate) fn in_flight_curve<'a>(
+    requests: impl IntoIterator<Item = &'a (f64, f64)>,
+) -> Vec<(f64, usize)> {
+    let mut events: Vec<(f64, i64)> = requests
+        .into_iter()
+        .flat_map(|(start, duration)| [(*start, 1), (start + duration, -1)])
+        .collect();
+    events.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    let mut curve: Vec<(f64, usize)> = Vec::with_capacity(events.len());
+    let mut n_in_flight = 0;
+    for (time, change) in events {
+        n_in_flight += change;
+        // NOTE: simultaneous events result in a single step
+        match curve.last_mut() {
+            Some((last_time, count)) if *last_time == time => *count = n_in_flight as usize,
+            _ => curve.push((time, n_in_flight as usize)),
+        }
+    }
+    curve
+}
+
+/// The average number of requests in flight from the first start until the last end of the
+/// `in_flight_curve`, i.e. the concurrency the server actually observed; as each thread waits for
+/// its response, it is often below the configured number of threads.
+/// `None` for a curve without duration.
+pub(crate) fn average_concurrency(in_flight: &[(f64, usize)]) -> Option<f64> {
+    let (first, last) = (in_flight.first()?.0, in_flight.last()?.0);
+    if last <= first {
+        return None;
+    }
+    let area: f64 = in_flight
+        .windows(2)
+        .map(|steps| (steps[1].0 - steps[0].0) * steps[0].1 as f64)
+        .sum();
+    Some(area / (last - first))
+}
+
+/// Downsamples the points (sorted by `x`) to at most `max_points` with the
+/// largest-triangle-three-buckets algorithm, which keeps the visual shape and both endpoints.
+/// At least the endpoints and one point between are kept.
+pub(crate) fn downsample_lttb(points: &[(f64, f64)], max_points: usize) -> Vec<(f64, f64)> {
+    let max_points = max_points.max(3);
+    if points.len() <= max_points {
+        return points.to_vec();
+    }
+
+    // the points between the endpoints are split into buckets, one point is chosen per bucket
+    let n_buckets = max_points - 2;
+    let bucket_size = (points.len() - 2) as f64 / n_buckets as f64;
+    let bucket_bounds = |bucket: usize| {
+        let start = 1 + (bucket as f64 * bucket_size) as usize;
+        let end = (1 + ((bucket + 1) as f64 * bucket_size) as usize).min(points.len() - 1);
+        (start, end)
+    };
+
+    let mut sampled = Vec::with_capacity(max_points);
+    sampled.push(points[0]);
+    let mut selected = points[0];
+    for bucket in 0..n_buckets {
+        let (start, end) = bucket_bounds(bucket);
+        // the average of the next bucket, or the last point for the last bucket
+        let next = match bucket + 1 < n_buckets {
+            true => {
+                let (next_start, next_end) = bucket_bounds(bucket + 1);
+                let next_points = &points[next_start..next_end];
+                let n = next_points.len() as f64;
+                next_points
+                    .iter()
+                    .fold((0.0, 0.0), |(x, y), p| (x + p.0 / n, y + p.1 / n))
+            }
+            false => points[points.len() - 1],
+        };
+
+        let area = |p: &(f64, f64)| {
+            ((selected.0 - next.0) * (p.1 - selected.1)
+                - (selected.0 - p.0) * (next.1 - selected.1))
+                .abs()
+        };
+        let largest = points[start..end]
+            .iter()
+            .max_by(|a, b| area(a).partial_cmp(&area(b)).unwrap())
+            .copied()
+            .unwrap_or(selected);
+        sampled.push(largest);
+        selected = largest;
+    }
+    sampled.push(points[points.len() - 1]);
+    sampled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use burl::DurationScale;
+
+    fn summary(durations: Vec<f64>) -> StatsSummary {
+        StatsSummary::calculate(
+            DurationScale::Milli,
+            0,
+            0,
+            durations,
+            HashMap::new(),
+            HashMap::new(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn sample_size_warning() {
+        let current = summary((0..10).map(|d| d as f64).collect());
+        let baseline = summary((0..10_000).map(|d| (d % 10) as f64).collect());
+        let tester = StatisticalTester::try_new(&current, &baseline).unwrap();
+        let warning = tester.sample_size_warning(10.0).unwrap();
+        assert!(warning.contains("10 samples vs. 10000"));
+        assert!(tester.sample_size_warning(1_000.0).is_none());
+
+        // either way
+        let tester = StatisticalTester::try_new(&baseline, &current).unwrap();
+        assert!(tester.sample_size_warning(10.0).is_some());
+        let tester = StatisticalTester::try_new(&current, &current).unwrap();
+        assert!(tester.sample_size_warning(1.0).is_none());
+    }
+
+    #[test]
+    fn primary_metric() {
+        // the same mean, but a lower body and a heavier tail
+        let baseline = summary(
+            (0..1_000)
+                .map(|idx| 10.0 + (idx % 10) as f64 * 0.1)
+                .collect(),
+        );
+        let current = summary(
+            (0..1_000)
+                .map(|idx| match idx % 100 {
+                    0..=2 => 20.0,
+                    _ => 9.7 + (idx % 10) as f64 * 0.1,
+                })
+                .collect(),
+        );
+        assert!((current.mean - baseline.mean).abs() < 0.05);
+
+        let tester = |metric: PrimaryMetric| {
+            StatisticalTester::try_new(&current, &baseline)
+                .unwrap()
+                .with_primary_metric(metric)
+        };
+        let outcome = |metric: PrimaryMetric| tester(metric).primary_test(500, 0.05).unwrap();
+        assert_eq!(outcome(PrimaryMetric::Mean), TestOutcome::Inconclusive);
+        assert!(matches!(
+            outcome(PrimaryMetric::Median),
+            TestOutcome::Improved { .. }
+        ));
+        assert!(matches!(
+            outcome(PrimaryMetric::P99),
+            TestOutcome::Regressed { .. }
+        ));
+
+        assert!(tester(PrimaryMetric::P99)
+            .verdict(500, 0.05)
+            .starts_with("[Primary: p99] regressed"));
+    }
+
+    #[test]
+    fn single_sample() {
+        let current = summary(vec![12.0]);
+        let baseline = summary((1..=50).map(|d| d as f64).collect());
+        assert!(current.normal_params().is_none());
+
+        let tester = StatisticalTester::try_new(&current, &baseline).unwrap();
+        assert_eq!(tester.analytic_test(0.05), None);
+        assert!(tester
+            .verdict(100, 0.05)
+            .contains("could not be determined"));
+
+        // the min samples are required of both runs
+        let current = summary((1..=10).map(|d| d as f64).collect());
+        let tester = StatisticalTester::try_new(&current, &baseline).unwrap();
+        assert!(tester.analytic_test(0.05).is_some());
+        assert_eq!(tester.with_min_samples(20).analytic_test(0.05), None);
+    }
+
+    #[test]
+    fn normality_note() {
+        let levels = (0..200).map(|i| (i as f64 + 0.5) / 200.0);
+        // exponentially distributed durations, e.g. with a long tail
+        let current = summary(levels.map(|p| -(1.0 - p).ln() * 10.0).collect());
+        let baseline = summary((0..200).map(|i| (i % 2) as f64 + 10.0).collect());
+        let symmetric = summary(vec![8.0, 9.0, 9.5, 10.0, 10.0, 10.5, 11.0, 12.0]);
+
+        let tester = StatisticalTester::try_new(&current, &symmetric).unwrap();
+        let note = tester.normality_note().unwrap();
+        assert!(note.contains("the current (Anderson-Darling"));
+        assert!(!note.contains("baseline"));
+        assert!(tester.verdict(100, 0.05).ends_with(&note));
+
+        let tester = StatisticalTester::try_new(&symmetric, &baseline).unwrap();
+        assert!(tester.normality_note().unwrap().contains("the baseline"));
+        let tester = StatisticalTester::try_new(&symmetric, &symmetric).unwrap();
+        assert!(tester.normality_note().is_none());
+    }
+
+    #[test]
+    fn memory_footprint() {
+        let footprint = estimated_memory_footprint(1_000);
+        assert!(footprint > 0);
+        assert_eq!(estimated_memory_footprint(10_000), 10 * footprint);
+
+        assert!(memory_warning(1_000, footprint).is_none());
+        let warning = memory_warning(1_001, footprint).unwrap();
+        assert!(warning.contains("1001 samples"));
+        assert!(memory_warning(100_000, MEMORY_WARNING_BYTES).is_none());
+    }
+
+    #[test]
+    fn thread_start_skew() {
+        let sample = |start: f64| SampleResult {
+            measurement_start: start,
+            measurement_end: start + 10.0,
+            duration: 10.0,
+            ..Default::default()
+        };
+        // the threads start staggered by 5 each, the samples are not ordered
+        let staggered: HashMap<ThreadIdx, Vec<SampleResult>> = (0..4)
+            .map(|thread_idx| {
+                let first = thread_idx as f64 * 5.0;
+                (thread_idx, vec![sample(first + 50.0), sample(first)])
+            })
+            .collect();
+
+        let skew = start_skew(&staggered).unwrap();
+        assert_eq!(skew.first, 0.0);
+        assert_eq!(skew.last, 15.0);
+        assert_eq!(skew.spread(), 15.0);
+        // from the first start until the last end at 15 + 50 + 10
+        assert_eq!(skew.run_duration, 75.0);
+        assert!(skew.is_large(0.1));
+        assert!(!skew.is_large(0.25));
+
+        let single_thread = HashMap::from([(0, vec![sample(0.0)]), (1, vec![])]);
+        assert_eq!(start_skew(&single_thread), None);
+    }
+
+    #[test]
+    fn in_flight_curve() {
+        let thread_a = [(0.0, 4.0), (4.0, 2.0)];
+        let thread_b = [(1.0, 2.0), (5.0, 3.0)];
+
+        let curve = super::in_flight_curve(thread_a.iter().chain(thread_b.iter()));
+        assert_eq!(
+            curve,
+            vec![
+                (0.0, 1),
+                (1.0, 2),
+                (3.0, 1),
+                (4.0, 1),
+                (5.0, 2),
+                (6.0, 1),
+                (8.0, 0)
+            ]
+        );
+        assert!(super::in_flight_curve(&[]).is_empty());
+    }
+
+    #[test]
+    fn average_concurrency() {
+        // overlapping for 2 of the 12 time units, idle for 2 of them
+        let requests = [(0.0, 4.0), (2.0, 4.0), (8.0, 4.0)];
+        let in_flight = super::in_flight_curve(&requests);
+        assert_eq!(super::average_concurrency(&in_flight), Some(1.0));
+
+        let requests = [(0.0, 4.0), (0.0, 4.0), (0.0, 2.0)];
+        let in_flight = super::in_flight_curve(&requests);
+        assert_eq!(super::average_concurrency(&in_flight), Some(2.5));
+
+        assert_eq!(
+            super::average_concurrency(&super::in_flight_curve(&[(1.0, 0.0)])),
+            None
+        );
+    }
+
+    #[test]
+    fn downsample_lttb() {
+        let points: Vec<(f64, f64)> = (0..10_000)
+            .map(|idx| {
+                (
+                    idx as f64,
+                    (idx as f64 / 500.0).sin() + (idx % 7) as f64 * 0.01,
+                )
+            })
+            .collect();
+
+        let sampled = super::downsample_lttb(&points, 500);
+        assert_eq!(sampled.len(), 500);
+        assert_eq!(sampled.first(), points.first());
+        assert_eq!(sampled.last(), points.last());
+        assert!(sampled.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        // the peaks of the sine are kept
+        let max = sampled.iter().map(|p| p.1).fold(f64::MIN, f64::max);
+        assert!(max > 0.99);
+
+        assert_eq!(super::downsample_lttb(&points[..100], 500), points[..100]);
+        assert_eq!(super::downsample_lttb(&points, 1).len(), 3);
+    }
 }
@@ -1,6 +1,9 @@
-use crate::sampling::Method;
+use crate::fd_limit::DEFAULT_FD_LIMIT_SHARE;
+use crate::sampling::{HeaderMode, IpVersion, Method, ThinkTime, TlsVersion};
+use crate::stats::{PartialBucket, PrimaryMetric, MIN_SAMPLES};
+use crate::{BurlError, BurlResult, ThreadIdx};
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::{fmt, time::Duration};
 
 #[derive(Default, Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum DurationScale {
@@ -54,9 +57,31 @@ pub struct StatsConfig {
     pub alpha: Option<f64>,
     pub n_bootstrap_samples: Option<usize>,
     pub n_bootstrap_draw_size: Option<usize>,
+    /// the number of percentiles plotted in the QQ plot
+    #[serde(alias = "nQqPoints")]
+    pub n_qq_points: Option<usize>,
+    /// the number of decimals of the displayed values (console and HTML); the data keeps full precision
+    #[serde(alias = "displayPrecision")]
+    pub display_precision: Option<usize>,
+    /// the minimal number of samples (of each run) for the analytical test, which assumes normal distributions
+    #[serde(alias = "minSamples")]
+    pub min_samples: Option<usize>,
+    /// a fixed target for the mean duration (in the duration scale), e.g. of an SLA, which is tested without a baseline
+    #[serde(alias = "targetMean")]
+    pub target_mean: Option<f64>,
+    /// the statistic of the headline verdict against the baseline: the mean (t-test) or the median, p95 or p99 (bootstrap)
+    #[serde(alias = "primaryMetric")]
+    pub primary_metric: Option<PrimaryMetric>,
+    /// the max ratio of the sample sizes of the current run and the baseline (either way) above which the comparison is warned about
+    #[serde(alias = "maxSampleRatio")]
+    pub max_sample_ratio: Option<f64>,
+    /// refuse to compare runs above the `max_sample_ratio` rather than warn
+    #[serde(alias = "refuseUnbalancedComparison")]
+    pub refuse_unbalanced_comparison: Option<bool>,
 }
 
 const ALPHA: f64 = 0.05;
+const DEFAULT_MAX_SAMPLE_RATIO: f64 = 10.0;
 
 impl Default for StatsConfig {
     fn default() -> Self {
@@ -64,10 +89,49 @@ impl Default for StatsConfig {
             alpha: Some(ALPHA),
             n_bootstrap_samples: Some(1_000),
             n_bootstrap_draw_size: Some(100),
+            n_qq_points: None,
+            display_precision: None,
+            min_samples: Some(MIN_SAMPLES),
+            target_mean: None,
+            primary_metric: None,
+            max_sample_ratio: Some(DEFAULT_MAX_SAMPLE_RATIO),
+            refuse_unbalanced_comparison: None,
         }
     }
 }
 
+/// A request of a mixed workload, see `BenchClientConfig::requests`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RequestConfig {
+    /// Defaults to the `url` of the benchmark.
+    pub url: Option<String>,
+    pub method: Method,
+    /// Defaults to the `json_payload` of the benchmark.
+    #[serde(alias = "jsonPayload")]
+    pub json_payload: Option<String>,
+    /// The relative frequency of the request in the mix; defaults to 1.
+    pub weight: Option<usize>,
+}
+
+/// The credentials and scope for signing the requests with AWS Signature Version 4,
+/// see `BenchClientConfig::aws_sigv4`. Unset values are read from the usual AWS env variables.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AwsSigV4Config {
+    /// Defaults to `AWS_ACCESS_KEY_ID`.
+    #[serde(alias = "accessKeyId")]
+    pub access_key_id: Option<String>,
+    /// Defaults to `AWS_SECRET_ACCESS_KEY`.
+    #[serde(alias = "secretAccessKey")]
+    pub secret_access_key: Option<String>,
+    /// Defaults to `AWS_SESSION_TOKEN`, if set (e.g. for temporary credentials).
+    #[serde(alias = "sessionToken")]
+    pub session_token: Option<String>,
+    /// Defaults to `AWS_REGION` or `AWS_DEFAULT_REGION`.
+    pub region: Option<String>,
+    /// The signing name of the service, e.g. "execute-api" for API Gateway or "s3".
+    pub service: String,
+}
+
 // TODO: structure into sub types
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct BenchClientConfig {
@@ -76,18 +140,143 @@ pub struct BenchClientConfig {
     pub method: Method,
     #[serde(alias = "disableCertificateValidation")]
     pub disable_certificate_validation: Option<bool>,
+    /// Hosts whose certificates are not validated (e.g. self-signed), unlike all other hosts.
+    #[serde(alias = "insecureHosts")]
+    pub insecure_hosts: Option<Vec<String>>,
+    /// PEM file with the client certificate for mutual TLS (may include the private key).
+    #[serde(alias = "clientCertRef")]
+    pub client_cert_ref: Option<String>,
+    /// PEM file with the private key of the client certificate.
+    #[serde(alias = "clientKeyRef")]
+    pub client_key_ref: Option<String>,
+    /// Pins the lowest TLS version the client accepts, e.g. "1.3".
+    #[serde(alias = "minTlsVersion")]
+    pub min_tls_version: Option<TlsVersion>,
+    /// Pins the highest TLS version the client accepts, e.g. "1.2".
+    #[serde(alias = "maxTlsVersion")]
+    pub max_tls_version: Option<TlsVersion>,
+    /// The interval of TCP keepalive probes on idle connections; disabled if unset.
+    #[serde(alias = "tcpKeepaliveSecs")]
+    pub tcp_keepalive_secs: Option<f64>,
+    /// How long idle connections are kept alive in the pool for reuse, default=90 secs.
+    #[serde(alias = "poolIdleTimeoutSecs")]
+    pub pool_idle_timeout_secs: Option<f64>,
+    /// The max number of idle connections kept per host; 0 opens a new connection per request.
+    #[serde(alias = "poolMaxIdlePerHost")]
+    pub pool_max_idle_per_host: Option<usize>,
+    /// Bounds each request (including the transfer of the body); a timed out request counts
+    /// as a failure, e.g. so that a hung endpoint does not stall the run.
+    #[serde(alias = "requestTimeoutMs")]
+    pub request_timeout_ms: Option<u64>,
+    /// Connects over this IP version only ("4" or "6"), e.g. to a dual-stack endpoint.
+    #[serde(alias = "ipVersion")]
+    pub ip_version: Option<IpVersion>,
+    /// Sends the requests over this Unix domain socket instead of TCP, e.g. to a local sidecar;
+    /// the urls (http only) then determine the path and the `Host` header only.
+    #[serde(alias = "unixSocket")]
+    pub unix_socket: Option<String>,
     // pub headers: HashMap<String, String>,
     pub headers: Option<Vec<(String, String)>>,
+    /// Whether headers of the same name are all sent (`Append`, default) or only the last one (`Set`).
+    #[serde(alias = "headerMode")]
+    pub header_mode: Option<HeaderMode>,
+    /// `Accept` headers cycled per iteration, e.g. to compare the content types an endpoint negotiates;
+    /// the stats are grouped by them. Ignored if `requests`, a `write_fraction`, a body template
+    /// or `labeled_payloads` are set.
+    #[serde(alias = "acceptHeaders")]
+    pub accept_headers: Option<Vec<String>>,
+    /// File with `Name: Value` lines (like a HTTP header block), added to the `headers`.
+    #[serde(alias = "headersFile")]
+    pub headers_file: Option<String>,
+    /// Further headers whose values are masked in the report, the request artifact and the logs,
+    /// besides `Authorization`, `Cookie` and the like.
+    #[serde(alias = "redactHeaders")]
+    pub redact_headers: Option<Vec<String>>,
     #[serde(alias = "jsonPayload")]
     pub json_payload: Option<String>,
     #[serde(alias = "jsonPayloadReference")]
     #[serde(alias = "jsonPayloadRef")]
     pub json_payload_ref: Option<String>,
+    /// Distinct JSON payloads per thread, assigned round robin by the thread index.
+    #[serde(alias = "jsonPayloads")]
+    pub json_payloads: Option<Vec<String>>,
+    /// File with a body whose `{{column}}` placeholders are substituted by the values of the
+    /// `data_rows_ref` rows, the next row per iteration (cycled). Supersedes the `json_payload`.
+    #[serde(alias = "bodyTemplateRef")]
+    pub body_template_ref: Option<String>,
+    /// CSV file with a header line naming the columns, see `body_template_ref`.
+    #[serde(alias = "dataRowsRef")]
+    pub data_rows_ref: Option<String>,
+    /// Payloads with their labels, cycled per request, e.g. to A/B test two request shapes
+    /// in one run with stats per label. Supersedes the `json_payload`.
+    #[serde(alias = "labeledPayloads")]
+    pub labeled_payloads: Option<Vec<(String, String)>>,
     #[serde(alias = "gqlQuery")]
     pub gql_query: Option<String>,
+    /// The variables of the `gql_query`, e.g. a TOML table.
+    #[serde(alias = "gqlVariables")]
+    pub gql_variables: Option<serde_json::Value>,
+    #[serde(alias = "gqlOperationName")]
+    pub gql_operation_name: Option<String>,
+    /// A binary body in base64, decoded before it is sent, e.g. a protobuf message.
+    /// Applies to POST and PUT requests without a `json_payload`.
+    #[serde(alias = "bodyBase64")]
+    pub body_base64: Option<String>,
+    /// Streams the file as the (chunked) body of the POST, PUT or PATCH requests, e.g. for large uploads.
+    /// The uploaded bytes are recorded per sample, and the upload throughput in the summary.
+    #[serde(alias = "uploadFileRef")]
+    pub upload_file_ref: Option<String>,
+    /// The content type of the `body_base64` (or the `upload_file_ref`), `application/octet-stream` by default.
+    #[serde(alias = "bodyContentType")]
+    pub body_content_type: Option<String>,
+    /// Attach the `json_payload` to GET requests too, e.g. for search APIs which expect a body on GET.
+    #[serde(alias = "allowGetBody")]
+    pub allow_get_body: Option<bool>,
+    /// Attach the `json_payload` to DELETE requests too, e.g. for bulk deletes.
+    #[serde(alias = "allowDeleteBody")]
+    pub allow_delete_body: Option<bool>,
+    /// Gzip the request body and set `Content-Encoding: gzip`.
+    #[serde(alias = "compressRequestBody")]
+    pub compress_request_body: Option<bool>,
+
+    /// A weighted mix of requests, chosen per iteration in a shuffled order (see `seed`).
+    /// Supersedes the single request given by `url`, `method` and `json_payload`.
+    pub requests: Option<Vec<RequestConfig>>,
+    /// Groups the stats by the value of this query parameter in the urls of the requests,
+    /// e.g. a parameter by which the server injects faults.
+    #[serde(alias = "labelByQueryParam")]
+    pub label_by_query_param: Option<String>,
+    /// The probability of a request to write the `json_payload` to the `url` (with the `write_method`),
+    /// chosen per iteration; the other requests are GET requests. Ignored if `requests` are set.
+    #[serde(alias = "writeFraction")]
+    pub write_fraction: Option<f64>,
+    /// The method of the writing requests, see `write_fraction`; defaults to POST.
+    #[serde(alias = "writeMethod")]
+    pub write_method: Option<Method>,
+    /// Hosts (`host` or `host:port`) replacing the host of the urls, e.g. the backends behind
+    /// a load balancer. The requests are distributed round robin over them; the stats are grouped by them.
+    #[serde(alias = "targetHosts")]
+    pub target_hosts: Option<Vec<String>>,
+    /// Pins each thread to one of the `target_hosts` (round robin by the thread index)
+    /// instead of distributing the requests of each thread over all of them.
+    #[serde(alias = "pinTargetHosts")]
+    pub pin_target_hosts: Option<bool>,
 
     #[serde(alias = "bearerToken")]
     pub bearer_token: Option<String>,
+    /// Distinct bearer tokens per thread, assigned round robin by the thread index.
+    #[serde(alias = "bearerTokens")]
+    pub bearer_tokens: Option<Vec<String>>,
+    /// Signs each request with AWS Signature Version 4 at the time it is sent.
+    #[serde(alias = "awsSigV4")]
+    pub aws_sigv4: Option<AwsSigV4Config>,
+    /// Path to a JSON schema which the bodies of successful responses have to conform to.
+    #[serde(alias = "responseSchemaRef")]
+    pub response_schema_ref: Option<String>,
+    /// A JSON field of the bodies of successful responses and its expected value,
+    /// e.g. `["$.status", "ok"]`; mismatches are counted as failures.
+    #[serde(alias = "expectJsonField")]
+    pub expect_json_field: Option<(String, serde_json::Value)>,
 
     // Benchmarking
     #[serde(alias = "durationScale")]
@@ -95,29 +284,156 @@ pub struct BenchClientConfig {
 
     #[serde(alias = "numberRuns")]
     #[serde(alias = "nRuns")]
-    n_runs: Option<usize>,
+    pub(crate) n_runs: Option<usize>,
+    /// Repeats the full run (including the warmups) independently, combining the samples of all runs
+    /// and estimating the variance between them; default=1.
+    #[serde(alias = "repeatRuns")]
+    repeat_runs: Option<usize>,
     #[serde(alias = "numberWarmupRuns")]
     #[serde(alias = "nWarmupRuns")]
     n_warmup_runs: Option<usize>,
+    /// Opens this many connections up front by concurrent (untimed) requests after the warmup,
+    /// so that the measurement does not pay for the connection setup.
+    #[serde(alias = "prewarmConnections")]
+    prewarm_connections: Option<usize>,
+    /// Warms each pooled connection by this many requests, i.e. sends `pool_max_idle_per_host` times
+    /// as many concurrent requests (one connection per thread without a pool size) instead of the
+    /// `prewarm_connections`, so that no cold connection is measured.
+    #[serde(alias = "warmupPerConnection")]
+    warmup_per_connection: Option<usize>,
+    /// Requires the last warmups to succeed before the measurement starts; otherwise the run aborts.
+    #[serde(alias = "warmupHealthRuns")]
+    warmup_health_runs: Option<usize>,
+    /// Requires the (checked) warmups to respond within the secs before the measurement starts.
+    #[serde(alias = "warmupMaxLatencySecs")]
+    warmup_max_latency_secs: Option<f64>,
     #[serde(alias = "concurrencyLevel")]
     concurrency_level: Option<usize>,
+    /// The share of the limit on open files (`ulimit -n`) the concurrent connections may take
+    /// before a warning, default=0.8.
+    #[serde(alias = "fdLimitShare")]
+    fd_limit_share: Option<f64>,
+    /// Caps the concurrency to the `fd_limit_share` instead of warning only.
+    #[serde(alias = "capConcurrencyToFdLimit")]
+    cap_concurrency_to_fd_limit: Option<bool>,
+    /// A safety cap on the number of requests over all threads, stopping all threads once reached.
+    #[serde(alias = "maxTotalRequests")]
+    max_total_requests: Option<usize>,
+    /// A ceiling on the duration of the whole run (including warmups and repeats) in secs;
+    /// once exceeded, the sampling stops and the report covers the samples collected so far.
+    #[serde(alias = "maxRunSecs")]
+    max_run_secs: Option<f64>,
+    /// Pauses the sampling while this file exists, e.g. during a deploy in a soak test.
+    /// The samples collected so far are kept and the run resumes once the file is removed.
+    #[serde(alias = "pauseFile")]
+    pause_file: Option<String>,
+    /// Idles for the secs after the measurement before the end of the run is stamped,
+    /// e.g. to let in-flight requests drain or an autoscaled service settle.
+    #[serde(alias = "cooldownSecs")]
+    cooldown_secs: Option<f64>,
+    /// Logs a summary of the samples collected so far (mean, p95 and requests/s) every secs.
+    #[serde(alias = "interimSummarySecs")]
+    interim_summary_secs: Option<f64>,
+    /// Excludes the samples started within the first secs of the measurement from the stats,
+    /// e.g. while the connection pools fill up. They are kept (flagged) in the samples dump.
+    #[serde(alias = "discardFirstSecs")]
+    discard_first_secs: Option<f64>,
+    /// The seed for randomized parts of the run, e.g. the order of mixed requests.
+    seed: Option<u64>,
+    /// The pause of each thread between its requests, drawn from the distribution with the `seed`,
+    /// e.g. `{ kind = "exponential", mean_ms = 100.0 }` to mimic users. The pauses are not measured.
+    #[serde(alias = "thinkTimeDistribution")]
+    pub think_time_distribution: Option<ThinkTime>,
+    /// Runs the threads one after another instead of concurrently, so that the order of
+    /// the requests is reproducible, e.g. for testing against a mock in CI. The think time is
+    /// then the mean of its distribution, without jitter. The measured timings, and the stats
+    /// derived from them, still depend on the server, unless measured by a mock clock
+    /// (see `BenchClient::with_clock`).
+    deterministic: Option<bool>,
+    /// Paces the requests of all threads to this rate of requests per second, e.g. for steady-state
+    /// traffic. The achieved rate is then compared against it for each second of the run.
+    #[serde(alias = "targetRps")]
+    target_rps: Option<f64>,
+    /// How the last, incomplete second of the run is compared against the `target_rps`:
+    /// `Drop` (default), `Normalize` to the covered part of it, or `Keep` as a full second.
+    #[serde(alias = "partialRpsBucket")]
+    pub partial_rps_bucket: Option<PartialBucket>,
+    /// The floor of the overall requests per second, below which the run fails, e.g. in CI.
+    #[serde(alias = "minRps")]
+    pub(crate) min_rps: Option<f64>,
+    /// Log the method, url and headers of failed requests with the status, headers and body
+    /// of their responses; the secret headers are redacted.
+    #[serde(alias = "logFailuresVerbose")]
+    log_failures_verbose: Option<bool>,
+    /// Read the full response body (default), so that the connection can be reused.
+    /// The measured durations then include the transfer of the body; otherwise they
+    /// cover the time until the response headers are received.
+    #[serde(alias = "drainResponseBody")]
+    pub(crate) drain_response_body: Option<bool>,
+    /// Benchmarks a stream of Server-Sent Events: the duration of a sample is the time until
+    /// the first event, and the stream is closed after this many events. With more than one,
+    /// the time of each event is recorded too, e.g. for the latency between the events.
+    #[serde(alias = "sseEvents")]
+    pub(crate) sse_events: Option<usize>,
+    /// On a `429 Too Many Requests` with a `Retry-After`, wait as requested and retry the request
+    /// (up to 10 times). The samples cover the final attempts only, the waits are reported apart.
+    #[serde(alias = "honorRetryAfter")]
+    pub(crate) honor_retry_after: Option<bool>,
 
     // Stats / reports
+    /// A label identifying the run, e.g. the release version; defaults to the url.
+    pub label: Option<String>,
     #[serde(alias = "reportDirectory")]
     pub report_directory: Option<String>,
+    /// Write the report as one standalone `report.html`, with the components and plotly inlined.
+    #[serde(alias = "singleFileReport")]
+    pub single_file_report: Option<bool>,
+    /// Export the key metrics in the Prometheus text format (`data/metrics.prom`).
+    #[serde(alias = "prometheusExport")]
+    pub prometheus_export: Option<bool>,
+    /// Export the estimates in the `estimates.json` format of criterion (`data/estimates.json`).
+    #[serde(alias = "criterionExport")]
+    pub criterion_export: Option<bool>,
+    /// Write PNG versions of the plots into the components directory, which requires
+    /// burl-reporter to be built with the `static-images` feature (kaleido).
+    #[serde(alias = "staticImages")]
+    pub static_images: Option<bool>,
+    /// Load plotly.js from a copy in the components directory rather than from its CDN,
+    /// so that the plots render without internet access.
+    #[serde(alias = "offlinePlots")]
+    pub offline_plots: Option<bool>,
+    /// Write the config, times, stats, failures and baseline verdict of the run into one `data/run.json`.
+    #[serde(alias = "runJson")]
+    pub run_json: Option<bool>,
+    /// Collect the warnings of the run and the report (e.g. a reached max run time or an unbalanced
+    /// comparison) into `data/warnings.json` and a section of the report, besides logging them;
+    /// default=true.
+    #[serde(alias = "reportWarnings")]
+    pub report_warnings: Option<bool>,
+    /// Sort the samples of each thread in `data/samples.json` by their start, e.g. of continued
+    /// runs, so that the dumps diff cleanly in version control.
+    #[serde(alias = "sortSamples")]
+    pub sort_samples: Option<bool>,
+    /// Downsamples the time series plot to at most this number of points (over all threads),
+    /// keeping its visual shape, e.g. for runs with millions of requests.
+    #[serde(alias = "maxTimeSeriesPoints")]
+    pub max_time_series_points: Option<usize>,
     #[serde(alias = "baselinePath")]
     pub baseline_path: Option<String>,
+    /// Compares the mean against the band of the means of this many runs archived in `data/hist`,
+    /// flagging a regression above its upper fence (`Q3 + 1.5 * IQR`).
+    #[serde(alias = "historyRuns")]
+    pub history_runs: Option<usize>,
     // TODO:
-    // * randomized requests / vec of payloads
+    // * randomized requests
     // * logging param with level?
-    // #[serde(alias = "jsonPayloads")]
-    // json_payloads: Option<Vec<String>>,
     #[serde(alias = "statsConfig")]
     #[serde(alias = "statisticsConfig")]
     pub stats_config: Option<StatsConfig>,
 }
 
 const DEFAULT_NRUNS: usize = 300;
+const DEFAULT_SEED: u64 = 42;
 
 impl BenchClientConfig {
     pub fn new(url: String) -> Self {
@@ -128,7 +444,11 @@ impl BenchClientConfig {
     }
 
     pub fn n_runs(&self) -> usize {
-        self.n_runs.unwrap_or(DEFAULT_NRUNS).max(0)
+        self.n_runs.unwrap_or(DEFAULT_NRUNS)
+    }
+
+    pub fn repeat_runs(&self) -> usize {
+        self.repeat_runs.unwrap_or(1)
     }
 
     pub fn concurrency_level(&self) -> ConcurrenyLevel {
@@ -138,12 +458,181 @@ impl BenchClientConfig {
         }
     }
 
+    pub fn fd_limit_share(&self) -> f64 {
+        self.fd_limit_share
+            .filter(|share| *share > 0.0)
+            .unwrap_or(DEFAULT_FD_LIMIT_SHARE)
+    }
+
+    pub fn cap_concurrency_to_fd_limit(&self) -> bool {
+        self.cap_concurrency_to_fd_limit.unwrap_or_default()
+    }
+
+    /// The number of threads for the concurrency level.
+    pub fn n_threads(&self) -> usize {
+        match self.concurrency_level() {
+            ConcurrenyLevel::Sequential => 1,
+            ConcurrenyLevel::Concurrent(n_threads) => n_threads,
+        }
+    }
+
+    /// The number of measured requests over all threads: `n_runs` on each thread,
+    /// capped by the `max_total_requests`.
+    pub fn effective_total_requests(&self) -> usize {
+        let total = self.n_runs() * self.n_threads();
+        match self.max_total_requests {
+            Some(max_requests) => total.min(max_requests),
+            None => total,
+        }
+    }
+
+    /// Rejects combinations of the run parameters which measure nothing or are ambiguous.
+    pub fn validate_run(&self) -> BurlResult<()> {
+        let issue = match (
+            self.n_runs,
+            self.concurrency_level,
+            self.max_total_requests,
+            self.repeat_runs,
+        ) {
+            (Some(0), _, _, _) => "`n_runs` has to be positive",
+            (_, Some(0), _, _) => {
+                "`concurrency_level` has to be positive, it is 1 for a sequential run"
+            }
+            (_, _, Some(0), _) => "`max_total_requests` has to be positive",
+            (_, _, _, Some(0)) => "`repeat_runs` has to be positive",
+            _ if matches!(self.target_rps, Some(rps) if !(rps.is_finite() && rps > 0.0)) => {
+                "`target_rps` has to be positive"
+            }
+            _ => {
+                return match &self.think_time_distribution {
+                    Some(think_time) => think_time.validate(),
+                    None => Ok(()),
+                }
+            }
+        };
+        Err(BurlError::InvalidConfig {
+            issue: issue.to_string(),
+        })
+    }
+
     pub fn duration_scale(&self) -> DurationScale {
         self.duration_scale.clone().unwrap_or_default()
     }
 
+    pub fn deterministic(&self) -> bool {
+        self.deterministic.unwrap_or_default()
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed.unwrap_or(DEFAULT_SEED)
+    }
+
+    pub fn max_total_requests(&self) -> Option<usize> {
+        self.max_total_requests
+    }
+
+    pub fn max_run_duration(&self) -> Option<Duration> {
+        self.max_run_secs
+            .filter(|secs| *secs > 0.0)
+            .map(Duration::from_secs_f64)
+    }
+
+    pub fn cooldown(&self) -> Option<Duration> {
+        self.cooldown_secs
+            .filter(|secs| *secs > 0.0)
+            .map(Duration::from_secs_f64)
+    }
+
+    pub fn interim_summary_interval(&self) -> Option<Duration> {
+        self.interim_summary_secs
+            .filter(|secs| *secs > 0.0)
+            .map(Duration::from_secs_f64)
+    }
+
+    pub fn pause_file(&self) -> Option<&str> {
+        self.pause_file.as_deref()
+    }
+
+    pub fn discard_window(&self) -> Option<Duration> {
+        self.discard_first_secs
+            .filter(|secs| *secs > 0.0)
+            .map(Duration::from_secs_f64)
+    }
+
+    pub fn target_rps(&self) -> Option<f64> {
+        self.target_rps
+    }
+
+    pub fn partial_rps_bucket(&self) -> PartialBucket {
+        self.partial_rps_bucket.unwrap_or_default()
+    }
+
+    pub fn min_rps(&self) -> Option<f64> {
+        self.min_rps
+    }
+
     pub fn warmup_runs(&self) -> usize {
-        self.n_warmup_runs.unwrap_or(0).max(0)
+        self.n_warmup_runs.unwrap_or(0)
+    }
+
+    /// The number of concurrent requests opening the connections up front, see `warmup_per_connection`.
+    pub fn prewarm_connections(&self) -> usize {
+        match self.warmup_per_connection {
+            Some(per_connection) => {
+                let pool_size = self
+                    .pool_max_idle_per_host
+                    .unwrap_or_else(|| self.n_threads());
+                pool_size * per_connection
+            }
+            None => self.prewarm_connections.unwrap_or(0),
+        }
+    }
+
+    pub fn warmup_health_runs(&self) -> Option<usize> {
+        self.warmup_health_runs
+    }
+
+    pub fn tcp_keepalive(&self) -> Option<Duration> {
+        self.tcp_keepalive_secs
+            .filter(|secs| *secs > 0.0)
+            .map(Duration::from_secs_f64)
+    }
+
+    pub fn request_timeout(&self) -> Option<Duration> {
+        self.request_timeout_ms.map(Duration::from_millis)
+    }
+
+    pub fn pool_idle_timeout(&self) -> Option<Duration> {
+        self.pool_idle_timeout_secs
+            .filter(|secs| *secs >= 0.0)
+            .map(Duration::from_secs_f64)
+    }
+
+    pub fn warmup_max_latency(&self) -> Option<Duration> {
+        self.warmup_max_latency_secs
+            .filter(|secs| *secs >= 0.0)
+            .map(Duration::from_secs_f64)
+    }
+
+    /// Whether the warmups are checked for their health before the measurement.
+    pub fn warmup_health_gate(&self) -> bool {
+        self.warmup_health_runs.is_some() || self.warmup_max_latency().is_some()
+    }
+
+    pub fn log_failures_verbose(&self) -> bool {
+        self.log_failures_verbose.unwrap_or_default()
+    }
+
+    pub fn drain_response_body(&self) -> bool {
+        self.drain_response_body.unwrap_or(true)
+    }
+
+    pub fn sse_events(&self) -> Option<usize> {
+        self.sse_events.map(|n_events| n_events.max(1))
+    }
+
+    pub fn honor_retry_after(&self) -> bool {
+        self.honor_retry_after.unwrap_or_default()
     }
 
     pub fn json_payload(&self) -> Option<String> {
@@ -158,6 +647,86 @@ impl BenchClientConfig {
         None
     }
 
+    pub fn body_content_type(&self) -> &str {
+        self.body_content_type
+            .as_deref()
+            .unwrap_or("application/octet-stream")
+    }
+
+    pub fn allow_get_body(&self) -> bool {
+        self.allow_get_body.unwrap_or(false)
+    }
+
+    pub fn allow_delete_body(&self) -> bool {
+        self.allow_delete_body.unwrap_or(false)
+    }
+
+    pub fn compress_request_body(&self) -> bool {
+        self.compress_request_body.unwrap_or_default()
+    }
+
+    /// The JSON payload of the given thread; falls back to `json_payload`.
+    pub fn thread_json_payload(&self, thread_idx: ThreadIdx) -> Option<String> {
+        match &self.json_payloads {
+            Some(payloads) if !payloads.is_empty() => {
+                Some(payloads[thread_idx % payloads.len()].clone())
+            }
+            _ => self.json_payload(),
+        }
+    }
+
+    /// The bearer token of the given thread; falls back to `bearer_token`.
+    pub fn thread_bearer_token(&self, thread_idx: ThreadIdx) -> Option<&String> {
+        match &self.bearer_tokens {
+            Some(tokens) if !tokens.is_empty() => Some(&tokens[thread_idx % tokens.len()]),
+            _ => self.bearer_token.as_ref(),
+        }
+    }
+
+    pub fn pin_target_hosts(&self) -> bool {
+        self.pin_target_hosts.unwrap_or(false)
+    }
+
+    pub fn run_label(&self) -> String {
+        self.label.clone().unwrap_or_else(|| self.url.clone())
+    }
+
+    pub fn prometheus_export(&self) -> bool {
+        self.prometheus_export.unwrap_or_default()
+    }
+
+    pub fn criterion_export(&self) -> bool {
+        self.criterion_export.unwrap_or_default()
+    }
+
+    pub fn static_images(&self) -> bool {
+        self.static_images.unwrap_or_default()
+    }
+
+    pub fn offline_plots(&self) -> bool {
+        self.offline_plots.unwrap_or_default()
+    }
+
+    pub fn run_json(&self) -> bool {
+        self.run_json.unwrap_or(false)
+    }
+
+    pub fn report_warnings(&self) -> bool {
+        self.report_warnings.unwrap_or(true)
+    }
+
+    pub fn sort_samples(&self) -> bool {
+        self.sort_samples.unwrap_or_default()
+    }
+
+    pub fn max_time_series_points(&self) -> Option<usize> {
+        self.max_time_series_points
+    }
+
+    pub fn single_file_report(&self) -> bool {
+        self.single_file_report.unwrap_or_default()
+    }
+
     pub fn alpha(&self) -> f64 {
         self.stats_config
             .as_ref()
@@ -179,6 +748,48 @@ impl BenchClientConfig {
             .unwrap_or(1_000)
     }
 
+    pub fn n_qq_points(&self) -> Option<usize> {
+        self.stats_config.as_ref().and_then(|scfg| scfg.n_qq_points)
+    }
+
+    pub fn min_samples(&self) -> usize {
+        self.stats_config
+            .as_ref()
+            .and_then(|scfg| scfg.min_samples)
+            .unwrap_or(MIN_SAMPLES)
+    }
+
+    pub fn target_mean(&self) -> Option<f64> {
+        self.stats_config.as_ref().and_then(|scfg| scfg.target_mean)
+    }
+
+    pub fn max_sample_ratio(&self) -> f64 {
+        self.stats_config
+            .as_ref()
+            .and_then(|scfg| scfg.max_sample_ratio)
+            .unwrap_or(DEFAULT_MAX_SAMPLE_RATIO)
+    }
+
+    pub fn refuse_unbalanced_comparison(&self) -> bool {
+        self.stats_config
+            .as_ref()
+            .and_then(|scfg| scfg.refuse_unbalanced_comparison)
+            .unwrap_or_default()
+    }
+
+    pub fn primary_metric(&self) -> PrimaryMetric {
+        self.stats_config
+            .as_ref()
+            .and_then(|scfg| scfg.primary_metric)
+            .unwrap_or_default()
+    }
+
+    pub fn display_precision(&self) -> Option<usize> {
+        self.stats_config
+            .as_ref()
+            .and_then(|scfg| scfg.display_precision)
+    }
+
     // pub fn stats_config(&self) -> StatsConfig {
     //     StatsConfig {
     //         alpha: self.alpha(),
@@ -187,3 +798,47 @@ impl BenchClientConfig {
     //     }
     // }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(toml_config: &str) -> BenchClientConfig {
+        toml::from_str(&format!(
+            "url = \"http://localhost\"\nmethod = \"Get\"\n{}",
+            toml_config
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn effective_total_requests() {
+        assert_eq!(config("").effective_total_requests(), DEFAULT_NRUNS);
+        assert_eq!(config("n_runs = 50").effective_total_requests(), 50);
+        assert_eq!(
+            config("n_runs = 50\nconcurrency_level = 1").effective_total_requests(),
+            50
+        );
+        assert_eq!(
+            config("n_runs = 50\nconcurrency_level = 4").effective_total_requests(),
+            200
+        );
+        assert_eq!(
+            config("n_runs = 50\nconcurrency_level = 4\nmax_total_requests = 120")
+                .effective_total_requests(),
+            120
+        );
+
+        assert!(config("concurrency_level = 4").validate_run().is_ok());
+        assert!(config("concurrency_level = 0").validate_run().is_err());
+        assert!(config("n_runs = 0").validate_run().is_err());
+        assert!(config("max_total_requests = 0").validate_run().is_err());
+        assert!(config("repeat_runs = 0").validate_run().is_err());
+        assert!(config("target_rps = 0.0").validate_run().is_err());
+        assert!(config(
+            "think_time_distribution = { kind = \"uniform\", min_ms = 5.0, max_ms = 1.0 }"
+        )
+        .validate_run()
+        .is_err());
+    }
+}
@@ -8,8 +8,22 @@ pub enum BurlError {
     #[error("Serialization error: {0}")]
     SerDe(#[from] serde_json::Error),
 
+    #[error("HTTP client error: {0}")]
+    Client(#[from] reqwest::Error),
+
     #[error("Invalid configuration: {issue}")]
     InvalidConfig { issue: String },
+
+    #[error("The run was aborted, e.g. as the warmup failed")]
+    RunAborted,
+
+    #[error("No valid samples were collected ({n_failed} requests failed)")]
+    NoValidSamples { n_failed: usize },
+
+    #[error(
+        "The overall throughput of {achieved_rps:.1} requests/s is below the min of {min_rps}"
+    )]
+    ThroughputTooLow { achieved_rps: f64, min_rps: f64 },
 }
 
 pub type BurlResult<T> = Result<T, BurlError>;
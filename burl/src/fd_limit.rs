@@ -0,0 +1,102 @@
+use crate::BenchClientConfig;
+use log::warn;
+
+/// The share of the limit on open files which the connections may take by default;
+/// the rest is left to the process itself, e.g. for writing the report.
+pub(crate) const DEFAULT_FD_LIMIT_SHARE: f64 = 0.8;
+
+/// The (soft) limit on the open file descriptors of the process, which covers the sockets too.
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)] // `rlim_t` is not a `u64` on all platforms
+pub(crate) fn fd_limit() -> Option<u64> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: `getrlimit` only writes to the given struct
+    let result = unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) };
+    (result == 0 && limit.rlim_cur != libc::RLIM_INFINITY).then_some(limit.rlim_cur as u64)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn fd_limit() -> Option<u64> {
+    None
+}
+
+/// The max number of connections within the `share` of the `fd_limit`,
+/// if the `n_connections` exceed it.
+fn exceeded_fd_limit(n_connections: usize, fd_limit: Option<u64>, share: f64) -> Option<usize> {
+    let safe_max = ((fd_limit? as f64 * share).floor() as usize).max(1);
+    (n_connections > safe_max).then_some(safe_max)
+}
+
+/// The number of threads of the config, checked against the safe share of the `fd_limit`:
+/// exceeding it warns of misleading connection errors, or caps the threads if configured.
+pub(crate) fn safe_n_threads(config: &BenchClientConfig, fd_limit: Option<u64>) -> usize {
+    let n_threads = config.n_threads();
+    let n_connections = n_threads.max(config.prewarm_connections());
+    let share = config.fd_limit_share();
+    let Some(safe_max) = exceeded_fd_limit(n_connections, fd_limit, share) else {
+        return n_threads;
+    };
+
+    if config.cap_concurrency_to_fd_limit() {
+        warn!(
+            "Capping the concurrency from {} to {} threads, {:.0}% of the limit of {} open files",
+            n_threads,
+            safe_max.min(n_threads),
+            share * 100.0,
+            fd_limit.unwrap_or_default()
+        );
+        n_threads.min(safe_max)
+    } else {
+        warn!(
+            "{} concurrent connections exceed {:.0}% of the limit of {} open files, which may \
+             cause misleading connection errors; raise the limit (`ulimit -n`) or set \
+             `cap_concurrency_to_fd_limit`",
+            n_connections,
+            share * 100.0,
+            fd_limit.unwrap_or_default()
+        );
+        n_threads
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn config(toml_config: &str) -> BenchClientConfig {
+        toml::from_str(&format!(
+            "url = \"http://localhost\"\nmethod = \"Get\"\n{}",
+            toml_config
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn fd_limit_concurrency() {
+        assert!(fd_limit().is_none_or(|limit| limit > 0));
+
+        let concurrent = config("concurrency_level = 200");
+        assert_eq!(safe_n_threads(&concurrent, None), 200);
+        assert_eq!(safe_n_threads(&concurrent, Some(1024)), 200);
+        // warns only, by default
+        assert_eq!(safe_n_threads(&concurrent, Some(100)), 200);
+
+        let capped = config("concurrency_level = 200\ncap_concurrency_to_fd_limit = true");
+        assert_eq!(safe_n_threads(&capped, Some(1024)), 200);
+        assert_eq!(safe_n_threads(&capped, Some(100)), 80);
+
+        let capped_share = config(
+            "concurrency_level = 200\ncap_concurrency_to_fd_limit = true\nfd_limit_share = 0.5",
+        );
+        assert_eq!(safe_n_threads(&capped_share, Some(300)), 150);
+        // the prewarmed connections count too, but do not raise the threads
+        let prewarmed = config(
+            "concurrency_level = 10\nprewarm_connections = 100\ncap_concurrency_to_fd_limit = true",
+        );
+        assert_eq!(safe_n_threads(&prewarmed, Some(100)), 10);
+        assert_eq!(exceeded_fd_limit(100, Some(100), 0.8), Some(80));
+    }
+}
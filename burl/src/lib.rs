@@ -1,63 +1,329 @@
 mod config;
 mod errors;
+mod fd_limit;
+mod redaction;
+mod warnings;
 
 pub mod parser;
 pub mod sampling;
 pub mod stats;
 
-pub use crate::parser::parse_toml;
+pub use crate::parser::{parse_spec, parse_toml};
 use crate::stats::StatsProcessor;
 pub(crate) use config::ConcurrenyLevel;
-pub use config::{BenchClientConfig, StatsConfig};
+pub use config::{AwsSigV4Config, BenchClientConfig, DurationScale, RequestConfig, StatsConfig};
 pub use errors::{BurlError, BurlResult};
+use fd_limit::{fd_limit, safe_n_threads};
+pub(crate) use redaction::HeaderRedactor;
+pub use warnings::Warnings;
 
 use chrono::{DateTime, Utc};
 use log::{error, info};
-use sampling::{RequestFactory, SampleCollector};
-use stats::StatsSummary;
-use std::sync::Arc;
-use tokio::time::Instant;
+use reqwest::RequestBuilder;
+use sampling::{
+    build_request, smoke_request, with_provided_body, AwsSigner, BodyProvider, Clock, FailureKind,
+    PauseSwitch, RateLimiter, RequestBudget, RequestFactory, ResponseValidator, SampleCollector,
+    SmokeResult, SystemClock,
+};
+use stats::{run_variance, InterimStats, RunVariance, StatsSummary};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::{task::JoinHandle, time::Instant};
 
 pub type ThreadIdx = usize;
 
+/// The time the samplers get to stop after the max run time, before they are aborted.
+const JOIN_GRACE: Duration = Duration::from_secs(1);
+
 pub struct RunSummary {
     pub stats_processor: StatsProcessor,
     pub start_time: DateTime<Utc>,
     pub end_time: DateTime<Utc>,
+    /// The variance between and within the runs, if repeated (see `repeat_runs`).
+    pub run_variance: Option<RunVariance>,
 }
 
 impl RunSummary {
     pub fn stats(&self) -> Option<StatsSummary> {
         self.stats_processor.stats_summary()
     }
+
+    /// The stats of the run; fails if no valid samples were collected, e.g. as all requests failed.
+    pub fn valid_stats(&self) -> BurlResult<StatsSummary> {
+        self.stats().ok_or_else(|| BurlError::NoValidSamples {
+            n_failed: self.stats_processor.n_failed(),
+        })
+    }
+
+    /// The stats and failures of the run, see `run_and_summarize`.
+    pub fn summarize(&self) -> BurlResult<BenchSummary> {
+        let (n_throttled, throttled_duration) = self.stats_processor.throttling();
+        Ok(BenchSummary {
+            stats: self.valid_stats()?,
+            n_failed: self.stats_processor.n_failed(),
+            failures_by_kind: self.stats_processor.failures_by_kind(),
+            overall_rps: self.stats_processor.overall_rps(),
+            n_throttled,
+            throttled_duration,
+            start_time: self.start_time,
+            end_time: self.end_time,
+            run_variance: self.run_variance.clone(),
+            warnings: self.stats_processor.warnings().to_vec(),
+        })
+    }
+}
+
+/// The outcome of a benchmark for programmatic use, i.e. without the CLI or the reporter.
+pub struct BenchSummary {
+    pub stats: StatsSummary,
+    /// The number of failed requests, broken down by their kind in `failures_by_kind`.
+    pub n_failed: usize,
+    pub failures_by_kind: HashMap<FailureKind, usize>,
+    /// The requests per second over the whole run.
+    pub overall_rps: Option<f64>,
+    /// The number of retries after a `Retry-After` and the time waited for them.
+    pub n_throttled: usize,
+    pub throttled_duration: Duration,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    /// The variance between and within the runs, if repeated (see `repeat_runs`).
+    pub run_variance: Option<RunVariance>,
+    /// The warnings logged during the run, e.g. failed prewarms or a reached max run time.
+    pub warnings: Vec<String>,
+}
+
+/// Runs the benchmark of the config and summarizes it, e.g. for using burl as a library.
+/// Fails if the config is invalid, the run is aborted (see the log) or no valid samples were collected.
+/// Thresholds such as the `min_rps` or the `target_mean` are left to the caller.
+pub async fn run_and_summarize(config: &BenchClientConfig) -> BurlResult<BenchSummary> {
+    let client = BenchClient::init(config).map_err(|issue| BurlError::InvalidConfig { issue })?;
+    client.run().await.ok_or(BurlError::RunAborted)?.summarize()
 }
 
 pub struct BenchClient<'a> {
     request_factory: RequestFactory,
+    response_validator: Option<Arc<ResponseValidator>>,
+    request_signer: Option<Arc<AwsSigner>>,
+    body_provider: Option<Arc<BodyProvider>>,
+    /// The number of threads, see `fd_limit_share`.
+    n_threads: usize,
     config: &'a BenchClientConfig,
+    /// The warnings logged during the current run, for its summary.
+    warnings: Warnings,
+    /// The clock of the measurements and the max run time, see `with_clock`.
+    clock: Arc<dyn Clock>,
 }
 
 impl<'a> BenchClient<'a> {
     pub fn init(config: &'a BenchClientConfig) -> Result<Self, String> {
-        let request_factory =
-            RequestFactory::new(config.disable_certificate_validation.unwrap_or_default())
-                .map_err(|err| format!("Could not initialize client: {}", err))?;
+        config
+            .validate_run()
+            .map_err(|err| format!("Could not initialize client: {}", err))?;
+        let request_factory = RequestFactory::new(config)
+            .map_err(|err| format!("Could not initialize client: {}", err))?;
+
+        let response_validator = ResponseValidator::from_config(config)
+            .map_err(|err| format!("Could not initialize the response validation: {}", err))?
+            .map(Arc::new);
+        let request_signer = AwsSigner::from_config(config)
+            .map_err(|err| format!("Could not initialize the request signing: {}", err))?
+            .map(Arc::new);
 
         Ok(Self {
             config,
             request_factory,
+            response_validator,
+            request_signer,
+            body_provider: None,
+            n_threads: safe_n_threads(config, fd_limit()),
+            warnings: Warnings::default(),
+            clock: Arc::new(SystemClock),
         })
     }
 
-    // TODO: split into collection of samples and report creation
+    /// Generates the body of each request (including warmups) by its iteration and the index
+    /// of its thread, superseding the payload of the config, e.g. for unique ids per request.
+    pub fn with_body_provider(
+        mut self,
+        body_provider: impl Fn(usize, ThreadIdx) -> Vec<u8> + Send + Sync + 'static,
+    ) -> Self {
+        self.body_provider = Some(Arc::new(body_provider));
+        self
+    }
+
+    /// Measures the requests and stops at the max run time by the given clock instead of
+    /// the system clock, e.g. a `MockClock` for reproducible timings against a mock server.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// A clone of the request with the provided body for the iteration, if a provider is set.
+    fn provided_request(
+        &self,
+        request: &RequestBuilder,
+        iteration: usize,
+        thread_idx: ThreadIdx,
+    ) -> BurlResult<Option<RequestBuilder>> {
+        self.body_provider
+            .as_deref()
+            .map(|provider| {
+                let compress = self.config.compress_request_body();
+                with_provided_body(request, provider, iteration, thread_idx, compress)
+            })
+            .transpose()
+    }
+
+    /// Sends the (first) request once, without warmups or stats, and returns the full response.
+    pub async fn smoke(&self) -> BurlResult<SmokeResult> {
+        let requests = self.request_factory.assemble_request_mix(self.config, 0)?;
+        let (request, _) = requests.get(0);
+        let provided_request = self.provided_request(request, 0, 0)?;
+        let request = provided_request.as_ref().unwrap_or(request);
+        smoke_request(
+            request,
+            self.request_signer.as_deref(),
+            &HeaderRedactor::from_config(self.config),
+        )
+        .await
+    }
+
+    /// Runs the benchmark `repeat_runs` times (see `run_once`) and combines the samples of all runs;
+    /// the variance between the runs is estimated if repeated.
     pub async fn run(&self) -> Option<RunSummary> {
         let start_time = Utc::now();
 
-        let n_runs = self.config.n_runs();
         let scale = self.config.duration_scale();
+        let n_repeats = self.config.repeat_runs();
+        let deadline = self
+            .config
+            .max_run_duration()
+            .map(|max_duration| self.clock.now() + max_duration);
+        // `global` timer over all threads and runs, started after the first warmup
+        let mut timer = None;
+        let pause_switch = self.config.pause_file().map(|path| {
+            let switch = Arc::new(PauseSwitch::default());
+            (switch.watch_file(path.to_string()), switch)
+        });
+        let interim_stats = self.config.interim_summary_interval().map(|interval| {
+            let stats = Arc::new(InterimStats::new(scale.clone()));
+            (stats.log_periodically(interval), stats)
+        });
+
+        let mut samples_by_thread = Vec::new();
+        let mut run_durations = Vec::with_capacity(n_repeats);
+        for repeat in 0..n_repeats {
+            if deadline.is_some_and(|deadline| self.clock.now() >= deadline) {
+                self.warnings.warn(format!(
+                    "The max run time is reached after {} of {} runs",
+                    repeat, n_repeats
+                ));
+                break;
+            }
+            if n_repeats > 1 {
+                info!("Starting run {} of {}", repeat + 1, n_repeats);
+            }
+            let samples = self
+                .run_once(
+                    &mut timer,
+                    repeat,
+                    deadline,
+                    pause_switch.as_ref().map(|(_, s)| s),
+                    interim_stats.as_ref().map(|(_, s)| s),
+                )
+                .await;
+            let Some(samples) = samples else {
+                if let Some((watcher, _)) = &pause_switch {
+                    watcher.abort();
+                }
+                if let Some((logger, _)) = &interim_stats {
+                    logger.abort();
+                }
+                return None;
+            };
+            let stats_processor = StatsProcessor::new(scale.clone(), samples)
+                .with_discard_window(self.config.discard_window())
+                .with_target_rps(self.config.target_rps());
+            if n_repeats > 1 {
+                let stats = stats_processor.stats_summary();
+                run_durations.push(stats.map(|stats| stats.durations).unwrap_or_default());
+            }
+            samples_by_thread.extend(stats_processor.into_sample_collections());
+        }
+
+        if let Some((watcher, _)) = pause_switch {
+            watcher.abort();
+        }
+        if let Some((logger, _)) = interim_stats {
+            logger.abort();
+        }
+
+        if let Some(cooldown) = self.config.cooldown() {
+            info!("Cooling down for {:.1} secs", cooldown.as_secs_f64());
+            tokio::time::sleep(cooldown).await;
+        }
+        let end_time = Utc::now();
+        let stats_processor = StatsProcessor::new(scale, samples_by_thread)
+            .with_discard_window(self.config.discard_window())
+            .with_target_rps(self.config.target_rps())
+            .with_warnings(self.warnings.take());
+        Some(RunSummary {
+            stats_processor,
+            start_time,
+            end_time,
+            run_variance: run_variance(&run_durations),
+        })
+    }
+
+    /// Sends `prewarm_connections` concurrent requests to fill the connection pool of the client.
+    async fn prewarm_connections(&self, request: &RequestBuilder) {
+        let n_connections = self.config.prewarm_connections();
+        info!("Opening {} connections", n_connections);
+
+        let mut tasks = Vec::with_capacity(n_connections);
+        for _ in 0..n_connections {
+            let (client, request) = match build_request(request, self.request_signer.as_deref()) {
+                Ok(request) => request,
+                Err(error) => {
+                    self.warnings
+                        .warn(format!("Failed to compile the prewarm request. {}", error));
+                    return;
+                }
+            };
+            // NOTE: the body is read, so that the connection is returned to the pool
+            tasks.push(tokio::spawn(async move {
+                client.execute(request).await?.bytes().await
+            }));
+        }
+
+        let mut n_failed = 0;
+        for task in tasks {
+            if !matches!(task.await, Ok(Ok(_))) {
+                n_failed += 1;
+            }
+        }
+        if n_failed > 0 {
+            self.warnings.warn(format!(
+                "{} of {} prewarm requests failed",
+                n_failed, n_connections
+            ));
+        }
+    }
 
-        let request_builder = match self.request_factory.assemble_request(self.config) {
-            Ok(req) => req,
+    /// Warms up and collects the samples of all threads once, stopping at the `deadline`
+    /// and idling while the `pause_switch` is paused. The samples are added to the `interim_stats` too.
+    /// The threads of the `repeat`-th run are indexed after the ones of the previous runs.
+    async fn run_once(
+        &self,
+        timer: &mut Option<Arc<Instant>>,
+        repeat: usize,
+        deadline: Option<Instant>,
+        pause_switch: Option<&Arc<PauseSwitch>>,
+        interim_stats: Option<&Arc<InterimStats>>,
+    ) -> Option<Vec<SampleCollector>> {
+        let n_runs = self.config.n_runs();
+
+        let warmup_requests = match self.request_factory.assemble_request_mix(self.config, 0) {
+            Ok(requests) => requests,
             Err(error) => {
                 error!("Failed to compile the request. {}", error);
                 return None;
@@ -66,13 +332,80 @@ impl<'a> BenchClient<'a> {
 
         // Trigger non-timed requests, possibly to populate a cache or similiar
         info!("Warming up");
-        for _ in 0..self.config.warmup_runs() {
-            if let Err(error) = request_builder.try_clone().unwrap().send().await {
-                error!("Warm up failed: {:?}", error);
+        let mut warmup_outcomes = Vec::with_capacity(self.config.warmup_runs());
+        for iteration in 0..self.config.warmup_runs() {
+            let (request_builder, _) = warmup_requests.get(iteration);
+            let provided_request = match self.provided_request(request_builder, iteration, 0) {
+                Ok(request) => request,
+                Err(error) => {
+                    error!(
+                        "Failed to set the provided body of the warmup request. {}",
+                        error
+                    );
+                    return None;
+                }
+            };
+            let request_builder = provided_request.as_ref().unwrap_or(request_builder);
+            let (client, request) =
+                match build_request(request_builder, self.request_signer.as_deref()) {
+                    Ok(request) => request,
+                    Err(error) => {
+                        error!("Failed to compile the warmup request. {}", error);
+                        return None;
+                    }
+                };
+            let warmup_start = self.clock.now();
+            let response = match deadline {
+                Some(deadline) => {
+                    match self
+                        .clock
+                        .timeout_at(deadline, client.execute(request))
+                        .await
+                    {
+                        Some(response) => response,
+                        None => {
+                            self.warnings
+                                .warn("The max run time is reached during the warmup".to_string());
+                            break;
+                        }
+                    }
+                }
+                None => client.execute(request).await,
+            };
+            match response {
+                Ok(response) if response.status().is_success() => {
+                    warmup_outcomes.push(Some(self.clock.elapsed_since(warmup_start)))
+                }
+                Ok(_) => warmup_outcomes.push(None),
+                Err(error) if self.config.warmup_health_gate() => {
+                    info!("Warm up failed: {:?}", error);
+                    warmup_outcomes.push(None);
+                }
+                Err(error) => {
+                    error!("Warm up failed: {:?}", error);
+                    return None;
+                }
+            }
+        }
+
+        if self.config.warmup_health_gate() {
+            if let Err(issue) = check_warmup_health(
+                &warmup_outcomes,
+                self.config.warmup_health_runs(),
+                self.config.warmup_max_latency(),
+            ) {
+                error!(
+                    "Aborting, the endpoint is not healthy after the warmup: {}",
+                    issue
+                );
                 return None;
             }
         }
 
+        if self.config.prewarm_connections() > 0 {
+            self.prewarm_connections(warmup_requests.get(0).0).await;
+        }
+
         let n_threads = match self.config.concurrency_level() {
             ConcurrenyLevel::Sequential => {
                 info!(
@@ -81,50 +414,946 @@ impl<'a> BenchClient<'a> {
                 );
                 1
             }
-            ConcurrenyLevel::Concurrent(n_threads) => {
+            ConcurrenyLevel::Concurrent(_) => {
                 info!(
                     "Starting measurement of {} samples (on each of {} threads) from {}",
-                    n_runs, n_threads, self.config.url
+                    n_runs, self.n_threads, self.config.url
                 );
-                n_threads.max(1)
+                self.n_threads.max(1)
             }
         };
+        match self.config.max_total_requests() {
+            Some(max_requests) if max_requests < n_runs * n_threads => info!(
+                "Effective total of {} requests, capped by the max total of requests",
+                max_requests
+            ),
+            _ => info!("Effective total of {} requests", n_runs * n_threads),
+        }
 
-        // `global` timer over all threads
-        let timer = Arc::new(Instant::now());
+        let timer = timer
+            .get_or_insert_with(|| Arc::new(self.clock.now()))
+            .clone();
+        let config = Arc::new(self.config.clone());
+        let request_budget = self
+            .config
+            .max_total_requests()
+            .map(|max_requests| Arc::new(RequestBudget::new(max_requests)));
+        let rate_limiter = self
+            .config
+            .target_rps()
+            .map(|target_rps| Arc::new(RateLimiter::new(target_rps)));
 
         // TODO: consider to use thread scope below
         let mut tasks = Vec::with_capacity(n_threads);
+        let mut samples_by_thread = Vec::with_capacity(n_threads);
         // NOTE: cannot use rayon due to unsatisfied trait bounds
         for thread_idx in 0..n_threads.max(1) {
-            let request_builder = request_builder.try_clone().unwrap();
+            let requests = match self
+                .request_factory
+                .assemble_request_mix(self.config, thread_idx)
+            {
+                Ok(requests) => requests,
+                Err(error) => {
+                    error!(
+                        "Failed to compile the request of thread {}. {}",
+                        thread_idx, error
+                    );
+                    return None;
+                }
+            };
 
-            let mut sampler = SampleCollector::new(
-                timer.clone(),
-                thread_idx,
-                n_runs,
-                self.config.duration_scale().clone(),
-            );
+            let sampler_idx = repeat * n_threads + thread_idx;
+            let mut sampler = SampleCollector::new(timer.clone(), sampler_idx, config.clone())
+                .with_clock(self.clock.clone())
+                .with_response_validator(self.response_validator.clone())
+                .with_request_signer(self.request_signer.clone())
+                .with_body_provider(self.body_provider.clone())
+                .with_request_budget(request_budget.clone())
+                .with_rate_limiter(rate_limiter.clone())
+                .with_pause_switch(pause_switch.cloned())
+                .with_interim_stats(interim_stats.cloned())
+                .with_deadline(deadline);
 
             let sampler = tokio::spawn(async move {
-                sampler.collect_samples(request_builder).await;
+                sampler.collect_samples(requests).await;
                 sampler
             });
 
-            tasks.push(sampler);
+            if self.config.deterministic() {
+                // NOTE: the threads take turns, such that the order of requests is reproducible
+                samples_by_thread.extend(self.join_sampler(sampler, deadline).await);
+            } else {
+                tasks.push(sampler);
+            }
         }
 
-        let mut samples_by_thread = Vec::new();
         for task in tasks {
-            samples_by_thread.push(task.await.unwrap());
+            samples_by_thread.extend(self.join_sampler(task, deadline).await);
         }
 
-        let end_time = Utc::now();
-        let stats_processor = StatsProcessor::new(scale.clone(), samples_by_thread);
-        Some(RunSummary {
-            stats_processor,
-            start_time,
-            end_time,
-        })
+        Some(samples_by_thread)
+    }
+
+    /// Waits for the sampler, at most shortly beyond the `deadline`: a sampler which did not stop
+    /// by then is aborted and its samples are lost.
+    async fn join_sampler(
+        &self,
+        mut task: JoinHandle<SampleCollector>,
+        deadline: Option<Instant>,
+    ) -> Option<SampleCollector> {
+        let sampler = match deadline {
+            Some(deadline) => {
+                match self
+                    .clock
+                    .timeout_at(deadline + JOIN_GRACE, &mut task)
+                    .await
+                {
+                    Some(sampler) => sampler,
+                    None => {
+                        task.abort();
+                        self.warnings.warn(
+                            "A thread did not stop at the max run time, its samples are dropped"
+                                .to_string(),
+                        );
+                        return None;
+                    }
+                }
+            }
+            None => task.await,
+        };
+        Some(sampler.unwrap())
+    }
+}
+
+/// Checks that the last `n_runs` warmups (all if `None`) succeeded, within the `max_latency` if given.
+/// The outcomes hold the durations of the successful warmups.
+fn check_warmup_health(
+    outcomes: &[Option<Duration>],
+    n_runs: Option<usize>,
+    max_latency: Option<Duration>,
+) -> Result<(), String> {
+    let n_runs = n_runs.unwrap_or(outcomes.len());
+    if outcomes.len() < n_runs {
+        return Err(format!(
+            "only {} of the {} required warmups were run",
+            outcomes.len(),
+            n_runs
+        ));
+    }
+
+    for (idx, outcome) in outcomes[outcomes.len() - n_runs..].iter().enumerate() {
+        match (outcome, max_latency) {
+            (None, _) => return Err(format!("warmup {} of the last {} failed", idx + 1, n_runs)),
+            (Some(duration), Some(max_latency)) if *duration > max_latency => {
+                return Err(format!(
+                    "warmup {} of the last {} took {:?}, above {:?}",
+                    idx + 1,
+                    n_runs,
+                    duration,
+                    max_latency
+                ))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampling::MockClock;
+    use std::collections::HashMap;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn config(toml_config: &str) -> BenchClientConfig {
+        toml::from_str(toml_config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn thread_bearer_tokens() {
+        let server = MockServer::start().await;
+        for token in ["token-a", "token-b"] {
+            Mock::given(method("GET"))
+                .and(header(
+                    "authorization",
+                    format!("Bearer {}", token).as_str(),
+                ))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(3)
+                .mount(&server)
+                .await;
+        }
+
+        let config = config(&format!(
+            r#"
+            url = "{}"
+            method = "Get"
+            n_runs = 3
+            concurrency_level = 2
+            bearer_tokens = ["token-a", "token-b"]
+            "#,
+            server.uri()
+        ));
+
+        let run_summary = BenchClient::init(&config).unwrap().run().await.unwrap();
+        let stats = run_summary.stats().unwrap();
+        assert_eq!(stats.n_ok, 6);
+        assert_eq!(stats.stats_by_thread.len(), 2);
+
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn deterministic_runs() {
+        let server = MockServer::start().await;
+        let config = config(&format!(
+            r#"
+            url = "{}"
+            method = "Get"
+            n_runs = 12
+            concurrency_level = 3
+            deterministic = true
+            bearer_tokens = ["token-a", "token-b", "token-c"]
+
+            [[requests]]
+            method = "Get"
+            weight = 2
+
+            [[requests]]
+            method = "Put"
+            json_payload = "{{}}"
+            "#,
+            server.uri()
+        ));
+
+        let mut runs = Vec::new();
+        let mut stats_files = Vec::new();
+        for _ in 0..2 {
+            // each request takes a fixed time by the clock, such that the timings are reproducible
+            let clock = Arc::new(MockClock::default());
+            server.reset().await;
+            for (verb, millis) in [("GET", 3), ("PUT", 5)] {
+                let clock = clock.clone();
+                Mock::given(method(verb))
+                    .respond_with(move |_: &wiremock::Request| {
+                        clock.advance(Duration::from_millis(millis));
+                        ResponseTemplate::new(200)
+                    })
+                    .mount(&server)
+                    .await;
+            }
+
+            let client = BenchClient::init(&config).unwrap().with_clock(clock);
+            let run_summary = client.run().await.unwrap();
+            let stats = run_summary.stats().unwrap();
+            assert_eq!(stats.n_ok, 36);
+            assert_eq!(stats.stats_by_label["GET"].n_ok, 24);
+            assert_eq!(stats.max, 5_000.0);
+            stats_files.push(serde_json::to_string(&Some(stats)).unwrap());
+
+            let requests: Vec<(String, String)> = server
+                .received_requests()
+                .await
+                .unwrap()
+                .iter()
+                .map(|request| {
+                    let token = request.headers.get(&"authorization".into()).unwrap();
+                    (request.method.to_string(), token.as_str().to_string())
+                })
+                .collect();
+            runs.push(requests);
+        }
+
+        assert_eq!(runs[0], runs[1]);
+        // the `stats.json` of both runs, including the timings
+        assert_eq!(stats_files[0], stats_files[1]);
+        // the threads take turns
+        let tokens: Vec<&str> = runs[0].iter().map(|(_, token)| token.as_str()).collect();
+        assert!(tokens[..12].iter().all(|token| *token == "Bearer token-a"));
+        assert!(tokens[24..].iter().all(|token| *token == "Bearer token-c"));
+    }
+
+    #[test]
+    fn warmup_health() {
+        let ms = Duration::from_millis;
+        let outcomes = [None, Some(ms(30)), Some(ms(5)), Some(ms(8))];
+
+        assert!(check_warmup_health(&outcomes, Some(3), None).is_ok());
+        assert!(check_warmup_health(&outcomes, Some(2), Some(ms(10))).is_ok());
+        assert!(check_warmup_health(&outcomes, Some(3), Some(ms(10))).is_err());
+        assert!(check_warmup_health(&outcomes, None, None).is_err());
+        assert!(check_warmup_health(&outcomes, Some(5), None).is_err());
+    }
+
+    #[tokio::test]
+    async fn failing_warmups() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(4)
+            .mount(&server)
+            .await;
+
+        let config = config(&format!(
+            r#"
+            url = "{}"
+            method = "Get"
+            n_runs = 10
+            n_warmup_runs = 4
+            warmup_health_runs = 2
+            "#,
+            server.uri()
+        ));
+
+        // no measurement after the warmups
+        assert!(BenchClient::init(&config).unwrap().run().await.is_none());
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn accept_headers() {
+        let server = MockServer::start().await;
+        for accept in [
+            "application/json",
+            "application/xml",
+            "application/x-protobuf",
+        ] {
+            Mock::given(method("GET"))
+                .and(header("accept", accept))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(4)
+                .mount(&server)
+                .await;
+        }
+
+        let config = config(&format!(
+            r#"
+            url = "{}"
+            method = "Get"
+            n_runs = 12
+            acceptHeaders = ["application/json", "application/xml", "application/x-protobuf"]
+            "#,
+            server.uri()
+        ));
+
+        let run_summary = BenchClient::init(&config).unwrap().run().await.unwrap();
+        let stats = run_summary.stats().unwrap();
+        assert_eq!(stats.n_ok, 12);
+        assert_eq!(stats.stats_by_label.len(), 3);
+        assert_eq!(stats.stats_by_label["application/xml"].n_ok, 4);
+
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn label_by_query_param() {
+        let server = MockServer::start().await;
+        for (status, response) in [("200", 200), ("503", 503)] {
+            Mock::given(method("GET"))
+                .and(query_param("status", status))
+                .respond_with(ResponseTemplate::new(response))
+                .mount(&server)
+                .await;
+        }
+
+        let config = config(&format!(
+            r#"
+            url = "{0}"
+            method = "Get"
+            n_runs = 9
+            labelByQueryParam = "status"
+
+            [[requests]]
+            url = "{0}/?status=200&delay=1"
+            method = "Get"
+            weight = 2
+
+            [[requests]]
+            url = "{0}/?status=503"
+            method = "Get"
+            "#,
+            server.uri()
+        ));
+
+        let run_summary = BenchClient::init(&config).unwrap().run().await.unwrap();
+        let stats = run_summary.stats().unwrap();
+        assert_eq!(stats.stats_by_label.len(), 2);
+        assert_eq!(stats.stats_by_label["200"].n_ok, 6);
+        assert_eq!(stats.stats_by_label["200"].n_errors, 0);
+        assert_eq!(stats.stats_by_label["503"].n_ok, 0);
+        assert_eq!(stats.stats_by_label["503"].n_errors, 3);
+    }
+
+    #[tokio::test]
+    async fn target_hosts() {
+        let mut servers = Vec::new();
+        for _ in 0..3 {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .respond_with(ResponseTemplate::new(200))
+                .mount(&server)
+                .await;
+            servers.push(server);
+        }
+        let hosts: Vec<String> = servers
+            .iter()
+            .map(|server| server.address().to_string())
+            .collect();
+
+        let config = |pinned: bool| {
+            config(&format!(
+                r#"
+                url = "http://localhost:1/items"
+                method = "Get"
+                n_runs = 9
+                concurrency_level = 2
+                deterministic = true
+                target_hosts = {:?}
+                pin_target_hosts = {}
+                "#,
+                hosts, pinned
+            ))
+        };
+
+        // each thread distributes its requests over all hosts
+        let run_summary = BenchClient::init(&config(false))
+            .unwrap()
+            .run()
+            .await
+            .unwrap();
+        let stats = run_summary.stats().unwrap();
+        assert_eq!(stats.stats_by_label.len(), 3);
+        for (host, server) in hosts.iter().zip(servers.iter()) {
+            assert_eq!(server.received_requests().await.unwrap().len(), 6);
+            assert_eq!(stats.stats_by_label[host].n_ok, 6);
+        }
+
+        // pinned, each of the 2 threads sends all its requests to one host
+        let run_summary = BenchClient::init(&config(true))
+            .unwrap()
+            .run()
+            .await
+            .unwrap();
+        let stats = run_summary.stats().unwrap();
+        assert_eq!(stats.stats_by_label.len(), 2);
+        assert_eq!(servers[0].received_requests().await.unwrap().len(), 6 + 9);
+        assert_eq!(servers[1].received_requests().await.unwrap().len(), 6 + 9);
+        assert_eq!(servers[2].received_requests().await.unwrap().len(), 6);
+        assert_eq!(stats.stats_by_label[&hosts[0]].n_ok, 9);
+    }
+
+    #[tokio::test]
+    async fn smoke() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(201)
+                    .insert_header("x-request-id", "abc")
+                    .insert_header("set-cookie", "session=s3cr3t")
+                    .set_body_string("created"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = config(&format!(
+            r#"
+            url = "{}/items"
+            method = "Get"
+            n_runs = 10
+            n_warmup_runs = 5
+            "#,
+            server.uri()
+        ));
+
+        let smoke = BenchClient::init(&config).unwrap().smoke().await.unwrap();
+        assert_eq!(smoke.status, 201);
+        assert!(smoke.is_success());
+        assert!(smoke.total_duration >= smoke.duration);
+
+        let output = smoke.to_string();
+        assert!(output.contains(&format!("GET {}/items", server.uri())));
+        assert!(output.contains("status: 201"));
+        assert!(output.contains("timing: "));
+        assert!(output.contains("x-request-id: abc"));
+        assert!(output.contains("set-cookie: <redacted>"));
+        assert!(!output.contains("s3cr3t"));
+        assert!(output.ends_with("BODY (7 bytes)\ncreated"));
+    }
+
+    #[tokio::test]
+    async fn repeat_runs() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(3 * (1 + 2 * 5))
+            .mount(&server)
+            .await;
+
+        let config = config(&format!(
+            r#"
+            url = "{}"
+            method = "Get"
+            n_runs = 5
+            n_warmup_runs = 1
+            concurrency_level = 2
+            repeat_runs = 3
+            "#,
+            server.uri()
+        ));
+
+        let run_summary = BenchClient::init(&config).unwrap().run().await.unwrap();
+        let stats = run_summary.stats().unwrap();
+        assert_eq!(stats.n_ok, 30);
+        assert_eq!(stats.stats_by_thread.len(), 6);
+
+        let run_variance = run_summary.run_variance.unwrap();
+        assert_eq!(run_variance.run_means.len(), 3);
+        assert!(run_variance.between_run_variance >= 0.0);
+        assert!(run_variance.within_run_variance > 0.0);
+
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn body_template() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+        let config = config(&format!(
+            r#"
+            url = "{}"
+            method = "Post"
+            n_runs = 3
+            body_template_ref = "{1}/body_template.json"
+            data_rows_ref = "{1}/data_rows.csv"
+            "#,
+            server.uri(),
+            fixtures
+        ));
+
+        let run_summary = BenchClient::init(&config).unwrap().run().await.unwrap();
+        assert_eq!(run_summary.stats().unwrap().n_ok, 3);
+
+        let bodies: Vec<String> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|request| String::from_utf8(request.body.clone()).unwrap())
+            .collect();
+        let john = "{ \"name\": \"john doezer\", \"price\": 10.2 }\n";
+        let jane = "{ \"name\": \"jane doezer\", \"price\": 12.5 }\n";
+        assert_eq!(bodies, vec![john, jane, john]);
+    }
+
+    #[tokio::test]
+    async fn labeled_payloads() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let config = config(&format!(
+            r#"
+            url = "{}"
+            method = "Post"
+            n_runs = 6
+            labeled_payloads = [["a", '{{ "id": 1 }}'], ["b", '{{ "id": 2, "name": "b" }}']]
+            "#,
+            server.uri()
+        ));
+
+        let run_summary = BenchClient::init(&config).unwrap().run().await.unwrap();
+        let stats = run_summary.stats().unwrap();
+        assert_eq!(stats.n_ok, 6);
+        assert_eq!(stats.stats_by_label.len(), 2);
+        assert_eq!(stats.stats_by_label["a"].n_ok, 3);
+        assert_eq!(stats.stats_by_label["b"].n_ok, 3);
+
+        let bodies: Vec<String> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|request| String::from_utf8(request.body.clone()).unwrap())
+            .collect();
+        let (a, b) = (r#"{ "id": 1 }"#, r#"{ "id": 2, "name": "b" }"#);
+        assert_eq!(bodies, vec![a, b, a, b, a, b]);
+    }
+
+    #[tokio::test]
+    async fn prewarm_connections() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(100)))
+            .expect(4 + 1 + 2)
+            .mount(&server)
+            .await;
+
+        let config = config(&format!(
+            r#"
+            url = "{}"
+            method = "Get"
+            n_runs = 2
+            n_warmup_runs = 1
+            prewarm_connections = 4
+            "#,
+            server.uri()
+        ));
+
+        let start = Instant::now();
+        let run_summary = BenchClient::init(&config).unwrap().run().await.unwrap();
+        assert_eq!(run_summary.stats().unwrap().n_ok, 2);
+        // the prewarm requests are concurrent
+        assert!(start.elapsed() < Duration::from_millis(4 * 100 + 3 * 100));
+
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn paced_run() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let config = config(&format!(
+            r#"
+            url = "{}"
+            method = "Get"
+            n_runs = 10
+            concurrency_level = 2
+            target_rps = 50.0
+            "#,
+            server.uri()
+        ));
+        let start = Instant::now();
+        let run_summary = BenchClient::init(&config).unwrap().run().await.unwrap();
+        // 20 requests spaced by 20 ms over both threads
+        assert!(start.elapsed() >= Duration::from_millis(19 * 20));
+
+        let stats = run_summary.stats().unwrap();
+        assert_eq!(stats.n_ok, 20);
+        assert_eq!(stats.target_rps, Some(50.0));
+        let effective_rps = stats.effective_rps.unwrap();
+        assert!((40.0..60.0).contains(&effective_rps), "{}", effective_rps);
+    }
+
+    #[tokio::test]
+    async fn warmup_per_connection() {
+        // without a pool size, each thread holds one connection
+        let unpooled =
+            config("url = \"http://localhost\"\nmethod = \"Get\"\nconcurrency_level = 4\nwarmup_per_connection = 2");
+        assert_eq!(unpooled.prewarm_connections(), 4 * 2);
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(3 * 2 + 2)
+            .mount(&server)
+            .await;
+
+        let config = config(&format!(
+            r#"
+            url = "{}"
+            method = "Get"
+            n_runs = 2
+            pool_max_idle_per_host = 3
+            warmup_per_connection = 2
+            prewarm_connections = 10
+            "#,
+            server.uri()
+        ));
+        assert_eq!(config.prewarm_connections(), 3 * 2);
+        BenchClient::init(&config).unwrap().run().await.unwrap();
+
+        server.verify().await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_socket() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let socket_path = std::env::temp_dir().join(format!("burl-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket_path);
+        let listener = tokio::net::UnixListener::bind(&socket_path).unwrap();
+        let heads = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let server_heads = heads.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let heads = server_heads.clone();
+                tokio::spawn(async move {
+                    let (mut buffer, mut chunk) = (Vec::new(), [0; 1024]);
+                    while let Ok(n @ 1..) = stream.read(&mut chunk).await {
+                        buffer.extend_from_slice(&chunk[..n]);
+                        while let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                            let head: Vec<u8> = buffer.drain(..end + 4).collect();
+                            heads
+                                .lock()
+                                .unwrap()
+                                .push(String::from_utf8_lossy(&head).to_lowercase());
+                            let response = b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok";
+                            stream.write_all(response).await.unwrap();
+                        }
+                    }
+                });
+            }
+        });
+
+        let config = config(&format!(
+            r#"
+            url = "http://sidecar.local/ping"
+            method = "Get"
+            n_runs = 5
+            concurrency_level = 4
+            unix_socket = "{}"
+            "#,
+            socket_path.display()
+        ));
+        let run_summary = BenchClient::init(&config).unwrap().run().await.unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        // the connections of all threads are relayed
+        assert_eq!(run_summary.valid_stats().unwrap().n_ok, 4 * 5);
+        let heads = heads.lock().unwrap();
+        // including the warmup
+        assert!(heads.len() >= 4 * 5);
+        for head in heads.iter() {
+            assert!(head.starts_with("get /ping http/1.1"));
+            assert!(head.contains("host: sidecar.local\r\n"));
+        }
+    }
+
+    #[tokio::test]
+    async fn cooldown() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let config = config(&format!(
+            r#"
+            url = "{}"
+            method = "Get"
+            n_runs = 2
+            cooldown_secs = 0.3
+            "#,
+            server.uri()
+        ));
+
+        let run_summary = BenchClient::init(&config).unwrap().run().await.unwrap();
+        let last_request_end = run_summary
+            .stats_processor
+            .sample_results_by_thread()
+            .values()
+            .flatten()
+            .map(|sample| sample.duration_request_end)
+            .max()
+            .unwrap();
+        let run_duration = (run_summary.end_time - run_summary.start_time)
+            .to_std()
+            .unwrap();
+        assert!(run_duration >= last_request_end + Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    async fn body_provider() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let config = config(&format!(
+            r#"
+            url = "{}"
+            method = "Post"
+            json_payload = "{{}}"
+            n_runs = 4
+            concurrency_level = 2
+            deterministic = true
+            "#,
+            server.uri()
+        ));
+
+        let run_summary = BenchClient::init(&config)
+            .unwrap()
+            .with_body_provider(|iteration, thread_idx| {
+                format!("{}-{}", thread_idx, iteration).into_bytes()
+            })
+            .run()
+            .await
+            .unwrap();
+        assert_eq!(run_summary.stats().unwrap().n_ok, 8);
+
+        let bodies: Vec<String> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|request| String::from_utf8(request.body.clone()).unwrap())
+            .collect();
+        let expected: Vec<String> = (0..2)
+            .flat_map(|thread_idx| {
+                (0..4).map(move |iteration| format!("{}-{}", thread_idx, iteration))
+            })
+            .collect();
+        assert_eq!(bodies, expected);
+    }
+
+    #[tokio::test]
+    async fn max_run_secs() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(100)))
+            .mount(&server)
+            .await;
+
+        let config = config(&format!(
+            r#"
+            url = "{}"
+            method = "Get"
+            n_runs = 100
+            n_warmup_runs = 2
+            concurrency_level = 2
+            max_run_secs = 0.5
+            "#,
+            server.uri()
+        ));
+
+        let start = Instant::now();
+        let run_summary = BenchClient::init(&config).unwrap().run().await.unwrap();
+        assert!(start.elapsed() < Duration::from_millis(500) + JOIN_GRACE);
+
+        // a partial run of both threads
+        let stats = run_summary.stats().unwrap();
+        assert!(stats.n_ok > 0 && stats.n_ok < 10, "{} samples", stats.n_ok);
+        assert_eq!(stats.stats_by_thread.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn run_warnings() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(50)))
+            .mount(&server)
+            .await;
+
+        let config = config(&format!(
+            r#"
+            url = "{}"
+            method = "Get"
+            n_runs = 100
+            repeat_runs = 3
+            max_run_secs = 0.3
+            "#,
+            server.uri()
+        ));
+
+        let summary = run_and_summarize(&config).await.unwrap();
+        assert_eq!(
+            summary.warnings,
+            vec!["The max run time is reached after 1 of 3 runs".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn redirect_hops() {
+        let server = MockServer::start().await;
+        for (from, to) in [("/a", "/b"), ("/b", "/c")] {
+            Mock::given(method("GET"))
+                .and(path(from))
+                .respond_with(ResponseTemplate::new(302).insert_header("location", to))
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/c"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let config = config(&format!(
+            r#"
+            url = "{}/a"
+            method = "Get"
+            n_runs = 4
+            concurrency_level = 2
+            "#,
+            server.uri()
+        ));
+
+        let run_summary = BenchClient::init(&config).unwrap().run().await.unwrap();
+        let samples = run_summary.stats_processor.sample_results_by_thread();
+        assert!(samples
+            .values()
+            .flatten()
+            .all(|sample| sample.redirects == 2));
+
+        let stats = run_summary.stats().unwrap();
+        assert_eq!(stats.redirect_hops, HashMap::from([(2, 8)]));
+        assert!(stats.to_string().contains("[2: 8]"));
+    }
+
+    #[tokio::test]
+    async fn no_valid_samples() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let config = config(&format!(
+            r#"
+            url = "{}"
+            method = "Get"
+            n_runs = 5
+            "#,
+            server.uri()
+        ));
+
+        let run_summary = BenchClient::init(&config).unwrap().run().await.unwrap();
+        assert!(matches!(
+            run_summary.valid_stats(),
+            Err(BurlError::NoValidSamples { n_failed: 5 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn max_total_requests() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let config = config(&format!(
+            r#"
+            url = "{}"
+            method = "Get"
+            n_runs = 10
+            concurrency_level = 4
+            max_total_requests = 15
+            "#,
+            server.uri()
+        ));
+
+        let run_summary = BenchClient::init(&config).unwrap().run().await.unwrap();
+        let stats = run_summary.stats().unwrap();
+        assert_eq!(stats.n_ok, 15);
+        assert!(stats.stats_by_thread.values().all(|s| s.n_ok <= 10));
+        assert_eq!(server.received_requests().await.unwrap().len(), 15);
     }
 }
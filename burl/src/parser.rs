@@ -2,7 +2,27 @@ use crate::config::BenchClientConfig;
 use log::error;
 use std::{fs, path::Path};
 
-pub fn parse_toml(file_name: &str) -> Option<BenchClientConfig> {
+/// The formats of the specs files, see `parse_spec`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SpecFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl SpecFormat {
+    fn from_extension(file: &Path) -> Option<Self> {
+        let extension = file.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "toml" => Some(SpecFormat::Toml),
+            "yaml" | "yml" => Some(SpecFormat::Yaml),
+            "json" => Some(SpecFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+fn parse_file(file_name: &str, format: SpecFormat) -> Option<BenchClientConfig> {
     let file = Path::new(file_name);
     if !file.exists() {
         error!("File {:?} does not exist", file.as_os_str());
@@ -10,17 +30,67 @@ pub fn parse_toml(file_name: &str) -> Option<BenchClientConfig> {
     }
 
     let file_content = fs::read_to_string(file_name).ok()?;
-    let specs: BenchClientConfig = match toml::from_str(&file_content) {
-        Ok(parsed) => parsed,
-        Err(error) => {
-            error!("unable to parse the TOML structure: {:?}", error);
-            return None;
+    let parsed = match format {
+        SpecFormat::Toml => toml::from_str(&file_content).map_err(|error| format!("{:?}", error)),
+        SpecFormat::Yaml => {
+            serde_yaml::from_str(&file_content).map_err(|error| format!("{:?}", error))
+        }
+        SpecFormat::Json => {
+            serde_json::from_str(&file_content).map_err(|error| format!("{:?}", error))
         }
     };
 
-    Some(specs)
+    match parsed {
+        Ok(specs) => Some(specs),
+        Err(error) => {
+            error!("unable to parse the {:?} structure: {}", format, error);
+            None
+        }
+    }
+}
+
+/// Parses the specs from a TOML, YAML (`.yaml` or `.yml`) or JSON file, by its extension.
+pub fn parse_spec(file_name: &str) -> Option<BenchClientConfig> {
+    match SpecFormat::from_extension(Path::new(file_name)) {
+        Some(format) => parse_file(file_name, format),
+        None => {
+            error!(
+                "Unsupported specs file {}, expected a .toml, .yaml or .json file",
+                file_name
+            );
+            None
+        }
+    }
+}
+
+pub fn parse_toml(file_name: &str) -> Option<BenchClientConfig> {
+    parse_file(file_name, SpecFormat::Toml)
 }
 
 pub fn from_get_url(url: String) -> BenchClientConfig {
     BenchClientConfig::new(url)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+    #[test]
+    fn spec_formats() {
+        let parse = |file: &str| {
+            let specs = parse_spec(&format!("{}/{}", FIXTURES, file)).unwrap();
+            serde_json::to_value(specs).unwrap()
+        };
+
+        let toml_specs = parse("specs.toml");
+        assert_eq!(toml_specs["url"], "http://localhost:8080/items/");
+        assert_eq!(toml_specs["requests"][1]["weight"], 2);
+        assert_eq!(toml_specs, parse("specs.yaml"));
+        assert_eq!(toml_specs, parse("specs.json"));
+
+        assert!(parse_toml(&format!("{}/specs.toml", FIXTURES)).is_some());
+        assert!(parse_spec(&format!("{}/headers.txt", FIXTURES)).is_none());
+    }
+}
@@ -0,0 +1,100 @@
+use crate::BenchClientConfig;
+
+/// The headers whose values are never written to the artifacts or logs.
+const SECRET_HEADERS: [&str; 6] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+    "x-amz-security-token",
+];
+const REDACTED: &str = "<redacted>";
+
+/// Masks the values of the secret headers: the `SECRET_HEADERS` and the configured `redact_headers`.
+/// The names are compared case-insensitively.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HeaderRedactor {
+    redact_headers: Vec<String>,
+}
+
+impl HeaderRedactor {
+    pub fn new(redact_headers: &[String]) -> Self {
+        Self {
+            redact_headers: redact_headers
+                .iter()
+                .map(|name| name.trim().to_lowercase())
+                .collect(),
+        }
+    }
+
+    pub fn from_config(config: &BenchClientConfig) -> Self {
+        Self::new(config.redact_headers.as_deref().unwrap_or_default())
+    }
+
+    pub fn is_secret(&self, name: &str) -> bool {
+        let name = name.trim().to_lowercase();
+        SECRET_HEADERS.contains(&name.as_str()) || self.redact_headers.contains(&name)
+    }
+
+    /// The value of the header, unless it is secret.
+    pub fn redact<'v>(&self, name: &str, value: &'v str) -> &'v str {
+        match self.is_secret(name) {
+            true => REDACTED,
+            false => value,
+        }
+    }
+}
+
+impl BenchClientConfig {
+    /// The config with the secret header values, the bearer tokens and the AWS credentials masked,
+    /// e.g. for writing it into the report.
+    pub fn redacted(&self) -> Self {
+        let redactor = HeaderRedactor::from_config(self);
+        let mut config = self.clone();
+        if let Some(headers) = config.headers.as_mut() {
+            for (name, value) in headers.iter_mut() {
+                *value = redactor.redact(name, value).to_string();
+            }
+        }
+        let redact = |secret: &mut Option<String>| {
+            if secret.is_some() {
+                *secret = Some(REDACTED.to_string());
+            }
+        };
+        redact(&mut config.bearer_token);
+        if let Some(tokens) = config.bearer_tokens.as_mut() {
+            tokens.fill(REDACTED.to_string());
+        }
+        if let Some(aws_sigv4) = config.aws_sigv4.as_mut() {
+            redact(&mut aws_sigv4.access_key_id);
+            redact(&mut aws_sigv4.secret_access_key);
+            redact(&mut aws_sigv4.session_token);
+        }
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacted_config() {
+        let mut config = BenchClientConfig::new("http://localhost:8080".to_string());
+        config.headers = Some(vec![
+            ("Authorization".to_string(), "Bearer s3cr3t".to_string()),
+            ("X-Tenant-Token".to_string(), "t0k3n".to_string()),
+            ("Accept".to_string(), "application/json".to_string()),
+        ]);
+        config.bearer_tokens = Some(vec!["s3cr3t".to_string(), "0th3r".to_string()]);
+        config.redact_headers = Some(vec!["x-tenant-token".to_string()]);
+
+        let serialized = serde_json::to_string(&config.redacted()).unwrap();
+        for secret in ["s3cr3t", "t0k3n", "0th3r"] {
+            assert!(!serialized.contains(secret));
+        }
+        assert!(serialized.contains("application/json"));
+        assert!(config.redacted().bearer_token.is_none());
+    }
+}
@@ -0,0 +1,141 @@
+use std::{future::Future, pin::Pin, time::Duration};
+use tokio::{sync::watch, time::Instant};
+
+/// The source of the time by which a sampler measures, paces and times out the requests.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    /// Waits until the clock reaches the `deadline`.
+    fn sleep_until(&self, deadline: Instant) -> Pin<Box<dyn Future<Output = ()> + Send + '_>>;
+
+    fn elapsed_since(&self, earlier: Instant) -> Duration {
+        self.now().saturating_duration_since(earlier)
+    }
+}
+
+impl dyn Clock + '_ {
+    pub async fn sleep(&self, duration: Duration) {
+        self.sleep_until(self.now() + duration).await
+    }
+
+    /// Runs the `future` until the clock reaches the `deadline`, `None` if it did not complete by then.
+    pub async fn timeout_at<F: Future>(&self, deadline: Instant, future: F) -> Option<F::Output> {
+        tokio::select! {
+            biased;
+            output = future => Some(output),
+            _ = self.sleep_until(deadline) => None,
+        }
+    }
+}
+
+/// The monotonic clock of the system.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        Box::pin(tokio::time::sleep_until(deadline))
+    }
+}
+
+/// A clock which only moves when advanced, for deterministic timings in tests:
+/// the sleepers wake up once the clock is advanced to their deadline.
+#[derive(Debug)]
+pub struct MockClock {
+    now: watch::Sender<Instant>,
+}
+
+impl MockClock {
+    pub fn new() -> Self {
+        let (now, _) = watch::channel(Instant::now());
+        Self { now }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.send_modify(|now| *now += duration);
+    }
+
+    /// Whether anyone waits for the clock to be advanced.
+    pub fn has_sleepers(&self) -> bool {
+        self.now.receiver_count() > 0
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        *self.now.borrow()
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        let mut now = self.now.subscribe();
+        Box::pin(async move {
+            while *now.borrow_and_update() < deadline {
+                // NOTE: the sender outlives the borrow of the clock
+                if now.changed().await.is_err() {
+                    return;
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn mock_clock() {
+        let clock = MockClock::default();
+        let start = clock.now();
+        assert_eq!(clock.elapsed_since(start), Duration::ZERO);
+        // reading the clock does not move it
+        assert_eq!(clock.now(), start);
+
+        clock.advance(Duration::from_millis(250));
+        clock.advance(Duration::from_micros(3));
+        assert_eq!(clock.elapsed_since(start), Duration::from_micros(250_003));
+        // an earlier clock never yields a negative duration
+        assert_eq!(
+            SystemClock.elapsed_since(clock.now() + Duration::from_secs(3_600)),
+            Duration::ZERO
+        );
+    }
+
+    #[tokio::test]
+    async fn mock_sleep() {
+        let clock = Arc::new(MockClock::default());
+        let start = clock.now();
+
+        let sleeper = clock.clone();
+        let task = tokio::spawn(async move {
+            let clock: &dyn Clock = sleeper.as_ref();
+            let timed_out = clock.timeout_at(start + Duration::from_millis(30), async {
+                clock.sleep(Duration::from_millis(50)).await;
+            });
+            (timed_out.await, clock.now())
+        });
+        while !clock.has_sleepers() {
+            tokio::task::yield_now().await;
+        }
+        clock.advance(Duration::from_millis(29));
+        tokio::task::yield_now().await;
+        assert!(!task.is_finished());
+
+        clock.advance(Duration::from_millis(1));
+        let (timed_out, woken) = task.await.unwrap();
+        assert!(timed_out.is_none());
+        assert_eq!(woken - start, Duration::from_millis(30));
+        assert!(!clock.has_sleepers());
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{BenchClientConfig, BurlError, BurlResult};
+use std::fs;
+
+/// The columns of a data row by their name (the header of the CSV).
+type DataRow = Vec<(String, String)>;
+
+/// Reads the rows of a CSV file with a header line naming the columns.
+fn read_data_rows(content: &str) -> BurlResult<Vec<DataRow>> {
+    let invalid = |err: csv::Error| BurlError::InvalidConfig {
+        issue: format!("Invalid data rows: {}", err),
+    };
+
+    let mut reader = csv::Reader::from_reader(content.as_bytes());
+    let columns: Vec<String> = reader
+        .headers()
+        .map_err(invalid)?
+        .iter()
+        .map(|column| column.trim().to_string())
+        .collect();
+
+    reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(invalid)?;
+            Ok(columns
+                .iter()
+                .cloned()
+                .zip(record.iter().map(String::from))
+                .collect())
+        })
+        .collect()
+}
+
+/// Substitutes the `{{column}}` placeholders of the template by the values of the row.
+/// Placeholders of unknown columns are kept.
+fn render(template: &str, row: &DataRow) -> String {
+    row.iter()
+        .fold(template.to_string(), |body, (column, value)| {
+            body.replace(&format!("{{{{{}}}}}", column), value)
+        })
+}
+
+/// The bodies of the `body_template_ref`, rendered with each row of the `data_rows_ref`, if configured.
+pub(crate) fn templated_bodies(config: &BenchClientConfig) -> BurlResult<Option<Vec<String>>> {
+    let (template_ref, rows_ref) = match (&config.body_template_ref, &config.data_rows_ref) {
+        (Some(template_ref), Some(rows_ref)) => (template_ref, rows_ref),
+        (None, None) => return Ok(None),
+        _ => {
+            return Err(BurlError::InvalidConfig {
+                issue: "`body_template_ref` and `data_rows_ref` are required together".to_string(),
+            })
+        }
+    };
+
+    let template = fs::read_to_string(template_ref)?;
+    let rows = read_data_rows(&fs::read_to_string(rows_ref)?)?;
+    if rows.is_empty() {
+        return Err(BurlError::InvalidConfig {
+            issue: format!("No data rows in '{}'", rows_ref),
+        });
+    }
+
+    Ok(Some(
+        rows.iter().map(|row| render(&template, row)).collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_rows() {
+        let rows = read_data_rows("name, price\njohn doezer,10.2\n\"doe, jane\",12.5\n").unwrap();
+        assert_eq!(rows.len(), 2);
+
+        let template = r#"{ "name": "{{name}}", "price": {{price}}, "id": "{{id}}" }"#;
+        assert_eq!(
+            render(template, &rows[0]),
+            r#"{ "name": "john doezer", "price": 10.2, "id": "{{id}}" }"#
+        );
+        assert_eq!(
+            render(template, &rows[1]),
+            r#"{ "name": "doe, jane", "price": 12.5, "id": "{{id}}" }"#
+        );
+
+        assert!(read_data_rows("name,price\njohn,10.2,extra\n").is_err());
+    }
+}
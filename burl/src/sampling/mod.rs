@@ -1,5 +1,34 @@
+mod clock;
+mod data_rows;
+mod redirect;
 mod request_factory;
+mod request_mix;
+mod resolved;
 mod sampler;
+mod signing;
+mod smoke;
+mod sse;
+mod think_time;
+mod tls;
+mod unix_socket;
+mod upload;
+mod validation;
 
-pub(crate) use request_factory::{Method, RequestFactory};
-pub use sampler::{RequestResult, SampleCollector, SampleResult, StatusCode};
+pub use clock::{Clock, MockClock, SystemClock};
+pub(crate) use request_factory::{with_provided_body, RequestFactory};
+pub use request_factory::{BodyProvider, HeaderMode, IpVersion, Method};
+pub use request_mix::RequestMix;
+pub use resolved::ResolvedRequest;
+pub use sampler::{
+    FailureKind, PauseSwitch, RateLimiter, RequestBudget, RequestResult, SampleCollector,
+    SampleResult, StatusCode,
+};
+pub use signing::AwsSigner;
+pub(crate) use signing::{build_request, sign_request};
+pub(crate) use smoke::smoke_request;
+pub use smoke::SmokeResult;
+pub(crate) use sse::read_events;
+pub use think_time::{ThinkTime, ThinkTimeSampler};
+pub use tls::TlsVersion;
+pub(crate) use upload::streamed_file;
+pub use validation::ResponseValidator;
@@ -0,0 +1,34 @@
+use reqwest::redirect::{Attempt, Policy};
+use std::{cell::Cell, future::Future};
+
+/// The max number of redirects to follow, as by reqwest's default policy.
+const MAX_REDIRECTS: usize = 10;
+
+tokio::task_local! {
+    /// The number of redirects followed by the request in flight on the current task.
+    static REDIRECT_HOPS: Cell<usize>;
+}
+
+/// Follows up to `MAX_REDIRECTS` redirects and counts them for the request in flight,
+/// see `count_redirects`. NOTE: the policy is checked within the future of the request.
+pub(crate) fn counting_redirect_policy() -> Policy {
+    Policy::custom(|attempt: Attempt| {
+        // NOTE: the previous urls include the one of the original request
+        let hops = attempt.previous().len();
+        if hops > MAX_REDIRECTS {
+            return attempt.error("too many redirects");
+        }
+        let _ = REDIRECT_HOPS.try_with(|redirect_hops| redirect_hops.set(hops));
+        attempt.follow()
+    })
+}
+
+/// Runs the request future and returns its output with the number of redirects it followed.
+pub(crate) async fn count_redirects<F: Future>(request: F) -> (F::Output, usize) {
+    REDIRECT_HOPS
+        .scope(Cell::new(0), async {
+            let output = request.await;
+            (output, REDIRECT_HOPS.with(Cell::get))
+        })
+        .await
+}
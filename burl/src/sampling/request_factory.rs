@@ -1,77 +1,671 @@
-use crate::{BenchClientConfig, BurlError, BurlResult};
+use super::{
+    data_rows::templated_bodies, redirect::counting_redirect_policy,
+    tls::insecure_hosts_tls_config, unix_socket::UnixSocketRelay, RequestMix, TlsVersion,
+};
+use crate::{BenchClientConfig, BurlError, BurlResult, HeaderRedactor, ThreadIdx};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::{write::GzEncoder, Compression};
 use log::warn;
-use reqwest::{Client, ClientBuilder, RequestBuilder, Result};
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, ACCEPT, CONTENT_ENCODING, CONTENT_TYPE, HOST,
+};
+use reqwest::{Client, ClientBuilder, Identity, RequestBuilder};
 use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::{fmt, fs, io::Write};
 
 #[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
 struct GqlQuery<'a> {
     query: &'a String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    variables: Option<&'a serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    operation_name: Option<&'a String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
 pub enum Method {
     #[default]
+    #[serde(alias = "GET")]
     Get,
+    #[serde(alias = "POST")]
     Post,
+    #[serde(alias = "PUT")]
     Put,
+    #[serde(alias = "DELETE")]
     Delete,
+    /// A partial update, with a body like POST and PUT.
+    #[serde(alias = "PATCH")]
+    Patch,
+    /// A GET without the response body, e.g. for liveness probes.
+    #[serde(alias = "HEAD")]
+    Head,
+    #[serde(alias = "OPTIONS")]
+    Options,
 }
 
-// pub struct RequestConfig {
-//     pub url: String,
-//     pub method: Method,
-//     pub headers: Option<String>, // TODO: make a KV collection
-//     #[serde(rename = "jsonPayload")]
-//     pub json_payload: Option<String>,
-//     #[serde(rename = "gqlQuery")]
-//     pub gql_query: Option<String>,
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Method::Get => write!(f, "GET"),
+            Method::Post => write!(f, "POST"),
+            Method::Put => write!(f, "PUT"),
+            Method::Delete => write!(f, "DELETE"),
+            Method::Patch => write!(f, "PATCH"),
+            Method::Head => write!(f, "HEAD"),
+            Method::Options => write!(f, "OPTIONS"),
+        }
+    }
+}
 
-//     // #[serde(rename = "bearerToken")]
-//     pub bearer_token: Option<String>,
-// }
+/// The IP version of the connections, e.g. to isolate the latency of a network path
+/// to a dual-stack endpoint.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IpVersion {
+    #[serde(rename = "4")]
+    V4,
+    #[serde(rename = "6")]
+    V6,
+}
+
+impl IpVersion {
+    /// The unspecified local address of the version; binding to it restricts the client
+    /// to the resolved addresses of the version.
+    fn local_address(&self) -> IpAddr {
+        match self {
+            IpVersion::V4 => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            IpVersion::V6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        }
+    }
+}
+
+/// How headers with the same name are combined, see `BenchClientConfig::header_mode`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HeaderMode {
+    /// All values are sent.
+    #[default]
+    Append,
+    /// Only the last value is sent.
+    Set,
+}
 
 pub struct RequestFactory {
     client: Client,
+    /// The headers read from the `headers_file`.
+    file_headers: Vec<(String, String)>,
+    /// The bodies rendered from the `body_template_ref` and the `data_rows_ref`.
+    templated_bodies: Option<Vec<String>>,
+    /// The decoded `body_base64`.
+    binary_body: Option<Vec<u8>>,
+    /// Routes the requests to the `unix_socket`.
+    unix_socket_relay: Option<UnixSocketRelay>,
+}
+
+/// Parses `Name: Value` lines, skipping empty lines and `#` comments.
+fn parse_headers(content: &str) -> BurlResult<Vec<(String, String)>> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match line.split_once(':') {
+            Some((name, value)) if !name.trim().is_empty() => {
+                Ok((name.trim().to_string(), value.trim().to_string()))
+            }
+            _ => Err(BurlError::InvalidConfig {
+                issue: format!("Invalid header line '{}', expected 'Name: Value'", line),
+            }),
+        })
+        .collect()
+}
+
+/// Assembles the headers, combining values of the same name according to the `mode`.
+fn header_map<'h>(
+    headers: impl Iterator<Item = &'h (String, String)>,
+    mode: HeaderMode,
+    redactor: &HeaderRedactor,
+) -> BurlResult<HeaderMap> {
+    let mut header_map = HeaderMap::new();
+    for (name, value) in headers {
+        let invalid_header = |err: &dyn fmt::Display| BurlError::InvalidConfig {
+            issue: format!(
+                "Invalid header '{}: {}': {}",
+                name,
+                redactor.redact(name, value),
+                err
+            ),
+        };
+        let name = HeaderName::from_bytes(name.as_bytes()).map_err(|err| invalid_header(&err))?;
+        let value = HeaderValue::from_str(value).map_err(|err| invalid_header(&err))?;
+        match mode {
+            HeaderMode::Append => {
+                header_map.append(name, value);
+            }
+            HeaderMode::Set => {
+                header_map.insert(name, value);
+            }
+        }
+    }
+    Ok(header_map)
+}
+
+/// Loads the client certificate (and key) for mutual TLS, if configured.
+/// The certificate file may already contain the private key, otherwise it is read from `client_key_ref`.
+fn client_identity(config: &BenchClientConfig) -> BurlResult<Option<Identity>> {
+    let cert_ref = match &config.client_cert_ref {
+        Some(cert_ref) => cert_ref,
+        None => return Ok(None),
+    };
+
+    let mut pem = fs::read(cert_ref)?;
+    if let Some(key_ref) = &config.client_key_ref {
+        pem.push(b'\n');
+        pem.extend(fs::read(key_ref)?);
+    }
+
+    let identity = Identity::from_pem(&pem)?;
+    Ok(Some(identity))
+}
+
+/// Pins the TLS versions of the client, if configured.
+/// TLS 1.3 requires the rustls backend, which in turn does not support TLS 1.0 and 1.1.
+fn with_tls_versions(
+    mut client_builder: ClientBuilder,
+    config: &BenchClientConfig,
+) -> BurlResult<ClientBuilder> {
+    let (min_version, max_version) = (config.min_tls_version, config.max_tls_version);
+    if let (Some(min_version), Some(max_version)) = (min_version, max_version) {
+        if min_version > max_version {
+            return Err(BurlError::InvalidConfig {
+                issue: format!(
+                    "The `min_tls_version` {} is above the `max_tls_version` {}",
+                    min_version, max_version
+                ),
+            });
+        }
+    }
+
+    if [min_version, max_version].contains(&Some(TlsVersion::Tls1_3)) {
+        client_builder = client_builder.use_rustls_tls();
+    }
+    if let Some(min_version) = min_version {
+        client_builder = client_builder.min_tls_version(min_version.into());
+    }
+    if let Some(max_version) = max_version {
+        client_builder = client_builder.max_tls_version(max_version.into());
+    }
+    Ok(client_builder)
+}
+
+/// Sends the request to the `host` (`host` or `host:port`) instead of the host of its url;
+/// the host is added to the label, by which the stats are grouped.
+fn with_target_host(
+    request: &RequestBuilder,
+    label: Option<&String>,
+    host: &str,
+) -> BurlResult<(RequestBuilder, Option<String>)> {
+    let invalid = || BurlError::InvalidConfig {
+        issue: format!("Invalid target host '{}'", host),
+    };
+
+    let (client, request) = request.try_clone().unwrap().build_split();
+    let mut request = request?;
+    let (host_name, port) = match host.rsplit_once(':') {
+        Some((host_name, port)) => (host_name, Some(port.parse().map_err(|_| invalid())?)),
+        None => (host, None),
+    };
+    let url = request.url_mut();
+    url.set_host(Some(host_name)).map_err(|_| invalid())?;
+    if port.is_some() {
+        url.set_port(port).map_err(|_| invalid())?;
+    }
+
+    let label = match label {
+        Some(label) => format!("{} @ {}", label, host),
+        None => host.to_string(),
+    };
+    Ok((RequestBuilder::from_parts(client, request), Some(label)))
+}
+
+/// The value of the query parameter in the url of the request, if any.
+fn query_param_value(request: &RequestBuilder, param: &str) -> Option<String> {
+    let request = request.try_clone()?.build().ok()?;
+    request
+        .url()
+        .query_pairs()
+        .find(|(name, _)| name == param)
+        .map(|(_, value)| value.into_owned())
+}
+
+/// Generates the body of each request from its iteration and the index of its sampler,
+/// superseding the configured payload, see `BenchClient::with_body_provider`.
+pub type BodyProvider = dyn Fn(usize, ThreadIdx) -> Vec<u8> + Send + Sync;
+
+/// A clone of the request with the body of the `provider` for the iteration of the sampler.
+pub(crate) fn with_provided_body(
+    request: &RequestBuilder,
+    provider: &BodyProvider,
+    iteration: usize,
+    thread_idx: ThreadIdx,
+    compress: bool,
+) -> BurlResult<RequestBuilder> {
+    let body = provider(iteration, thread_idx);
+    with_body(request.try_clone().unwrap(), body, compress)
+}
+
+/// Decodes the base64 body, ignoring whitespace, e.g. the line breaks of a multi-line string.
+fn decode_base64(body: &str) -> BurlResult<Vec<u8>> {
+    let body: String = body.split_whitespace().collect();
+    STANDARD
+        .decode(body)
+        .map_err(|err| BurlError::InvalidConfig {
+            issue: format!("Invalid `body_base64`: {}", err),
+        })
+}
+
+/// Sets the body of the request, gzip-compressed if `compress` is set.
+fn with_body(request: RequestBuilder, body: Vec<u8>, compress: bool) -> BurlResult<RequestBuilder> {
+    if !compress {
+        return Ok(request.body(body));
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&body)?;
+    let compressed = encoder.finish()?;
+    Ok(request.header(CONTENT_ENCODING, "gzip").body(compressed))
+}
+
+/// Sets the JSON body of the request along with its content type, which configured headers may override.
+fn with_json_body(
+    request: RequestBuilder,
+    json: Vec<u8>,
+    compress: bool,
+) -> BurlResult<RequestBuilder> {
+    Ok(with_body(request, json, compress)?.header(CONTENT_TYPE, "application/json"))
 }
 
 impl RequestFactory {
-    pub fn new(disable_certificate_validation: bool) -> Result<Self> {
-        let client = ClientBuilder::new()
-            // .redirect(redirect::Policy::none())
-            .danger_accept_invalid_certs(disable_certificate_validation)
-            .build()?;
-        Ok(Self { client })
-    }
-
-    pub fn assemble_request(&self, config: &BenchClientConfig) -> BurlResult<RequestBuilder> {
-        let mut request = match config.method {
-            Method::Get => self.client.get(&config.url),
-            Method::Post => {
-                let request = self.client.post(&config.url);
-                if let Some(json) = config.json_payload() {
-                    request.body(json)
+    pub fn new(config: &BenchClientConfig) -> BurlResult<Self> {
+        let accept_invalid_certs = config.disable_certificate_validation.unwrap_or_default();
+        let mut client_builder = ClientBuilder::new()
+            .redirect(counting_redirect_policy())
+            .danger_accept_invalid_certs(accept_invalid_certs)
+            .tcp_keepalive(config.tcp_keepalive());
+        if let Some(idle_timeout) = config.pool_idle_timeout() {
+            client_builder = client_builder.pool_idle_timeout(idle_timeout);
+        }
+        if let Some(max_idle) = config.pool_max_idle_per_host {
+            client_builder = client_builder.pool_max_idle_per_host(max_idle);
+        }
+        if let Some(timeout) = config.request_timeout() {
+            client_builder = client_builder.timeout(timeout);
+        }
+        if let Some(ip_version) = config.ip_version {
+            client_builder = client_builder.local_address(ip_version.local_address());
+        }
+
+        let identity = client_identity(config)?;
+        let pinned_tls_versions =
+            config.min_tls_version.is_some() || config.max_tls_version.is_some();
+        match &config.insecure_hosts {
+            Some(hosts) if !hosts.is_empty() && !accept_invalid_certs => {
+                if identity.is_some() {
+                    return Err(BurlError::InvalidConfig {
+                        issue: "`insecure_hosts` cannot be combined with a client certificate"
+                            .to_string(),
+                    });
+                }
+                if pinned_tls_versions {
+                    return Err(BurlError::InvalidConfig {
+                        issue: "`insecure_hosts` cannot be combined with pinned TLS versions"
+                            .to_string(),
+                    });
+                }
+                client_builder =
+                    client_builder.use_preconfigured_tls(insecure_hosts_tls_config(hosts));
+            }
+            _ => {
+                if let Some(identity) = identity {
+                    // NOTE: PEM identities are only supported by the rustls backend
+                    client_builder = client_builder.use_rustls_tls().identity(identity);
+                }
+                client_builder = with_tls_versions(client_builder, config)?;
+            }
+        }
+
+        let file_headers = match &config.headers_file {
+            Some(headers_file) => parse_headers(&fs::read_to_string(headers_file)?)?,
+            None => Vec::new(),
+        };
+
+        let client = client_builder
+            .build()
+            .map_err(|err| match pinned_tls_versions {
+                true => BurlError::InvalidConfig {
+                    issue: format!(
+                        "The TLS versions (min {:?}, max {:?}) are not supported: {}",
+                        config.min_tls_version, config.max_tls_version, err
+                    ),
+                },
+                false => BurlError::Client(err),
+            })?;
+        if let Some(upload_file_ref) = &config.upload_file_ref {
+            let other_bodies = [
+                config.json_payload.is_some(),
+                config.json_payload_ref.is_some(),
+                config.json_payloads.is_some(),
+                config.body_base64.is_some(),
+                config.gql_query.is_some(),
+            ];
+            if other_bodies.contains(&true) {
+                return Err(BurlError::InvalidConfig {
+                    issue: "`upload_file_ref` cannot be combined with a `json_payload`, \
+                            `json_payloads`, `body_base64` or `gql_query`"
+                        .to_string(),
+                });
+            }
+            // fails up front rather than on each request
+            fs::metadata(upload_file_ref)?;
+        }
+        Ok(Self {
+            client,
+            file_headers,
+            templated_bodies: templated_bodies(config)?,
+            binary_body: config
+                .body_base64
+                .as_deref()
+                .map(decode_base64)
+                .transpose()?,
+            unix_socket_relay: config
+                .unix_socket
+                .as_deref()
+                .map(UnixSocketRelay::start)
+                .transpose()?,
+        })
+    }
+
+    /// Assembles the request for the given thread, which may use its own payload and bearer token.
+    pub fn assemble_request(
+        &self,
+        config: &BenchClientConfig,
+        thread_idx: ThreadIdx,
+    ) -> BurlResult<RequestBuilder> {
+        self.assemble(
+            config,
+            &config.url,
+            &config.method,
+            config.thread_json_payload(thread_idx),
+            thread_idx,
+        )
+    }
+
+    /// Assembles the requests of the given thread: either the single configured request,
+    /// or the weighted mix of `requests`, shuffled with the configured seed and labeled by method.
+    pub fn assemble_request_mix(
+        &self,
+        config: &BenchClientConfig,
+        thread_idx: ThreadIdx,
+    ) -> BurlResult<RequestMix> {
+        let requests = self.assemble_labeled_mix(config, thread_idx)?;
+        let requests = match &config.label_by_query_param {
+            Some(param) => requests.relabeled(|request| query_param_value(request, param)),
+            None => requests,
+        };
+
+        match &config.target_hosts {
+            Some(hosts) if !hosts.is_empty() && config.pin_target_hosts() => {
+                let host = &hosts[thread_idx % hosts.len()];
+                requests.fanned_out(1, |request, label, _| {
+                    with_target_host(request, label, host)
+                })
+            }
+            Some(hosts) if !hosts.is_empty() => requests
+                .fanned_out(hosts.len(), |request, label, host_idx| {
+                    with_target_host(request, label, &hosts[host_idx])
+                }),
+            _ => Ok(requests),
+        }
+    }
+
+    fn assemble_labeled_mix(
+        &self,
+        config: &BenchClientConfig,
+        thread_idx: ThreadIdx,
+    ) -> BurlResult<RequestMix> {
+        let request_configs = match (&config.requests, config.write_fraction) {
+            (Some(request_configs), _) if !request_configs.is_empty() => request_configs,
+            (_, Some(write_fraction)) => {
+                return self.assemble_read_write_mix(config, write_fraction, thread_idx)
+            }
+            _ if self.templated_bodies.is_some() => {
+                return self.assemble_template_mix(config, thread_idx)
+            }
+            _ if config
+                .labeled_payloads
+                .as_ref()
+                .is_some_and(|p| !p.is_empty()) =>
+            {
+                return self.assemble_labeled_payload_mix(config, thread_idx)
+            }
+            _ => match &config.accept_headers {
+                Some(accept_headers) if !accept_headers.is_empty() => {
+                    return self.assemble_accept_mix(config, accept_headers, thread_idx)
+                }
+                _ => return Ok(self.assemble_request(config, thread_idx)?.into()),
+            },
+        };
+
+        let mut requests = Vec::with_capacity(request_configs.len());
+        let mut weights = Vec::with_capacity(request_configs.len());
+        for request_config in request_configs.iter() {
+            let url = request_config.url.as_ref().unwrap_or(&config.url);
+            let payload = request_config
+                .json_payload
+                .clone()
+                .or_else(|| config.thread_json_payload(thread_idx));
+            let request =
+                self.assemble(config, url, &request_config.method, payload, thread_idx)?;
+            requests.push((request, Some(request_config.method.to_string())));
+            weights.push(request_config.weight.unwrap_or(1));
+        }
+
+        // NOTE: each thread has its own (deterministic) order
+        let seed = config.seed().wrapping_add(thread_idx as u64);
+        Ok(RequestMix::shuffled(
+            requests,
+            &weights,
+            config.n_runs(),
+            seed,
+        ))
+    }
+
+    /// Assembles the request once for each `Accept` header, labeled by it and cycled per iteration.
+    fn assemble_accept_mix(
+        &self,
+        config: &BenchClientConfig,
+        accept_headers: &[String],
+        thread_idx: ThreadIdx,
+    ) -> BurlResult<RequestMix> {
+        let requests = accept_headers
+            .iter()
+            .map(|accept| {
+                let request = self.assemble_request(config, thread_idx)?;
+                Ok((request.header(ACCEPT, accept), Some(accept.clone())))
+            })
+            .collect::<BurlResult<Vec<_>>>()?;
+        Ok(RequestMix::cycled(requests))
+    }
+
+    /// Assembles a request per templated body, cycled per iteration. Each thread starts at
+    /// another row, so that the threads do not send the same rows at the same time.
+    fn assemble_template_mix(
+        &self,
+        config: &BenchClientConfig,
+        thread_idx: ThreadIdx,
+    ) -> BurlResult<RequestMix> {
+        let bodies = self.templated_bodies.as_deref().unwrap_or_default();
+        if !matches!(config.method, Method::Post | Method::Put | Method::Patch) {
+            return Err(BurlError::InvalidConfig {
+                issue: format!(
+                    "A body template requires a POST, PUT or PATCH request, not {}",
+                    config.method
+                ),
+            });
+        }
+
+        let requests = (0..bodies.len())
+            .map(|idx| {
+                let body = bodies[(idx + thread_idx) % bodies.len()].clone();
+                let request =
+                    self.assemble(config, &config.url, &config.method, Some(body), thread_idx)?;
+                Ok((request, None))
+            })
+            .collect::<BurlResult<Vec<_>>>()?;
+        Ok(RequestMix::cycled(requests))
+    }
+
+    /// Assembles the request once for each of the `labeled_payloads`, labeled accordingly
+    /// and cycled per iteration.
+    fn assemble_labeled_payload_mix(
+        &self,
+        config: &BenchClientConfig,
+        thread_idx: ThreadIdx,
+    ) -> BurlResult<RequestMix> {
+        let labeled_payloads = config.labeled_payloads.as_deref().unwrap_or_default();
+        let requests = labeled_payloads
+            .iter()
+            .map(|(label, payload)| {
+                let payload = Some(payload.clone());
+                let request =
+                    self.assemble(config, &config.url, &config.method, payload, thread_idx)?;
+                Ok((request, Some(label.clone())))
+            })
+            .collect::<BurlResult<Vec<_>>>()?;
+        Ok(RequestMix::cycled(requests))
+    }
+
+    /// Assembles a GET request and a request writing the payload, which is chosen with
+    /// probability `write_fraction` per iteration.
+    fn assemble_read_write_mix(
+        &self,
+        config: &BenchClientConfig,
+        write_fraction: f64,
+        thread_idx: ThreadIdx,
+    ) -> BurlResult<RequestMix> {
+        if !(0.0..=1.0).contains(&write_fraction) {
+            return Err(BurlError::InvalidConfig {
+                issue: format!(
+                    "The `write_fraction` {} is not within [0, 1]",
+                    write_fraction
+                ),
+            });
+        }
+
+        let write_method = config.write_method.clone().unwrap_or(Method::Post);
+        let read = self.assemble(config, &config.url, &Method::Get, None, thread_idx)?;
+        let write = self.assemble(
+            config,
+            &config.url,
+            &write_method,
+            config.thread_json_payload(thread_idx),
+            thread_idx,
+        )?;
+
+        let requests = vec![
+            (read, Some(Method::Get.to_string())),
+            (write, Some(write_method.to_string())),
+        ];
+        let seed = config.seed().wrapping_add(thread_idx as u64);
+        // NOTE: the fraction is within [0, 1], hence the probabilities are valid
+        Ok(RequestMix::sampled(
+            requests,
+            &[1.0 - write_fraction, write_fraction],
+            config.n_runs(),
+            seed,
+        )
+        .expect("valid probabilities"))
+    }
+
+    fn assemble(
+        &self,
+        config: &BenchClientConfig,
+        url: &str,
+        method: &Method,
+        json_payload: Option<String>,
+        thread_idx: ThreadIdx,
+    ) -> BurlResult<RequestBuilder> {
+        let routed = match &self.unix_socket_relay {
+            Some(relay) => Some(relay.route(url)?),
+            None => None,
+        };
+        let url = routed.as_ref().map_or(url, |(url, _)| url.as_str());
+
+        let mut request = match method {
+            Method::Get => match json_payload {
+                Some(json) if config.allow_get_body() => with_json_body(
+                    self.client.get(url),
+                    json.into_bytes(),
+                    config.compress_request_body(),
+                )?,
+                _ => self.client.get(url),
+            },
+            Method::Post | Method::Put | Method::Patch => {
+                let request = match method {
+                    Method::Put => self.client.put(url),
+                    Method::Patch => self.client.patch(url),
+                    _ => self.client.post(url),
+                };
+                if let Some(json) = json_payload {
+                    with_json_body(request, json.into_bytes(), config.compress_request_body())?
+                } else if let Some(body) = &self.binary_body {
+                    with_body(request, body.clone(), config.compress_request_body())?
+                        .header(CONTENT_TYPE, config.body_content_type())
+                } else if config.upload_file_ref.is_some() {
+                    // NOTE: the file is streamed by the sampler, as a streamed body cannot be cloned
+                    request.header(CONTENT_TYPE, config.body_content_type())
                 } else if let Some(query) = &config.gql_query {
-                    let gql_query_payload = GqlQuery { query };
-                    request.json(&gql_query_payload)
+                    let gql_query_payload = serde_json::to_vec(&GqlQuery {
+                        query,
+                        variables: config.gql_variables.as_ref(),
+                        operation_name: config.gql_operation_name.as_ref(),
+                    })?;
+                    with_json_body(request, gql_query_payload, config.compress_request_body())?
                 } else {
                     return Err(BurlError::InvalidConfig {
-                        issue: "Expected either `json_payload` or `gql_query` for the POST request"
-                            .to_string(),
+                        issue: format!(
+                            "Expected either `json_payload`, `body_base64`, `upload_file_ref` or `gql_query` for the {} request",
+                            method
+                        ),
                     });
                 }
             }
-            _ => unimplemented!("todo"),
+            Method::Delete => match json_payload {
+                Some(json) if config.allow_delete_body() => with_json_body(
+                    self.client.delete(url),
+                    json.into_bytes(),
+                    config.compress_request_body(),
+                )?,
+                _ => self.client.delete(url),
+            },
+            Method::Head => self.client.head(url),
+            Method::Options => self.client.request(reqwest::Method::OPTIONS, url),
         };
 
-        if let Some(token) = &config.bearer_token {
+        if let Some((_, host)) = &routed {
+            request = request.header(HOST, host.as_str());
+        }
+        if let Some(token) = config.thread_bearer_token(thread_idx) {
             request = request.bearer_auth(token);
         }
 
-        if let Some(headers) = &config.headers {
-            for (header_name, value) in headers.iter() {
-                request = request.header(header_name, value);
-            }
-        } else if config.method == Method::Post {
+        let inline_headers = config.headers.iter().flatten();
+        let headers = header_map(
+            inline_headers.chain(self.file_headers.iter()),
+            config.header_mode.unwrap_or_default(),
+            &HeaderRedactor::from_config(config),
+        )?;
+        request = request.headers(headers);
+        if config.headers.is_none() && self.file_headers.is_empty() && *method == Method::Post {
             warn!("The method is 'POST' but no request headers are configured");
         }
 
@@ -81,3 +675,543 @@ impl RequestFactory {
         Ok(request)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use std::time::Duration;
+    use wiremock::matchers::{header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+    #[test]
+    fn thread_requests() {
+        let mut config = BenchClientConfig::new("http://localhost".to_string());
+        config.method = Method::Post;
+        config.bearer_tokens = Some(vec!["token-a".to_string(), "token-b".to_string()]);
+        config.json_payloads = Some(vec![r#"{ "id": 0 }"#.to_string()]);
+
+        let factory = RequestFactory::new(&config).unwrap();
+        for (thread_idx, token) in [(0, "token-a"), (1, "token-b"), (2, "token-a")] {
+            let request = factory
+                .assemble_request(&config, thread_idx)
+                .unwrap()
+                .build()
+                .unwrap();
+            assert_eq!(
+                request.headers()["authorization"],
+                format!("Bearer {}", token).as_str()
+            );
+            assert_eq!(
+                request.body().and_then(|b| b.as_bytes()),
+                Some(br#"{ "id": 0 }"#.as_slice())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn compressed_request_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("content-encoding", "gzip"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let payload = r#"{ "name": "john doezer", "price": 10.2 }"#;
+        let mut config = BenchClientConfig::new(server.uri());
+        config.method = Method::Post;
+        config.json_payload = Some(payload.to_string());
+        config.compress_request_body = Some(true);
+
+        let factory = RequestFactory::new(&config).unwrap();
+        let response = factory
+            .assemble_request(&config, 0)
+            .unwrap()
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+
+        let requests = server.received_requests().await.unwrap();
+        let mut decompressed = String::new();
+        GzDecoder::new(requests[0].body.as_slice())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, payload);
+    }
+
+    #[tokio::test]
+    async fn ip_version() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        // the mock listens on 127.0.0.1 only, whereas `localhost` may resolve to ::1 too
+        let url = format!("http://localhost:{}", server.address().port());
+        let mut config = BenchClientConfig::new(url);
+        let send = |config: &BenchClientConfig| {
+            let request = RequestFactory::new(config)
+                .unwrap()
+                .assemble_request(config, 0)
+                .unwrap();
+            request.send()
+        };
+
+        config.ip_version = Some(IpVersion::V4);
+        assert_eq!(send(&config).await.unwrap().status(), 200);
+        config.ip_version = Some(IpVersion::V6);
+        assert!(send(&config).await.is_err());
+
+        let config: BenchClientConfig =
+            serde_json::from_str(r#"{ "url": "", "method": "Get", "ipVersion": "6" }"#).unwrap();
+        assert_eq!(config.ip_version, Some(IpVersion::V6));
+    }
+
+    #[tokio::test]
+    async fn base64_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("content-type", "application/x-protobuf"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut config = BenchClientConfig::new(server.uri());
+        config.method = Method::Post;
+        config.body_base64 = Some("AAEC\n/f7/".to_string());
+        config.body_content_type = Some("application/x-protobuf".to_string());
+
+        let factory = RequestFactory::new(&config).unwrap();
+        let request = factory.assemble_request(&config, 0).unwrap();
+        assert_eq!(request.send().await.unwrap().status(), 200);
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests[0].body, vec![0, 1, 2, 253, 254, 255]);
+
+        config.body_base64 = Some("not base64!".to_string());
+        assert!(RequestFactory::new(&config).is_err());
+    }
+
+    #[test]
+    fn gql_variables() {
+        let mut config: BenchClientConfig = toml::from_str(
+            r#"
+            url = "http://localhost/graphql"
+            method = "Post"
+            gql_query = "query Item($id: ID!) { item(id: $id) { name } }"
+            gql_operation_name = "Item"
+            [gql_variables]
+            id = "42"
+            "#,
+        )
+        .unwrap();
+
+        let factory = RequestFactory::new(&config).unwrap();
+        let request = factory
+            .assemble_request(&config, 0)
+            .unwrap()
+            .build()
+            .unwrap();
+        let body: serde_json::Value =
+            serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "query": "query Item($id: ID!) { item(id: $id) { name } }",
+                "variables": { "id": "42" },
+                "operationName": "Item"
+            })
+        );
+
+        config.gql_variables = None;
+        config.gql_operation_name = None;
+        let request = factory
+            .assemble_request(&config, 0)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(
+            request.body().unwrap().as_bytes().unwrap(),
+            br#"{"query":"query Item($id: ID!) { item(id: $id) { name } }"}"#
+        );
+    }
+
+    #[test]
+    fn json_content_type() {
+        let mut config = BenchClientConfig::new("http://localhost".to_string());
+        config.method = Method::Post;
+        config.json_payload = Some(r#"{ "id": 0 }"#.to_string());
+
+        let factory = RequestFactory::new(&config).unwrap();
+        let request = factory
+            .assemble_request(&config, 0)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(request.headers()[CONTENT_TYPE], "application/json");
+
+        // configured headers take precedence
+        config.headers = Some(vec![(
+            "Content-Type".to_string(),
+            "application/vnd.api+json".to_string(),
+        )]);
+        let request = factory
+            .assemble_request(&config, 0)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(request.headers().get_all(CONTENT_TYPE).iter().count(), 1);
+        assert_eq!(request.headers()[CONTENT_TYPE], "application/vnd.api+json");
+    }
+
+    #[test]
+    fn write_fraction() {
+        let mut config = BenchClientConfig::new("http://localhost".to_string());
+        config.json_payload = Some(r#"{ "id": 0 }"#.to_string());
+        config.write_fraction = Some(0.2);
+        config.n_runs = Some(10_000);
+
+        let factory = RequestFactory::new(&config).unwrap();
+        let requests = factory.assemble_request_mix(&config, 0).unwrap();
+        let n_writes = (0..10_000)
+            .filter(|iteration| {
+                let (request, label) = requests.get(*iteration);
+                let request = request.try_clone().unwrap().build().unwrap();
+                assert_eq!(request.method().as_str(), label.unwrap());
+                request.method() == reqwest::Method::POST
+            })
+            .count();
+        assert!((n_writes as f64 / 10_000.0 - 0.2).abs() < 0.02);
+
+        config.write_fraction = Some(1.5);
+        assert!(factory.assemble_request_mix(&config, 0).is_err());
+    }
+
+    #[tokio::test]
+    async fn duplicate_headers() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let mut config = BenchClientConfig::new(server.uri());
+        config.headers = Some(vec![
+            ("X-Foo".to_string(), "a".to_string()),
+            ("X-Foo".to_string(), "b".to_string()),
+        ]);
+
+        for (mode, expected) in [
+            (None, vec!["a", "b"]),
+            (Some(HeaderMode::Append), vec!["a", "b"]),
+            (Some(HeaderMode::Set), vec!["b"]),
+        ] {
+            config.header_mode = mode;
+            let factory = RequestFactory::new(&config).unwrap();
+            factory
+                .assemble_request(&config, 0)
+                .unwrap()
+                .send()
+                .await
+                .unwrap();
+
+            let requests = server.received_requests().await.unwrap();
+            let values: Vec<&str> = requests
+                .last()
+                .unwrap()
+                .headers
+                .get(&"x-foo".into())
+                .unwrap()
+                .iter()
+                .map(|value| value.as_str())
+                .collect();
+            assert_eq!(values, expected);
+        }
+
+        config.headers = Some(vec![("X Foo".to_string(), "a".to_string())]);
+        let factory = RequestFactory::new(&config).unwrap();
+        assert!(matches!(
+            factory.assemble_request(&config, 0),
+            Err(BurlError::InvalidConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn headers_file() {
+        let mut config = BenchClientConfig::new("http://localhost".to_string());
+        config.headers = Some(vec![("X-Tag".to_string(), "inline".to_string())]);
+        config.headers_file = Some(format!("{}/headers.txt", FIXTURES));
+
+        let factory = RequestFactory::new(&config).unwrap();
+        let request = factory
+            .assemble_request(&config, 0)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let headers = request.headers();
+        assert_eq!(headers["accept"], "application/json");
+        assert_eq!(headers["x-tenant"], "tenant-a");
+        assert_eq!(headers["x-trace-context"], "key=value:with:colons");
+        let tags: Vec<_> = headers.get_all("x-tag").iter().collect();
+        assert_eq!(tags, vec!["inline", "first", "second"]);
+
+        assert!(parse_headers("X-Tenant tenant-a").is_err());
+    }
+
+    /// Serves HTTPS with a self-signed certificate for `localhost` and `127.0.0.1`.
+    fn self_signed_server() -> u16 {
+        use std::io::BufReader;
+        use std::net::TcpListener;
+
+        let read_pem = |file: &str| {
+            let file = fs::File::open(format!("{}/{}", FIXTURES, file)).unwrap();
+            BufReader::new(file)
+        };
+        let certs = rustls_pemfile::certs(&mut read_pem("server_cert.pem"))
+            .unwrap()
+            .into_iter()
+            .map(rustls::Certificate)
+            .collect();
+        let key = rustls_pemfile::pkcs8_private_keys(&mut read_pem("server_key.pem")).unwrap();
+        let tls_config = std::sync::Arc::new(
+            rustls::ServerConfig::builder()
+                .with_safe_defaults()
+                .with_no_client_auth()
+                .with_single_cert(certs, rustls::PrivateKey(key[0].clone()))
+                .unwrap(),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let connection = rustls::ServerConnection::new(tls_config.clone()).unwrap();
+                let mut tls = rustls::StreamOwned::new(connection, stream);
+                let mut buffer = [0; 1024];
+                // NOTE: fails for clients which reject the certificate
+                if tls.read(&mut buffer).is_ok() {
+                    let _ = tls.write_all(
+                        b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                    );
+                    tls.conn.send_close_notify();
+                    let _ = tls.flush();
+                }
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn insecure_hosts() {
+        let port = self_signed_server();
+        let mut config = BenchClientConfig::new(format!("https://localhost:{}", port));
+        config.insecure_hosts = Some(vec!["localhost".to_string()]);
+        let factory = RequestFactory::new(&config).unwrap();
+
+        let response = factory
+            .assemble_request(&config, 0)
+            .unwrap()
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+
+        // the same server, but not allowlisted by its IP
+        config.url = format!("https://127.0.0.1:{}", port);
+        let error = factory
+            .assemble_request(&config, 0)
+            .unwrap()
+            .send()
+            .await
+            .unwrap_err();
+        assert!(error.is_connect());
+        assert!(format!("{:?}", error).contains("InvalidCertificate"));
+    }
+
+    #[test]
+    fn client_identity() {
+        let mut config = BenchClientConfig::new("https://localhost".to_string());
+        config.client_cert_ref = Some(format!("{}/client_cert.pem", FIXTURES));
+        config.client_key_ref = Some(format!("{}/client_key.pem", FIXTURES));
+
+        assert!(super::client_identity(&config).unwrap().is_some());
+        assert!(RequestFactory::new(&config).is_ok());
+
+        // the certificate alone is not a valid identity
+        config.client_key_ref = None;
+        assert!(matches!(
+            RequestFactory::new(&config),
+            Err(BurlError::Client(_))
+        ));
+
+        config.client_cert_ref = Some(format!("{}/missing.pem", FIXTURES));
+        assert!(matches!(
+            RequestFactory::new(&config),
+            Err(BurlError::IO(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn get_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let payload = r#"{ "query": { "match_all": {} } }"#;
+        let mut config = BenchClientConfig::new(format!("{}/_search", server.uri()));
+        config.json_payload = Some(payload.to_string());
+        let factory = RequestFactory::new(&config).unwrap();
+
+        for allow_get_body in [false, true] {
+            config.allow_get_body = Some(allow_get_body);
+            let request = factory.assemble_request(&config, 0).unwrap();
+            request.send().await.unwrap();
+        }
+
+        let bodies: Vec<Vec<u8>> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|request| request.body)
+            .collect();
+        assert_eq!(bodies, vec![Vec::new(), payload.as_bytes().to_vec()]);
+    }
+
+    #[tokio::test]
+    async fn delete_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("DELETE"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let payload = r#"{ "ids": [1, 2, 3] }"#;
+        let mut config = BenchClientConfig::new(format!("{}/items", server.uri()));
+        config.method = Method::Delete;
+        config.json_payload = Some(payload.to_string());
+        let factory = RequestFactory::new(&config).unwrap();
+
+        for allow_delete_body in [false, true] {
+            config.allow_delete_body = Some(allow_delete_body);
+            let request = factory.assemble_request(&config, 0).unwrap();
+            request.send().await.unwrap();
+        }
+
+        let bodies: Vec<Vec<u8>> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|request| request.body)
+            .collect();
+        assert_eq!(bodies, vec![Vec::new(), payload.as_bytes().to_vec()]);
+    }
+
+    #[tokio::test]
+    async fn patch_head_options() {
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::any())
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        for method in ["Patch", "HEAD", "Options"] {
+            let config: BenchClientConfig = toml::from_str(&format!(
+                "url = \"{}/items\"\nmethod = \"{}\"\njson_payload = '{{ \"price\": 10.2 }}'",
+                server.uri(),
+                method
+            ))
+            .unwrap();
+            let factory = RequestFactory::new(&config).unwrap();
+            let request = factory.assemble_request(&config, 0).unwrap();
+            request.send().await.unwrap();
+        }
+
+        let requests: Vec<(String, usize)> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|request| (request.method.to_string(), request.body.len()))
+            .collect();
+        assert_eq!(
+            requests,
+            vec![
+                ("PATCH".to_string(), 17),
+                ("HEAD".to_string(), 0),
+                ("OPTIONS".to_string(), 0)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn keepalive() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let mut config = BenchClientConfig::new(server.uri());
+        config.tcp_keepalive_secs = Some(15.0);
+        config.pool_idle_timeout_secs = Some(5.0);
+        config.pool_max_idle_per_host = Some(4);
+        assert_eq!(config.tcp_keepalive(), Some(Duration::from_secs(15)));
+
+        let factory = RequestFactory::new(&config).unwrap();
+        let response = factory.assemble_request(&config, 0).unwrap().send().await;
+        assert_eq!(response.unwrap().status(), 200);
+    }
+
+    #[test]
+    fn tls_versions() {
+        let mut config = BenchClientConfig::new("https://localhost".to_string());
+        config.min_tls_version = Some(TlsVersion::Tls1_2);
+        config.max_tls_version = Some(TlsVersion::Tls1_2);
+        assert!(RequestFactory::new(&config).is_ok());
+
+        config.min_tls_version = Some(TlsVersion::Tls1_3);
+        config.max_tls_version = None;
+        assert!(RequestFactory::new(&config).is_ok());
+
+        config.max_tls_version = Some(TlsVersion::Tls1_2);
+        assert!(matches!(
+            RequestFactory::new(&config),
+            Err(BurlError::InvalidConfig { .. })
+        ));
+
+        // the rustls backend (required by the client identity) does not support TLS 1.1
+        config.client_cert_ref = Some(format!("{}/client_cert.pem", FIXTURES));
+        config.client_key_ref = Some(format!("{}/client_key.pem", FIXTURES));
+        config.min_tls_version = None;
+        config.max_tls_version = Some(TlsVersion::Tls1_1);
+        match RequestFactory::new(&config) {
+            Err(BurlError::InvalidConfig { issue }) => assert!(issue.contains("TLS versions")),
+            _ => panic!("expected an unsupported TLS version"),
+        }
+
+        let config: BenchClientConfig = toml::from_str(
+            r#"
+            url = "https://localhost"
+            method = "Get"
+            minTlsVersion = "1.2"
+            maxTlsVersion = "1.3"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.max_tls_version, Some(TlsVersion::Tls1_3));
+        assert!(RequestFactory::new(&config).is_ok());
+    }
+}
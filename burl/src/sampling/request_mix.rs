@@ -0,0 +1,146 @@
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use reqwest::RequestBuilder;
+
+/// The requests sent by a sampler, one per iteration.
+/// Each request may carry a label by which its samples are grouped in the stats.
+pub struct RequestMix {
+    requests: Vec<(RequestBuilder, Option<String>)>,
+    /// The index of the request for each iteration; cycled if shorter than the number of runs.
+    sequence: Vec<usize>,
+}
+
+impl From<RequestBuilder> for RequestMix {
+    fn from(request: RequestBuilder) -> Self {
+        Self {
+            requests: vec![(request, None)],
+            sequence: vec![0],
+        }
+    }
+}
+
+impl RequestMix {
+    /// Cycles through the labeled requests, one per iteration.
+    pub fn cycled(requests: Vec<(RequestBuilder, Option<String>)>) -> Self {
+        let sequence = (0..requests.len()).collect();
+        Self { requests, sequence }
+    }
+
+    /// Mixes the labeled requests in proportion to their weights over `n_runs` iterations,
+    /// in an order which is shuffled deterministically by the `seed`.
+    pub fn shuffled(
+        requests: Vec<(RequestBuilder, Option<String>)>,
+        weights: &[usize],
+        n_runs: usize,
+        seed: u64,
+    ) -> Self {
+        let pattern: Vec<usize> = weights
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, weight)| std::iter::repeat_n(idx, *weight))
+            .collect();
+
+        let mut sequence: Vec<usize> = pattern.iter().cycle().take(n_runs).copied().collect();
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+        sequence.shuffle(&mut rng);
+
+        Self { requests, sequence }
+    }
+
+    /// Chooses one of the labeled requests for each of the `n_runs` iterations randomly,
+    /// with the given probabilities (seeded by `seed`).
+    /// Returns `None` if the probabilities are invalid, e.g. negative or all zero.
+    pub fn sampled(
+        requests: Vec<(RequestBuilder, Option<String>)>,
+        probabilities: &[f64],
+        n_runs: usize,
+        seed: u64,
+    ) -> Option<Self> {
+        let distribution = WeightedIndex::new(probabilities).ok()?;
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+        let sequence = (0..n_runs).map(|_| distribution.sample(&mut rng)).collect();
+
+        Some(Self { requests, sequence })
+    }
+
+    /// Replaces the label of each request for which `label` returns one.
+    pub fn relabeled(mut self, label: impl Fn(&RequestBuilder) -> Option<String>) -> Self {
+        for (request, request_label) in self.requests.iter_mut() {
+            if let Some(new_label) = label(request) {
+                *request_label = Some(new_label);
+            }
+        }
+        self
+    }
+
+    /// Replaces each request by `n_targets` variants, e.g. one per target host, which are
+    /// cycled per iteration while keeping the order of the original requests.
+    pub fn fanned_out<E>(
+        self,
+        n_targets: usize,
+        target: impl Fn(
+            &RequestBuilder,
+            Option<&String>,
+            usize,
+        ) -> Result<(RequestBuilder, Option<String>), E>,
+    ) -> Result<Self, E> {
+        let n_targets = n_targets.max(1);
+        let mut requests = Vec::with_capacity(self.requests.len() * n_targets);
+        for (request, label) in self.requests.iter() {
+            for target_idx in 0..n_targets {
+                requests.push(target(request, label.as_ref(), target_idx)?);
+            }
+        }
+
+        let sequence = (0..self.sequence.len() * n_targets)
+            .map(|idx| self.sequence[idx % self.sequence.len()] * n_targets + idx % n_targets)
+            .collect();
+        Ok(Self { requests, sequence })
+    }
+
+    /// The request (and its label) for the given iteration.
+    pub fn get(&self, iteration: usize) -> (&RequestBuilder, Option<&String>) {
+        let idx = match self.sequence.len() {
+            0 => 0,
+            n => self.sequence[iteration % n],
+        };
+        let (request, label) = &self.requests[idx];
+        (request, label.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mix(seed: u64) -> RequestMix {
+        let client = reqwest::Client::new();
+        let requests = vec![
+            (client.get("http://localhost"), Some("GET".to_string())),
+            (client.post("http://localhost"), Some("POST".to_string())),
+            (client.put("http://localhost"), Some("PUT".to_string())),
+        ];
+        RequestMix::shuffled(requests, &[3, 2, 1], 60, seed)
+    }
+
+    fn methods(mix: &RequestMix) -> Vec<String> {
+        (0..60)
+            .map(|iteration| mix.get(iteration).1.unwrap().clone())
+            .collect()
+    }
+
+    #[test]
+    fn deterministic_shuffle() {
+        let methods_a = methods(&mix(7));
+        assert_eq!(methods_a, methods(&mix(7)));
+        assert_ne!(methods_a, methods(&mix(8)));
+
+        // the methods are mixed according to their weights, not blocked
+        let count = |method: &str| methods_a.iter().filter(|m| *m == method).count();
+        assert_eq!(count("GET"), 30);
+        assert_eq!(count("POST"), 20);
+        assert_eq!(count("PUT"), 10);
+        assert_ne!(&methods_a[..30], vec!["GET".to_string(); 30].as_slice());
+    }
+}
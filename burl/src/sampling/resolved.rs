@@ -0,0 +1,45 @@
+use super::{signing::hex_digest, RequestFactory};
+use crate::{BenchClientConfig, BurlResult, HeaderRedactor};
+use serde::{Deserialize, Serialize};
+
+/// The first request as it is sent, e.g. to reproduce a run: with the final url and headers,
+/// but the secret headers redacted and the body only by its hash.
+/// NOTE: the headers of the request signing (see `aws_sigv4`) are added at sending and not included.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ResolvedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    /// The hex SHA-256 of the body, if any.
+    pub body_sha256: Option<String>,
+    pub body_bytes: Option<usize>,
+}
+
+impl ResolvedRequest {
+    /// Resolves the request of the first iteration of the first thread.
+    pub fn from_config(config: &BenchClientConfig) -> BurlResult<Self> {
+        let factory = RequestFactory::new(config)?;
+        let requests = factory.assemble_request_mix(config, 0)?;
+        let (request, _) = requests.get(0);
+        let request = request.try_clone().unwrap().build()?;
+
+        let redactor = HeaderRedactor::from_config(config);
+        let headers = request
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let value = redactor.redact(name.as_str(), value.to_str().unwrap_or("<binary>"));
+                (name.to_string(), value.to_string())
+            })
+            .collect();
+        let body = request.body().and_then(|body| body.as_bytes());
+
+        Ok(Self {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            headers,
+            body_sha256: body.map(hex_digest),
+            body_bytes: body.map(<[u8]>::len),
+        })
+    }
+}
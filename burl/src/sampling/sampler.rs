@@ -1,9 +1,26 @@
-use crate::{config::DurationScale, ThreadIdx};
-use log::{error, warn};
-use reqwest::RequestBuilder;
-use serde::Serialize;
-use std::{sync::Arc, time::Duration};
-use tokio::time::Instant; // TODO: check against std::time::Instant
+use super::{
+    build_request, read_events, redirect::count_redirects, sign_request, streamed_file,
+    with_provided_body, AwsSigner, BodyProvider, Clock, RequestMix, ResponseValidator, SystemClock,
+    ThinkTime, ThinkTimeSampler,
+};
+use crate::{
+    config::DurationScale, stats::InterimStats, BenchClientConfig, HeaderRedactor, ThreadIdx,
+};
+use chrono::{DateTime, Utc};
+use log::{error, info, warn};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, RETRY_AFTER};
+use reqwest::{Request, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::{task::JoinHandle, time::Instant}; // TODO: check against std::time::Instant
 
 impl DurationScale {
     pub fn elapsed(&self, duration: &Duration) -> f64 {
@@ -16,13 +33,13 @@ impl DurationScale {
     }
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct SampleResult {
-    #[serde(skip_serializing)]
+    #[serde(skip_serializing, default)]
     pub duration_since_start: Duration,
-    #[serde(skip_serializing)]
+    #[serde(skip_serializing, default)]
     pub duration_request_end: Duration,
-    #[serde(skip_serializing)]
+    #[serde(skip_serializing, default)]
     pub request_duration: Duration,
 
     pub measurement_start: f64,
@@ -30,17 +47,86 @@ pub struct SampleResult {
     pub duration: f64,
 
     pub content_length: Option<u64>,
+    /// The label of the request, e.g. its method in a mix of requests.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub label: Option<String>,
+    /// Set if the sample is excluded from the stats, see `discard_first_secs`.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub discarded: bool,
+    /// The number of redirects followed by the request.
+    #[serde(skip_serializing_if = "is_zero", default)]
+    pub redirects: usize,
+    /// The time of each event since the start of the request, for streams of several
+    /// Server-Sent Events (see `sse_events`).
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub event_times: Vec<f64>,
+    /// The bytes of the streamed request body, see `upload_file_ref`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub upload_bytes: Option<u64>,
+    /// The time from the start of the request until its body was streamed (in the duration scale).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub upload_duration: Option<f64>,
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
 }
 
 impl SampleResult {
     pub fn as_timeseries_point(&self) -> (f64, f64) {
         (self.measurement_start, self.duration)
     }
+
+    /// The uploaded bytes per second of streaming the request body, see `upload_file_ref`.
+    pub fn upload_bytes_per_sec(&self, scale: &DurationScale) -> Option<f64> {
+        let secs = self.upload_duration? / scale.factor(&DurationScale::Secs);
+        self.upload_bytes
+            .filter(|_| secs > 0.0)
+            .map(|n_bytes| n_bytes as f64 / secs)
+    }
+
+    /// Restores the (not serialized) durations from the measurements in the given scale.
+    pub fn restore_durations(&mut self, scale: &DurationScale) {
+        let to_duration =
+            |value: f64| Duration::from_secs_f64(value.max(0.0) / scale.scale() as f64);
+        self.duration_since_start = to_duration(self.measurement_start);
+        self.duration_request_end = to_duration(self.measurement_end);
+        self.request_duration = to_duration(self.duration);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FailureKind {
+    /// Contains the (non-success) status code.
+    Status(StatusCode),
+    /// The response body does not conform to the configured JSON schema.
+    SchemaViolation,
+    /// The expected JSON field of the response body is missing or has another value.
+    FieldMismatch,
+    /// The stream of Server-Sent Events closed before the first event.
+    NoEvents,
+    /// The request did not complete within the `request_timeout_ms`.
+    Timeout,
+    /// The `upload_file_ref` could not be opened, e.g. as it was removed during the run.
+    UploadFile,
+}
+
+impl fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailureKind::Status(status_code) => write!(f, "status code {}", status_code),
+            FailureKind::SchemaViolation => write!(f, "schema violation"),
+            FailureKind::FieldMismatch => write!(f, "JSON field mismatch"),
+            FailureKind::NoEvents => write!(f, "no events on the stream"),
+            FailureKind::Timeout => write!(f, "timeout"),
+            FailureKind::UploadFile => write!(f, "unreadable upload file"),
+        }
+    }
 }
 
 pub enum RequestResult {
-    /// Contains the status code.
-    Failed(usize),
+    /// Contains the kind of failure, the label of the request and its duration (in the duration scale).
+    Failed(FailureKind, Option<String>, f64),
     /// Contains the duration of the request.
     Ok(SampleResult),
 }
@@ -49,40 +135,321 @@ impl RequestResult {
     pub fn as_result(&self) -> Option<&SampleResult> {
         match self {
             RequestResult::Ok(sr) => Some(sr),
-            RequestResult::Failed(_) => None,
+            RequestResult::Failed(..) => None,
+        }
+    }
+
+    /// The label of the request, by which the stats are grouped.
+    pub fn label(&self) -> Option<&String> {
+        match self {
+            RequestResult::Ok(sr) => sr.label.as_ref(),
+            RequestResult::Failed(_, label, _) => label.as_ref(),
         }
     }
 }
 
 pub type StatusCode = usize;
 const SUCCESS: usize = 200;
+const TOO_MANY_REQUESTS: usize = 429;
+/// The max number of retries of a request throttled with a `Retry-After`.
+const MAX_THROTTLED_RETRIES: usize = 10;
+/// The max number of characters of a response body to log for failed requests.
+const BODY_SNIPPET_LEN: usize = 512;
+
+/// The headers with the values of the secret ones redacted.
+fn redacted_headers(headers: &HeaderMap, redactor: &HeaderRedactor) -> String {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = redactor.redact(name.as_str(), value.to_str().unwrap_or("<binary>"));
+            format!("{}: {}", name, value)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The method, url and headers of a request.
+fn request_head(request: &Request, redactor: &HeaderRedactor) -> String {
+    format!(
+        "{} {} with headers [{}]",
+        request.method(),
+        request.url(),
+        redacted_headers(request.headers(), redactor)
+    )
+}
+
+/// The status, url and headers of a response.
+fn response_head(response: &Response, redactor: &HeaderRedactor) -> String {
+    format!(
+        "Response from {} with status {}. Headers: [{}].",
+        response.url(),
+        response.status(),
+        redacted_headers(response.headers(), redactor)
+    )
+}
+
+/// The log message for failed requests with the request, the response head and a snippet of the body.
+fn failure_details(request_head: &str, response_head: &str, body: Option<&[u8]>) -> String {
+    let body = match body {
+        Some(body) => String::from_utf8_lossy(body)
+            .chars()
+            .take(BODY_SNIPPET_LEN)
+            .collect(),
+        None => "<unreadable body>".to_string(),
+    };
+    format!(
+        "Request {} failed. {} Body: {}",
+        request_head, response_head, body
+    )
+}
+
+/// The wait requested by a `429 Too Many Requests` response, in seconds or until an HTTP date.
+fn retry_after(response: &Response) -> Option<Duration> {
+    if response.status().as_u16() as usize != TOO_MANY_REQUESTS {
+        return None;
+    }
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// Runs a part of a request until the `timeout` after its `start` by the clock,
+/// the timeout as the duration of the request if it did not complete by then.
+async fn within_timeout<F: Future>(
+    clock: &dyn Clock,
+    start: Instant,
+    timeout: Option<Duration>,
+    future: F,
+) -> Result<F::Output, Duration> {
+    match timeout {
+        Some(timeout) => clock
+            .timeout_at(start + timeout, future)
+            .await
+            .ok_or(timeout),
+        None => Ok(future.await),
+    }
+}
+
+/// A cap on the total number of requests, shared by the samplers of all threads.
+pub struct RequestBudget {
+    max_requests: usize,
+    n_requests: AtomicUsize,
+}
+
+impl RequestBudget {
+    pub fn new(max_requests: usize) -> Self {
+        Self {
+            max_requests,
+            n_requests: AtomicUsize::new(0),
+        }
+    }
+
+    /// Reserves a request; returns `false` once the cap is reached.
+    pub fn try_acquire(&self) -> bool {
+        self.n_requests
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < self.max_requests).then_some(n + 1)
+            })
+            .is_ok()
+    }
+}
+
+/// Paces the requests of all threads to a target rate: each request reserves the next slot,
+/// spaced evenly by the rate, and waits for it. Slots missed by a slow server are not caught up
+/// by bursts, so that the effective rate falls short of the target instead.
+pub struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(target_rps: f64) -> Self {
+        Self {
+            interval: Duration::from_secs_f64(1.0 / target_rps),
+            next_slot: Mutex::new(None),
+        }
+    }
+
+    /// Reserves the next slot, at the earliest `now`.
+    pub fn reserve(&self, now: Instant) -> Instant {
+        let mut next_slot = self.next_slot.lock().unwrap();
+        let slot = next_slot.map_or(now, |next_slot| next_slot.max(now));
+        *next_slot = Some(slot + self.interval);
+        slot
+    }
+}
+
+/// The interval in which paused samplers (and the watcher of the pause file) check for changes.
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A pause flag shared by the samplers of all threads: while paused, no requests are sent,
+/// but the samples collected so far are kept.
+#[derive(Default)]
+pub struct PauseSwitch {
+    paused: AtomicBool,
+}
+
+impl PauseSwitch {
+    pub fn set_paused(&self, paused: bool) {
+        if self.paused.swap(paused, Ordering::SeqCst) != paused {
+            match paused {
+                true => info!("Pausing the sampling"),
+                false => info!("Resuming the sampling"),
+            }
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Waits until the switch is not paused (anymore).
+    pub async fn resumed(&self) {
+        while self.is_paused() {
+            tokio::time::sleep(PAUSE_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Pauses while the file at `path` exists, until the returned task is aborted.
+    pub fn watch_file(self: &Arc<Self>, path: String) -> JoinHandle<()> {
+        let switch = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let exists = tokio::fs::try_exists(&path).await.unwrap_or(false);
+                switch.set_paused(exists);
+                tokio::time::sleep(PAUSE_POLL_INTERVAL).await;
+            }
+        })
+    }
+}
 
 /// Creates and collects samples:
 /// Iteratively sends the same request, measures timings and responses, and adds results.
 pub struct SampleCollector {
     timer: Arc<Instant>, // TODO: as param? same as for requestBuilder?
+    clock: Arc<dyn Clock>,
+    config: Arc<BenchClientConfig>,
+    response_validator: Option<Arc<ResponseValidator>>,
+    request_signer: Option<Arc<AwsSigner>>,
+    body_provider: Option<Arc<BodyProvider>>,
+    request_budget: Option<Arc<RequestBudget>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    pause_switch: Option<Arc<PauseSwitch>>,
+    interim_stats: Option<Arc<InterimStats>>,
+    deadline: Option<Instant>,
+    think_time: Option<ThinkTimeSampler>,
     pub thread_idx: ThreadIdx,
     pub duration_scale: DurationScale,
     pub n_runs: usize,
     pub results: Vec<RequestResult>,
+    /// The number of retries after a `Retry-After`, see `honor_retry_after`.
+    pub n_throttled: usize,
+    /// The time waited for the retries after a `Retry-After`.
+    pub throttled_duration: Duration,
 }
 
 impl SampleCollector {
-    pub fn new(
-        timer: Arc<Instant>,
-        thread_idx: ThreadIdx,
-        n_runs: usize,
-        duration_scale: DurationScale,
-    ) -> Self {
+    pub fn new(timer: Arc<Instant>, thread_idx: ThreadIdx, config: Arc<BenchClientConfig>) -> Self {
+        let n_runs = config.n_runs();
+        let think_time = config.think_time_distribution.map(|distribution| {
+            // NOTE: even seeded, the jitter would shift the timings of the requests
+            let distribution = match config.deterministic() {
+                true => ThinkTime::Fixed {
+                    ms: distribution.mean_ms(),
+                },
+                false => distribution,
+            };
+            ThinkTimeSampler::new(distribution, config.seed().wrapping_add(thread_idx as u64))
+        });
         Self {
             timer,
-            duration_scale,
+            think_time,
+            clock: Arc::new(SystemClock),
+            duration_scale: config.duration_scale(),
             thread_idx,
             n_runs,
             results: Vec::with_capacity(n_runs),
+            n_throttled: 0,
+            throttled_duration: Duration::ZERO,
+            config,
+            response_validator: None,
+            request_signer: None,
+            body_provider: None,
+            request_budget: None,
+            rate_limiter: None,
+            pause_switch: None,
+            interim_stats: None,
+            deadline: None,
         }
     }
 
+    /// Stops sampling (before `n_runs`) once the shared budget is used up.
+    pub fn with_request_budget(mut self, request_budget: Option<Arc<RequestBudget>>) -> Self {
+        self.request_budget = request_budget;
+        self
+    }
+
+    /// Paces the requests by the shared limiter, see `target_rps`.
+    pub fn with_rate_limiter(mut self, rate_limiter: Option<Arc<RateLimiter>>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Idles between requests while the shared switch is paused.
+    pub fn with_pause_switch(mut self, pause_switch: Option<Arc<PauseSwitch>>) -> Self {
+        self.pause_switch = pause_switch;
+        self
+    }
+
+    /// Adds each result to the shared interim stats too, see `interim_summary_secs`.
+    pub fn with_interim_stats(mut self, interim_stats: Option<Arc<InterimStats>>) -> Self {
+        self.interim_stats = interim_stats;
+        self
+    }
+
+    /// Measures, paces and times out the requests by the given clock instead of the system clock,
+    /// e.g. a `MockClock`.
+    /// NOTE: the `timer` should be a reading of the same clock.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Stops sampling at the deadline, abandoning a request in flight.
+    pub fn with_deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Validates the bodies of successful responses; violations are counted as failures.
+    pub fn with_response_validator(
+        mut self,
+        response_validator: Option<Arc<ResponseValidator>>,
+    ) -> Self {
+        self.response_validator = response_validator;
+        self
+    }
+
+    /// Signs each request right before it is sent, e.g. with AWS SigV4.
+    pub fn with_request_signer(mut self, request_signer: Option<Arc<AwsSigner>>) -> Self {
+        self.request_signer = request_signer;
+        self
+    }
+
+    /// Generates the body of each request, superseding the payload of the requests.
+    pub fn with_body_provider(mut self, body_provider: Option<Arc<BodyProvider>>) -> Self {
+        self.body_provider = body_provider;
+        self
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn add(
         &mut self,
         duration_since_start: Duration,
@@ -90,6 +457,8 @@ impl SampleCollector {
         request_duration: Duration,
         status_code: StatusCode,
         content_length: Option<u64>,
+        label: Option<&String>,
+        redirects: usize,
     ) {
         let result = match status_code {
             SUCCESS => RequestResult::Ok(SampleResult {
@@ -100,36 +469,278 @@ impl SampleCollector {
                 duration_request_end,
                 request_duration,
                 content_length,
+                label: label.cloned(),
+                discarded: false,
+                redirects,
+                event_times: Vec::new(),
+                upload_bytes: None,
+                upload_duration: None,
             }),
             status_code => {
                 warn!("Received response with status code {}", status_code);
-                RequestResult::Failed(status_code)
+                RequestResult::Failed(
+                    FailureKind::Status(status_code),
+                    label.cloned(),
+                    self.duration_scale.elapsed(&request_duration),
+                )
             }
         };
 
+        self.push(result);
+    }
+
+    fn push(&mut self, result: RequestResult) {
+        if let Some(interim_stats) = &self.interim_stats {
+            interim_stats.add(&result);
+        }
         self.results.push(result);
     }
 
-    async fn timed_request(&mut self, request: &RequestBuilder) {
-        let request = request.try_clone().unwrap();
-        let measurement_start = self.timer.elapsed();
-        let start = Instant::now();
+    fn add_failure(&mut self, failure: FailureKind, label: Option<&String>, duration: Duration) {
+        warn!("Request failed due to {}", failure);
+        let duration = self.duration_scale.elapsed(&duration);
+        self.push(RequestResult::Failed(failure, label.cloned(), duration));
+    }
 
-        match request.send().await {
+    /// Reads the events of a stream of Server-Sent Events, timing the first of them.
+    async fn add_event_stream(
+        &mut self,
+        response: Response,
+        start: Instant,
+        measurement_start: Duration,
+        n_events: usize,
+        label: Option<&String>,
+        redirects: usize,
+    ) {
+        let clock = self.clock.clone();
+        let timeout = self.config.request_timeout();
+        let events = read_events(response, n_events, start, clock.as_ref());
+        let stream = match within_timeout(clock.as_ref(), start, timeout, events).await {
+            Ok(Ok(stream)) => stream,
+            Err(timeout) => {
+                self.add_failure(FailureKind::Timeout, label, timeout);
+                return;
+            }
+            Ok(Err(error)) if error.is_timeout() => {
+                let duration = self.clock.elapsed_since(start);
+                self.add_failure(FailureKind::Timeout, label, duration);
+                return;
+            }
+            Ok(Err(error)) => {
+                error!("Error while reading the event stream: {:?}", error);
+                return;
+            }
+        };
+        let Some(first_event) = stream.event_times.first().copied() else {
+            let duration = self.clock.elapsed_since(start);
+            self.add_failure(FailureKind::NoEvents, label, duration);
+            return;
+        };
+
+        self.add(
+            measurement_start,
+            measurement_start + first_event,
+            first_event,
+            SUCCESS,
+            Some(stream.n_bytes),
+            label,
+            redirects,
+        );
+        if n_events > 1 {
+            if let Some(RequestResult::Ok(sample)) = self.results.last_mut() {
+                sample.event_times = stream
+                    .event_times
+                    .iter()
+                    .map(|time| self.duration_scale.elapsed(time))
+                    .collect();
+            }
+        }
+    }
+
+    async fn timed_request(&mut self, request_builder: &RequestBuilder, label: Option<&String>) {
+        let sse_events = self.config.sse_events();
+        let timeout = self.config.request_timeout();
+        let mut n_retries = 0;
+        let redactor = HeaderRedactor::from_config(&self.config);
+        let (response, redirects, measurement_start, start, head, uploaded, failure_request) = loop {
+            // NOTE: signed after the upload file is attached, see `sign_request`
+            let (client, mut request) = match build_request(request_builder, None) {
+                Ok(request) => request,
+                Err(error) => {
+                    error!("Error while building request: {:?}", error);
+                    return;
+                }
+            };
+            if sse_events.is_some() {
+                request
+                    .headers_mut()
+                    .entry(ACCEPT)
+                    .or_insert(HeaderValue::from_static("text/event-stream"));
+            }
+            // NOTE: only the methods with a body stream the file, see `upload_file_ref`
+            let streams_body = [
+                reqwest::Method::POST,
+                reqwest::Method::PUT,
+                reqwest::Method::PATCH,
+            ]
+            .contains(request.method());
+            let uploaded = match (&self.config.upload_file_ref, streams_body) {
+                (Some(upload_file_ref), true) => {
+                    match streamed_file(upload_file_ref, self.clock.clone()).await {
+                        Ok((body, uploaded)) => {
+                            *request.body_mut() = Some(body);
+                            Some(uploaded)
+                        }
+                        Err(error) => {
+                            error!("Error while opening the upload file: {:?}", error);
+                            self.add_failure(FailureKind::UploadFile, label, Duration::ZERO);
+                            return;
+                        }
+                    }
+                }
+                _ => None,
+            };
+            sign_request(&mut request, self.request_signer.as_deref());
+            // NOTE: the request is consumed by its execution
+            let failure_request = self
+                .config
+                .log_failures_verbose()
+                .then(|| request_head(&request, &redactor));
+            let head = request.method() == reqwest::Method::HEAD;
+            let measurement_start = self.clock.elapsed_since(*self.timer);
+            let start = self.clock.now();
+
+            let execution = count_redirects(client.execute(request));
+            let (response, redirects) =
+                match within_timeout(self.clock.as_ref(), start, timeout, execution).await {
+                    Ok(execution) => execution,
+                    Err(timeout) => {
+                        self.add_failure(FailureKind::Timeout, label, timeout);
+                        return;
+                    }
+                };
+            // NOTE: the throttled attempts and the waits are not part of the samples
+            let wait = match &response {
+                Ok(response) if self.config.honor_retry_after() => retry_after(response),
+                _ => None,
+            };
+            match wait {
+                Some(wait) if n_retries < MAX_THROTTLED_RETRIES => {
+                    info!("Throttled by the server, retrying in {:?}", wait);
+                    n_retries += 1;
+                    self.n_throttled += 1;
+                    self.throttled_duration += wait;
+                    self.clock.sleep(wait).await;
+                }
+                _ => {
+                    break (
+                        response,
+                        redirects,
+                        measurement_start,
+                        start,
+                        head,
+                        uploaded,
+                        failure_request,
+                    )
+                }
+            }
+        };
+        match response {
             Ok(response) => {
                 // TODO: better way of measuring the time?
-                let duration = start.elapsed();
-                let measurement_end = self.timer.elapsed();
+                let mut duration = self.clock.elapsed_since(start);
+                let mut measurement_end = self.clock.elapsed_since(*self.timer);
                 let status_code = response.status().as_u16() as usize;
-                let content_length = response.content_length();
-                drop(response);
+                if let (SUCCESS, Some(n_events)) = (status_code, sse_events) {
+                    self.add_event_stream(
+                        response,
+                        start,
+                        measurement_start,
+                        n_events,
+                        label,
+                        redirects,
+                    )
+                    .await;
+                    return;
+                }
+                // a HEAD response has no body, its `Content-Length` is the one of a GET
+                let mut content_length = (!head).then(|| response.content_length()).flatten();
+
+                let drain_body = self.config.drain_response_body();
+                let validator = match status_code {
+                    SUCCESS => self.response_validator.clone(),
+                    _ => None,
+                };
+                let failure_head = failure_request
+                    .filter(|_| status_code != SUCCESS)
+                    .map(|request| (request, response_head(&response, &redactor)));
+
+                if drain_body || validator.is_some() || failure_head.is_some() {
+                    let body = response.bytes();
+                    let body = match within_timeout(self.clock.as_ref(), start, timeout, body).await
+                    {
+                        Ok(Ok(body)) => Some(body),
+                        Err(timeout) => {
+                            self.add_failure(FailureKind::Timeout, label, timeout);
+                            return;
+                        }
+                        Ok(Err(error)) if error.is_timeout() => {
+                            let duration = self.clock.elapsed_since(start);
+                            self.add_failure(FailureKind::Timeout, label, duration);
+                            return;
+                        }
+                        Ok(Err(error)) => {
+                            error!("Error while reading the response body: {:?}", error);
+                            None
+                        }
+                    };
+
+                    // NOTE: when draining, the duration covers the transfer of the full body
+                    if drain_body {
+                        duration = self.clock.elapsed_since(start);
+                        measurement_end = self.clock.elapsed_since(*self.timer);
+                        if !head {
+                            content_length =
+                                body.as_ref().map(|b| b.len() as u64).or(content_length);
+                        }
+                    }
+
+                    if let Some((request, response)) = failure_head {
+                        error!("{}", failure_details(&request, &response, body.as_deref()));
+                    }
+
+                    if let Some(validator) = validator {
+                        // a missing body is no valid JSON either
+                        if let Err(failure) = validator.validate(body.as_deref().unwrap_or(b"")) {
+                            self.add_failure(failure, label, duration);
+                            return;
+                        }
+                    }
+                } else {
+                    drop(response);
+                }
+
                 self.add(
                     measurement_start,
                     measurement_end,
                     duration,
                     status_code,
                     content_length,
+                    label,
+                    redirects,
                 );
+                if let (Some(uploaded), Some(RequestResult::Ok(sample))) =
+                    (uploaded, self.results.last_mut())
+                {
+                    sample.upload_bytes = Some(uploaded.n_bytes());
+                    sample.upload_duration = uploaded
+                        .duration_since(start)
+                        .map(|duration| self.duration_scale.elapsed(&duration));
+                }
+            }
+            Err(error) if error.is_timeout() => {
+                let duration = self.clock.elapsed_since(start);
+                self.add_failure(FailureKind::Timeout, label, duration);
             }
             Err(error) => {
                 error!("Error while sending request: {:?}", error);
@@ -137,9 +748,689 @@ impl SampleCollector {
         }
     }
 
-    pub async fn collect_samples(&mut self, request_builder: RequestBuilder) {
-        for _ in 0..self.n_runs {
-            self.timed_request(&request_builder).await;
+    pub async fn collect_samples(&mut self, requests: impl Into<RequestMix>) {
+        let requests = requests.into();
+        let clock = self.clock.clone();
+        for iteration in 0..self.n_runs {
+            if let (Some(think_time), true) = (&mut self.think_time, iteration > 0) {
+                let pause = clock.sleep(think_time.sample());
+                let paused = match self.deadline {
+                    Some(deadline) => clock.timeout_at(deadline, pause).await.is_some(),
+                    None => {
+                        pause.await;
+                        true
+                    }
+                };
+                if !paused {
+                    info!(
+                        "Thread {} stops after {} requests: the max run time is reached while thinking",
+                        self.thread_idx, iteration
+                    );
+                    break;
+                }
+            }
+            if let Some(switch) = &self.pause_switch {
+                let resumed = switch.resumed();
+                let resumed = match self.deadline {
+                    Some(deadline) => clock.timeout_at(deadline, resumed).await.is_some(),
+                    None => {
+                        resumed.await;
+                        true
+                    }
+                };
+                if !resumed {
+                    info!(
+                        "Thread {} stops after {} requests: the max run time is reached while paused",
+                        self.thread_idx, iteration
+                    );
+                    break;
+                }
+            }
+            if let Some(budget) = &self.request_budget {
+                if !budget.try_acquire() {
+                    info!(
+                        "Thread {} stops after {} requests: the max total of requests is reached",
+                        self.thread_idx, iteration
+                    );
+                    break;
+                }
+            }
+            if let Some(limiter) = &self.rate_limiter {
+                let slot = clock.sleep_until(limiter.reserve(clock.now()));
+                let paced = match self.deadline {
+                    Some(deadline) => clock.timeout_at(deadline, slot).await.is_some(),
+                    None => {
+                        slot.await;
+                        true
+                    }
+                };
+                if !paced {
+                    info!(
+                        "Thread {} stops after {} requests: the max run time is reached while pacing",
+                        self.thread_idx, iteration
+                    );
+                    break;
+                }
+            }
+            let (request, label) = requests.get(iteration);
+            let provided_request = match &self.body_provider {
+                Some(provider) => match with_provided_body(
+                    request,
+                    provider.as_ref(),
+                    iteration,
+                    self.thread_idx,
+                    self.config.compress_request_body(),
+                ) {
+                    Ok(request) => Some(request),
+                    Err(error) => {
+                        error!("Error while setting the provided body: {:?}", error);
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            let request = provided_request.as_ref().unwrap_or(request);
+            match self.deadline {
+                Some(deadline) => {
+                    let request = self.timed_request(request, label);
+                    if clock.timeout_at(deadline, request).await.is_none() {
+                        info!(
+                            "Thread {} stops after {} requests: the max run time is reached",
+                            self.thread_idx, iteration
+                        );
+                        break;
+                    }
+                }
+                None => self.timed_request(request, label).await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampling::{MockClock, RequestFactory};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn collector(url: String, n_runs: usize) -> SampleCollector {
+        let mut config = BenchClientConfig::new(url);
+        config.n_runs = Some(n_runs);
+        SampleCollector::new(Arc::new(Instant::now()), 0, Arc::new(config))
+    }
+
+    #[tokio::test]
+    async fn schema_violations() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{ "price": 10.2 }"#))
+            .mount(&server)
+            .await;
+
+        let schema = json!({ "type": "object", "required": ["name"] });
+        let validator = ResponseValidator::new(&schema).unwrap();
+
+        let mut sampler =
+            collector(server.uri(), 5).with_response_validator(Some(Arc::new(validator)));
+        sampler
+            .collect_samples(reqwest::Client::new().get(server.uri()))
+            .await;
+
+        let n_violations = sampler
+            .results
+            .iter()
+            .filter(|r| matches!(r, RequestResult::Failed(FailureKind::SchemaViolation, ..)))
+            .count();
+        assert_eq!(n_violations, 5);
+    }
+
+    #[tokio::test]
+    async fn field_mismatches() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{ "status": "ok" }"#))
+            .up_to_n_times(3)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{ "status": "busy" }"#))
+            .mount(&server)
+            .await;
+
+        let mut config = BenchClientConfig::new(server.uri());
+        config.n_runs = Some(5);
+        config.expect_json_field = Some(("$.status".to_string(), json!("ok")));
+        let validator = ResponseValidator::from_config(&config).unwrap();
+        let mut sampler = SampleCollector::new(Arc::new(Instant::now()), 0, Arc::new(config))
+            .with_response_validator(validator.map(Arc::new));
+        sampler
+            .collect_samples(reqwest::Client::new().get(server.uri()))
+            .await;
+
+        let n_mismatches = sampler
+            .results
+            .iter()
+            .filter(|r| matches!(r, RequestResult::Failed(FailureKind::FieldMismatch, ..)))
+            .count();
+        assert_eq!(n_mismatches, 2);
+        assert_eq!(
+            sampler
+                .results
+                .iter()
+                .filter(|r| r.as_result().is_some())
+                .count(),
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn honor_retry_after() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "1"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let mut config = BenchClientConfig::new(server.uri());
+        config.n_runs = Some(2);
+        config.honor_retry_after = Some(true);
+        let mut sampler = SampleCollector::new(Arc::new(Instant::now()), 0, Arc::new(config));
+        let start = Instant::now();
+        sampler
+            .collect_samples(reqwest::Client::new().get(server.uri()))
+            .await;
+
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+        assert_eq!(sampler.n_throttled, 1);
+        assert_eq!(sampler.throttled_duration, Duration::from_secs(1));
+        assert_eq!(sampler.results.len(), 2);
+        for result in sampler.results.iter() {
+            // the wait is not part of the samples
+            assert!(result.as_result().unwrap().duration < 1_000_000.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn sse_first_event() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(": connected\n\ndata: a\n\ndata: b\n\ndata: c\n\n")
+                    .set_delay(Duration::from_millis(50)),
+            )
+            .mount(&server)
+            .await;
+
+        let mut config = BenchClientConfig::new(server.uri());
+        config.n_runs = Some(3);
+        config.sse_events = Some(2);
+        let mut sampler = SampleCollector::new(Arc::new(Instant::now()), 0, Arc::new(config));
+        sampler
+            .collect_samples(reqwest::Client::new().get(server.uri()))
+            .await;
+
+        assert_eq!(sampler.results.len(), 3);
+        for result in sampler.results.iter() {
+            let sample = result.as_result().unwrap();
+            // in micros
+            assert!(sample.duration >= 50_000.0);
+            assert_eq!(sample.event_times.len(), 2);
+            assert_eq!(sample.event_times[0], sample.duration);
+        }
+        let requests = server.received_requests().await.unwrap();
+        let accept = requests[0].headers.get(&"accept".into()).unwrap();
+        assert_eq!(accept, "text/event-stream");
+    }
+
+    /// A minimal HTTP/1.1 server on which each request takes the `delay` by the `clock`:
+    /// it advances the clock on receiving a request, and responds only if `respond`.
+    async fn clocked_server(clock: Arc<MockClock>, delay: Duration, respond: bool) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let clock = clock.clone();
+                tokio::spawn(async move {
+                    let mut buffer = Vec::new();
+                    let mut chunk = [0u8; 1024];
+                    loop {
+                        match stream.read(&mut chunk).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+                        }
+                        while let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                            buffer.drain(..end + 4);
+                            clock.advance(delay);
+                            let response = b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n";
+                            if respond && stream.write_all(response).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        url
+    }
+
+    #[tokio::test]
+    async fn mock_clock() {
+        let clock = Arc::new(MockClock::default());
+        let url = clocked_server(clock.clone(), Duration::from_micros(1_500), true).await;
+
+        let mut config = BenchClientConfig::new(url.clone());
+        config.n_runs = Some(3);
+        clock.advance(Duration::from_secs(1));
+        let timer = Arc::new(clock.now());
+        let mut sampler =
+            SampleCollector::new(timer, 0, Arc::new(config)).with_clock(clock.clone());
+        sampler
+            .collect_samples(reqwest::Client::new().get(url))
+            .await;
+
+        let samples: Vec<&SampleResult> = sampler
+            .results
+            .iter()
+            .map(|result| result.as_result().unwrap())
+            .collect();
+        assert_eq!(samples.len(), 3);
+        for (idx, sample) in samples.iter().enumerate() {
+            // only the advances of the clock count
+            assert_eq!(sample.request_duration, Duration::from_micros(1_500));
+            assert_eq!(sample.duration, 1_500.0);
+            assert_eq!(sample.measurement_start, idx as f64 * 1_500.0);
+            assert_eq!(sample.measurement_end, sample.measurement_start + 1_500.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn mock_clock_pacing() {
+        let clock = Arc::new(MockClock::default());
+        let url = clocked_server(clock.clone(), Duration::from_millis(4), true).await;
+
+        let mut config = BenchClientConfig::new(url.clone());
+        config.n_runs = Some(3);
+        let timer = Arc::new(clock.now());
+        let mut sampler = SampleCollector::new(timer.clone(), 0, Arc::new(config))
+            .with_clock(clock.clone())
+            .with_rate_limiter(Some(Arc::new(RateLimiter::new(100.0))));
+        let request = reqwest::Client::new().get(url);
+        let task = tokio::spawn(async move {
+            sampler.collect_samples(request).await;
+            sampler
+        });
+
+        // advance to the next slot whenever the sampler waits for it
+        for slot in [10, 20] {
+            let until_slot = Duration::from_millis(slot - 6);
+            while !clock.has_sleepers() || clock.elapsed_since(*timer) != until_slot {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+            clock.advance(Duration::from_millis(6));
+        }
+        let sampler = task.await.unwrap();
+
+        let samples: Vec<&SampleResult> = sampler
+            .results
+            .iter()
+            .map(|result| result.as_result().unwrap())
+            .collect();
+        assert_eq!(samples.len(), 3);
+        for (idx, sample) in samples.iter().enumerate() {
+            assert_eq!(sample.measurement_start, idx as f64 * 10_000.0);
+            assert_eq!(sample.duration, 4_000.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn mock_clock_timeout() {
+        let clock = Arc::new(MockClock::default());
+        let mut config = BenchClientConfig::new("http://localhost".to_string());
+        config.n_runs = Some(2);
+        config.request_timeout_ms = Some(50);
+        let config = Arc::new(config);
+
+        // completing just before the timeout
+        let url = clocked_server(clock.clone(), Duration::from_millis(49), true).await;
+        let mut sampler = SampleCollector::new(Arc::new(clock.now()), 0, config.clone())
+            .with_clock(clock.clone());
+        sampler
+            .collect_samples(reqwest::Client::new().get(url))
+            .await;
+        assert_eq!(sampler.results.len(), 2);
+        for result in &sampler.results {
+            assert_eq!(result.as_result().unwrap().duration, 49_000.0);
+        }
+
+        // never responding, but timed out by the clock since the client has no timeout
+        let url = clocked_server(clock.clone(), Duration::from_millis(80), false).await;
+        let mut sampler =
+            SampleCollector::new(Arc::new(clock.now()), 0, config).with_clock(clock.clone());
+        sampler
+            .collect_samples(reqwest::Client::new().get(url))
+            .await;
+        assert_eq!(sampler.results.len(), 2);
+        for result in &sampler.results {
+            assert!(matches!(
+                result,
+                RequestResult::Failed(FailureKind::Timeout, None, duration) if *duration == 50_000.0
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn pause_switch() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let switch = Arc::new(PauseSwitch::default());
+        switch.set_paused(true);
+        let mut sampler = collector(server.uri(), 5).with_pause_switch(Some(switch.clone()));
+        let request = reqwest::Client::new().get(server.uri());
+        let task = tokio::spawn(async move {
+            sampler.collect_samples(request).await;
+            sampler
+        });
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(server.received_requests().await.unwrap().is_empty());
+        assert!(!task.is_finished());
+
+        switch.set_paused(false);
+        let sampler = task.await.unwrap();
+        assert_eq!(sampler.results.len(), 5);
+        assert_eq!(server.received_requests().await.unwrap().len(), 5);
+    }
+
+    /// A minimal HTTP/1.1 server with keep-alive support, counting the accepted connections.
+    async fn keep_alive_server(body_len: usize) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let n_connections = Arc::new(AtomicUsize::new(0));
+
+        let counter = n_connections.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut buffer = Vec::new();
+                    let mut chunk = [0u8; 1024];
+                    loop {
+                        match stream.read(&mut chunk).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+                        }
+                        while let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                            buffer.drain(..end + 4);
+                            let head =
+                                format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body_len);
+                            let mut response = head.into_bytes();
+                            response.extend(vec![b'x'; body_len]);
+                            if stream.write_all(&response).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        (url, n_connections)
+    }
+
+    #[tokio::test]
+    async fn drain_response_body() {
+        let body_len = 256 * 1024;
+
+        let (url, n_connections) = keep_alive_server(body_len).await;
+        let mut sampler = collector(url.clone(), 10);
+        sampler
+            .collect_samples(reqwest::Client::new().get(&url))
+            .await;
+        let n_drained_connections = n_connections.load(Ordering::SeqCst);
+
+        // the connection is reused and the full body is counted
+        assert_eq!(n_drained_connections, 1);
+        for result in sampler.results.iter() {
+            assert_eq!(
+                result.as_result().unwrap().content_length,
+                Some(body_len as u64)
+            );
+        }
+
+        let (url, n_connections) = keep_alive_server(body_len).await;
+        let mut config = BenchClientConfig::new(url.clone());
+        config.n_runs = Some(10);
+        config.drain_response_body = Some(false);
+        let mut sampler = SampleCollector::new(Arc::new(Instant::now()), 0, Arc::new(config));
+        sampler
+            .collect_samples(reqwest::Client::new().get(&url))
+            .await;
+
+        // undrained connections cannot be returned to the pool
+        assert!(n_connections.load(Ordering::SeqCst) > n_drained_connections);
+    }
+
+    #[tokio::test]
+    async fn streamed_upload() {
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::any())
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let file_size = 300_000;
+        let upload_file =
+            std::env::temp_dir().join(format!("burl-upload-{}.bin", std::process::id()));
+        std::fs::write(&upload_file, vec![7u8; file_size]).unwrap();
+
+        let mut config = BenchClientConfig::new(server.uri());
+        config.method = crate::sampling::Method::Put;
+        config.n_runs = Some(3);
+        config.upload_file_ref = Some(upload_file.to_string_lossy().to_string());
+        let factory = RequestFactory::new(&config).unwrap();
+        let request = factory.assemble_request(&config, 0).unwrap();
+        let mut sampler =
+            SampleCollector::new(Arc::new(Instant::now()), 0, Arc::new(config.clone()));
+        sampler.collect_samples(request).await;
+
+        // a GET request has no body to stream the file as
+        let mut get_config = config.clone();
+        get_config.method = crate::sampling::Method::Get;
+        let request = factory.assemble_request(&get_config, 0).unwrap();
+        let mut get_sampler =
+            SampleCollector::new(Arc::new(Instant::now()), 0, Arc::new(get_config));
+        get_sampler.collect_samples(request).await;
+        std::fs::remove_file(&upload_file).unwrap();
+
+        // the file is checked up front, but may vanish during the run
+        assert!(RequestFactory::new(&config).is_err());
+        let request = factory.assemble_request(&config, 0).unwrap();
+        let mut vanished_sampler =
+            SampleCollector::new(Arc::new(Instant::now()), 0, Arc::new(config.clone()));
+        vanished_sampler.collect_samples(request).await;
+        assert_eq!(vanished_sampler.results.len(), 3);
+        assert!(vanished_sampler
+            .results
+            .iter()
+            .all(|r| matches!(r, RequestResult::Failed(FailureKind::UploadFile, ..))));
+
+        assert_eq!(sampler.results.len(), 3);
+        for result in sampler.results.iter() {
+            let sample = result.as_result().unwrap();
+            assert_eq!(sample.upload_bytes, Some(file_size as u64));
+            let upload_duration = sample.upload_duration.unwrap();
+            assert!(upload_duration > 0.0 && upload_duration <= sample.duration);
+            assert!(sample.upload_bytes_per_sec(&DurationScale::Micro).unwrap() > 0.0);
+        }
+        assert!(get_sampler.results.iter().all(|result| result
+            .as_result()
+            .unwrap()
+            .upload_bytes
+            .is_none()));
+        for request in server.received_requests().await.unwrap() {
+            let expected_size = match request.method {
+                wiremock::http::Method::Put => file_size,
+                _ => 0,
+            };
+            assert_eq!(request.body.len(), expected_size);
+        }
+
+        let stats = crate::stats::StatsProcessor::new(DurationScale::Micro, vec![sampler])
+            .stats_summary()
+            .unwrap();
+        assert_eq!(stats.total_upload_bytes, 3 * file_size as u64);
+        assert!(stats.upload_bytes_per_sec().is_some());
+
+        // e.g. the payload would be replaced by the file, keeping its JSON content type
+        config.json_payload = Some(r#"{ "id": 0 }"#.to_string());
+        assert!(matches!(
+            RequestFactory::new(&config),
+            Err(crate::BurlError::InvalidConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn deterministic_think_time() {
+        let config: BenchClientConfig = serde_json::from_value(json!({
+            "url": "http://localhost",
+            "method": "Get",
+            "deterministic": true,
+            "think_time_distribution": { "kind": "uniform", "min_ms": 10.0, "max_ms": 30.0 },
+        }))
+        .unwrap();
+        let mut sampler = SampleCollector::new(Arc::new(Instant::now()), 0, Arc::new(config));
+        let think_time = sampler.think_time.as_mut().unwrap();
+        for _ in 0..3 {
+            assert_eq!(think_time.sample(), Duration::from_millis(20));
         }
     }
+
+    #[test]
+    fn rate_limiter() {
+        let limiter = RateLimiter::new(100.0);
+        let first = Instant::now();
+        assert_eq!(limiter.reserve(first), first);
+        assert_eq!(limiter.reserve(first), first + Duration::from_millis(10));
+        assert_eq!(
+            limiter.reserve(first + Duration::from_millis(5)),
+            first + Duration::from_millis(20)
+        );
+
+        // missed slots are not caught up
+        let late = first + Duration::from_millis(50);
+        assert_eq!(limiter.reserve(late), late);
+        assert_eq!(limiter.reserve(late), late + Duration::from_millis(10));
+    }
+
+    #[tokio::test]
+    async fn request_timeout() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+            .mount(&server)
+            .await;
+
+        let mut config = BenchClientConfig::new(server.uri());
+        config.n_runs = Some(3);
+        config.request_timeout_ms = Some(50);
+        let factory = RequestFactory::new(&config).unwrap();
+        let request = factory.assemble_request(&config, 0).unwrap();
+        let mut sampler = SampleCollector::new(Arc::new(Instant::now()), 0, Arc::new(config));
+
+        let start = Instant::now();
+        sampler.collect_samples(request).await;
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        // the timed out requests are failures rather than dropped
+        assert_eq!(sampler.results.len(), 3);
+        assert!(sampler
+            .results
+            .iter()
+            .all(|r| matches!(r, RequestResult::Failed(FailureKind::Timeout, ..))));
+    }
+
+    #[tokio::test]
+    async fn head_requests() {
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("a body of a GET"))
+            .mount(&server)
+            .await;
+
+        let mut sampler = collector(server.uri(), 5);
+        sampler
+            .collect_samples(reqwest::Client::new().head(server.uri()))
+            .await;
+
+        // no bytes are counted for the missing body
+        assert_eq!(sampler.results.len(), 5);
+        for result in sampler.results.iter() {
+            assert_eq!(result.as_result().unwrap().content_length, None);
+        }
+    }
+
+    #[tokio::test]
+    async fn failure_details() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(500)
+                    .insert_header("x-trace-id", "abc123")
+                    .insert_header("authorization", "Bearer s3cr3t")
+                    .insert_header("x-tenant-token", "t0k3n")
+                    .set_body_string("internal database timeout"),
+            )
+            .mount(&server)
+            .await;
+
+        let redactor = HeaderRedactor::new(&["X-Tenant-Token".to_string()]);
+        let request = reqwest::Client::new()
+            .post(format!("{}/orders", server.uri()))
+            .header("x-request-id", "req-7")
+            .header("authorization", "Bearer r3qu3st")
+            .build()
+            .unwrap();
+        let request = request_head(&request, &redactor);
+        let response = reqwest::Client::new()
+            .get(server.uri())
+            .send()
+            .await
+            .unwrap();
+        let head = response_head(&response, &redactor);
+        let body = response.bytes().await.ok();
+        let details = super::failure_details(&request, &head, body.as_deref());
+
+        // the method, url and headers of the failed attempt
+        assert!(details.starts_with(&format!(
+            "Request POST {}/orders with headers [",
+            server.uri()
+        )));
+        assert!(details.contains("x-request-id: req-7"));
+        assert!(details.contains("500"));
+        assert!(details.contains("x-trace-id: abc123"));
+        assert!(details.contains("internal database timeout"));
+        // the secret headers are never logged
+        assert!(details.contains("authorization: <redacted>"));
+        assert!(!details.contains("s3cr3t") && !details.contains("t0k3n"));
+        assert!(!details.contains("r3qu3st"));
+    }
 }
@@ -0,0 +1,299 @@
+use crate::{config::AwsSigV4Config, BenchClientConfig, BurlError, BurlResult};
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderName, HeaderValue};
+use reqwest::{Client, Request, RequestBuilder};
+use ring::{digest, hmac};
+use std::env;
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// Signs requests with AWS Signature Version 4, e.g. for API Gateway or S3 endpoints.
+/// As the signature depends on the time, each request is signed right before it is sent.
+pub struct AwsSigner {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<HeaderValue>,
+    region: String,
+    service: String,
+}
+
+/// The configured value, falling back to the first of the env variables which is set.
+fn value_or_env(value: &Option<String>, vars: &[&str]) -> Option<String> {
+    value
+        .clone()
+        .or_else(|| vars.iter().find_map(|var| env::var(var).ok()))
+}
+
+impl AwsSigner {
+    pub fn new(config: &AwsSigV4Config) -> BurlResult<Self> {
+        let missing = |name: &str| BurlError::InvalidConfig {
+            issue: format!("Missing the {} for signing the requests (AWS SigV4)", name),
+        };
+
+        let access_key_id = value_or_env(&config.access_key_id, &["AWS_ACCESS_KEY_ID"])
+            .ok_or_else(|| missing("access key id"))?;
+        let secret_access_key = value_or_env(&config.secret_access_key, &["AWS_SECRET_ACCESS_KEY"])
+            .ok_or_else(|| missing("secret access key"))?;
+        let region = value_or_env(&config.region, &["AWS_REGION", "AWS_DEFAULT_REGION"])
+            .ok_or_else(|| missing("region"))?;
+        let session_token = value_or_env(&config.session_token, &["AWS_SESSION_TOKEN"])
+            .map(|token| HeaderValue::from_str(&token))
+            .transpose()
+            .map_err(|_| BurlError::InvalidConfig {
+                issue: "Invalid AWS session token".to_string(),
+            })?;
+
+        Ok(Self {
+            access_key_id,
+            secret_access_key,
+            session_token,
+            region,
+            service: config.service.clone(),
+        })
+    }
+
+    /// The signer of the `aws_sigv4` config, if configured.
+    pub fn from_config(config: &BenchClientConfig) -> BurlResult<Option<Self>> {
+        config.aws_sigv4.as_ref().map(Self::new).transpose()
+    }
+
+    /// Adds the `Authorization` header (and the `X-Amz-*` headers it covers) for the given time.
+    /// All headers of the request at this point are signed.
+    pub fn sign(&self, request: &mut Request, time: DateTime<Utc>) {
+        let amz_date = time.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = time.format("%Y%m%d").to_string();
+
+        let payload_hash = match request.body() {
+            Some(body) => match body.as_bytes() {
+                Some(bytes) => hex_digest(bytes),
+                None => UNSIGNED_PAYLOAD.to_string(),
+            },
+            None => hex_digest(b""),
+        };
+
+        let headers = request.headers_mut();
+        headers.insert(
+            HeaderName::from_static("x-amz-date"),
+            HeaderValue::from_str(&amz_date).unwrap(),
+        );
+        if self.service == "s3" {
+            headers.insert(
+                HeaderName::from_static("x-amz-content-sha256"),
+                HeaderValue::from_str(&payload_hash).unwrap(),
+            );
+        }
+        if let Some(token) = &self.session_token {
+            headers.insert(
+                HeaderName::from_static("x-amz-security-token"),
+                token.clone(),
+            );
+        }
+
+        let (canonical_headers, signed_headers) = canonical_headers(request);
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            request.method().as_str(),
+            self.canonical_uri(request),
+            canonical_query(request),
+            canonical_headers,
+            signed_headers,
+            payload_hash
+        );
+
+        let scope = format!("{}/{}/{}/aws4_request", date, self.region, self.service);
+        let string_to_sign = format!(
+            "{}\n{}\n{}\n{}",
+            ALGORITHM,
+            amz_date,
+            scope,
+            hex_digest(canonical_request.as_bytes())
+        );
+
+        let signature =
+            hex(hmac_sha256(self.signing_key(&date).as_ref(), string_to_sign.as_bytes()).as_ref());
+        let authorization = format!(
+            "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+            ALGORITHM, self.access_key_id, scope, signed_headers, signature
+        );
+
+        // the header value consists of printable ASCII only, unless the access key id is invalid
+        if let Ok(authorization) = HeaderValue::from_str(&authorization) {
+            request
+                .headers_mut()
+                .insert(reqwest::header::AUTHORIZATION, authorization);
+        }
+    }
+
+    fn signing_key(&self, date: &str) -> hmac::Tag {
+        let secret = format!("AWS4{}", self.secret_access_key);
+        let date_key = hmac_sha256(secret.as_bytes(), date.as_bytes());
+        let region_key = hmac_sha256(date_key.as_ref(), self.region.as_bytes());
+        let service_key = hmac_sha256(region_key.as_ref(), self.service.as_bytes());
+        hmac_sha256(service_key.as_ref(), b"aws4_request")
+    }
+
+    /// The (already encoded) path, which is encoded once more for all services but S3.
+    fn canonical_uri(&self, request: &Request) -> String {
+        let path = request.url().path();
+        let path = if path.is_empty() { "/" } else { path };
+        match self.service.as_str() {
+            "s3" => path.to_string(),
+            _ => uri_encode(path, false),
+        }
+    }
+}
+
+/// Builds a clone of the request, signed for the current time if a signer is given.
+pub(crate) fn build_request(
+    request: &RequestBuilder,
+    signer: Option<&AwsSigner>,
+) -> reqwest::Result<(Client, Request)> {
+    let (client, request) = request.try_clone().unwrap().build_split();
+    let mut request = request?;
+    sign_request(&mut request, signer);
+    Ok((client, request))
+}
+
+/// Signs the request for the current time if a signer is given, e.g. after attaching a streamed
+/// body to it, whose payload is signed as `UNSIGNED-PAYLOAD`.
+pub(crate) fn sign_request(request: &mut Request, signer: Option<&AwsSigner>) {
+    if let Some(signer) = signer {
+        signer.sign(request, Utc::now());
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> hmac::Tag {
+    hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), data)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub(crate) fn hex_digest(data: &[u8]) -> String {
+    hex(digest::digest(&digest::SHA256, data).as_ref())
+}
+
+/// Percent-encodes all but the unreserved characters (and the slash, unless `encode_slash`).
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            b'/' if !encode_slash => "/".to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// The query parameters, encoded and sorted by name and value.
+fn canonical_query(request: &Request) -> String {
+    let mut params: Vec<(String, String)> = request
+        .url()
+        .query_pairs()
+        .map(|(name, value)| (uri_encode(&name, true), uri_encode(&value, true)))
+        .collect();
+    params.sort();
+
+    params
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// The canonical headers (including the host) and the list of their names.
+fn canonical_headers(request: &Request) -> (String, String) {
+    let url = request.url();
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+
+    let mut headers: Vec<(String, String)> = vec![("host".to_string(), host)];
+    for name in request.headers().keys() {
+        let values: Vec<String> = request
+            .headers()
+            .get_all(name)
+            .iter()
+            .map(|value| {
+                String::from_utf8_lossy(value.as_bytes())
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+        headers.push((name.as_str().to_string(), values.join(",")));
+    }
+    headers.sort();
+
+    let canonical = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value))
+        .collect();
+    let signed = headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+    (canonical, signed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn sigv4_get_vanilla() {
+        // the `get-vanilla` example of the AWS SigV4 test suite
+        let config = AwsSigV4Config {
+            access_key_id: Some("AKIDEXAMPLE".to_string()),
+            secret_access_key: Some("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string()),
+            session_token: None,
+            region: Some("us-east-1".to_string()),
+            service: "service".to_string(),
+        };
+        let signer = AwsSigner::new(&config).unwrap();
+
+        let mut request = Client::new()
+            .get("https://example.amazonaws.com/")
+            .build()
+            .unwrap();
+        let time = Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap();
+        signer.sign(&mut request, time);
+
+        assert_eq!(request.headers()["x-amz-date"], "20150830T123600Z");
+        assert_eq!(
+            request.headers()["authorization"],
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn sigv4_streamed_body() {
+        let config = AwsSigV4Config {
+            access_key_id: Some("AKIDEXAMPLE".to_string()),
+            secret_access_key: Some("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string()),
+            session_token: None,
+            region: Some("us-east-1".to_string()),
+            service: "s3".to_string(),
+        };
+        let signer = AwsSigner::new(&config).unwrap();
+        let request = Client::new().put("https://bucket.s3.amazonaws.com/key");
+
+        // the body of the upload is attached after the request is built, but before it is signed
+        let (_, mut request) = build_request(&request, None).unwrap();
+        let body = tokio_util::io::ReaderStream::new(&b"streamed"[..]);
+        *request.body_mut() = Some(reqwest::Body::wrap_stream(body));
+        sign_request(&mut request, Some(&signer));
+
+        assert_eq!(request.headers()["x-amz-content-sha256"], UNSIGNED_PAYLOAD);
+        assert!(request.headers().contains_key("authorization"));
+    }
+}
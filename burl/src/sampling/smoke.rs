@@ -0,0 +1,83 @@
+use super::{build_request, AwsSigner};
+use crate::{BurlResult, HeaderRedactor};
+use reqwest::RequestBuilder;
+use std::{fmt, time::Duration};
+use tokio::time::Instant;
+
+/// The details of a single request, e.g. to validate an endpoint before benchmarking it.
+pub struct SmokeResult {
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// The time until the response headers are received.
+    pub duration: Duration,
+    /// The time until the full body is received.
+    pub total_duration: Duration,
+}
+
+impl SmokeResult {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+impl fmt::Display for SmokeResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "SMOKE TEST")?;
+        writeln!(f, "{} {}", self.method, self.url)?;
+        writeln!(f, "status: {}", self.status)?;
+        writeln!(
+            f,
+            "timing: {:.3} ms until the headers, {:.3} ms in total",
+            self.duration.as_secs_f64() * 1_000.0,
+            self.total_duration.as_secs_f64() * 1_000.0
+        )?;
+        writeln!(f, "HEADERS")?;
+        for (name, value) in self.headers.iter() {
+            writeln!(f, "{}: {}", name, value)?;
+        }
+        writeln!(f, "BODY ({} bytes)", self.body.len())?;
+        write!(f, "{}", String::from_utf8_lossy(&self.body))
+    }
+}
+
+/// Sends the request once and records the full response, with the secret headers redacted.
+pub(crate) async fn smoke_request(
+    request: &RequestBuilder,
+    signer: Option<&AwsSigner>,
+    redactor: &HeaderRedactor,
+) -> BurlResult<SmokeResult> {
+    let (client, request) = build_request(request, signer)?;
+    let (method, url) = (request.method().to_string(), request.url().to_string());
+
+    let start = Instant::now();
+    let response = client.execute(request).await?;
+    let duration = start.elapsed();
+
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            let value = value.to_str().unwrap_or("<binary>");
+            (
+                name.to_string(),
+                redactor.redact(name.as_str(), value).to_string(),
+            )
+        })
+        .collect();
+    let body = response.bytes().await?.to_vec();
+    let total_duration = start.elapsed();
+
+    Ok(SmokeResult {
+        method,
+        url,
+        status,
+        headers,
+        body,
+        duration,
+        total_duration,
+    })
+}
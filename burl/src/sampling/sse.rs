@@ -0,0 +1,82 @@
+use super::Clock;
+use reqwest::Response;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// The timing of the events received on a stream of Server-Sent Events.
+pub(crate) struct EventStream {
+    /// The time of each event since the start of the request.
+    pub event_times: Vec<Duration>,
+    /// The number of bytes received until the stream was closed.
+    pub n_bytes: u64,
+}
+
+/// Splits a stream of Server-Sent Events into events, which end with a blank line.
+#[derive(Default)]
+struct EventParser {
+    buffer: String,
+}
+
+impl EventParser {
+    /// Adds the chunk to the stream and returns the number of events it completed.
+    /// Blocks of comments only (lines starting with `:`, e.g. keep-alives) are no events.
+    fn push(&mut self, chunk: &[u8]) -> usize {
+        self.buffer
+            .push_str(&String::from_utf8_lossy(chunk).replace("\r\n", "\n"));
+
+        let mut n_events = 0;
+        while let Some(end) = self.buffer.find("\n\n") {
+            let block: String = self.buffer.drain(..end + 2).collect();
+            if block
+                .lines()
+                .any(|line| !line.is_empty() && !line.starts_with(':'))
+            {
+                n_events += 1;
+            }
+        }
+        n_events
+    }
+}
+
+/// Reads the events of the response until `n_events` are received or the stream ends,
+/// closing the stream thereafter.
+pub(crate) async fn read_events(
+    mut response: Response,
+    n_events: usize,
+    start: Instant,
+    clock: &dyn Clock,
+) -> reqwest::Result<EventStream> {
+    let mut parser = EventParser::default();
+    let mut stream = EventStream {
+        event_times: Vec::with_capacity(n_events),
+        n_bytes: 0,
+    };
+
+    while stream.event_times.len() < n_events {
+        let Some(chunk) = response.chunk().await? else {
+            break;
+        };
+        let received = clock.elapsed_since(start);
+        stream.n_bytes += chunk.len() as u64;
+        for _ in 0..parser.push(&chunk) {
+            stream.event_times.push(received);
+        }
+    }
+    stream.event_times.truncate(n_events);
+
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_events() {
+        let mut parser = EventParser::default();
+        assert_eq!(parser.push(b"data: a\n"), 0);
+        assert_eq!(parser.push(b"\ndata: b\r\n\r\n: keep-alive\n\n"), 2);
+        assert_eq!(parser.push(b"event: c\ndata: "), 0);
+        assert_eq!(parser.push(b"c\n\n"), 1);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{BurlError, BurlResult};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The distribution of the pause of a thread between its requests, e.g. to mimic users.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ThinkTime {
+    Fixed {
+        ms: f64,
+    },
+    Uniform {
+        #[serde(alias = "minMs")]
+        min_ms: f64,
+        #[serde(alias = "maxMs")]
+        max_ms: f64,
+    },
+    Exponential {
+        #[serde(alias = "meanMs")]
+        mean_ms: f64,
+    },
+    /// A log-normal distribution with the given mean and `sigma` of the underlying normal one.
+    LogNormal {
+        #[serde(alias = "meanMs")]
+        mean_ms: f64,
+        sigma: f64,
+    },
+}
+
+impl ThinkTime {
+    /// The mean of the distribution in millis.
+    pub fn mean_ms(&self) -> f64 {
+        match self {
+            ThinkTime::Fixed { ms } => *ms,
+            ThinkTime::Uniform { min_ms, max_ms } => (min_ms + max_ms) / 2.0,
+            ThinkTime::Exponential { mean_ms } | ThinkTime::LogNormal { mean_ms, .. } => *mean_ms,
+        }
+    }
+
+    pub fn validate(&self) -> BurlResult<()> {
+        let valid = |ms: f64| ms.is_finite() && ms >= 0.0;
+        let issue = match *self {
+            ThinkTime::Fixed { ms } if !valid(ms) => "`ms` has to be non-negative",
+            ThinkTime::Uniform { min_ms, max_ms } if !valid(min_ms) || !valid(max_ms) => {
+                "`min_ms` and `max_ms` have to be non-negative"
+            }
+            ThinkTime::Uniform { min_ms, max_ms } if min_ms > max_ms => {
+                "`min_ms` has to be at most `max_ms`"
+            }
+            ThinkTime::Exponential { mean_ms } if !valid(mean_ms) || mean_ms == 0.0 => {
+                "`mean_ms` has to be positive"
+            }
+            ThinkTime::LogNormal { mean_ms, .. } if !valid(mean_ms) || mean_ms == 0.0 => {
+                "`mean_ms` has to be positive"
+            }
+            ThinkTime::LogNormal { sigma, .. } if !sigma.is_finite() || sigma <= 0.0 => {
+                "`sigma` has to be positive"
+            }
+            _ => return Ok(()),
+        };
+        Err(BurlError::InvalidConfig {
+            issue: format!("Invalid think time distribution: {}", issue),
+        })
+    }
+}
+
+/// Draws the think times of a thread from the distribution, reproducibly by the seed.
+pub struct ThinkTimeSampler {
+    distribution: ThinkTime,
+    rng: ChaCha8Rng,
+}
+
+impl ThinkTimeSampler {
+    pub fn new(distribution: ThinkTime, seed: u64) -> Self {
+        Self {
+            distribution,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    /// A standard normal draw (Box-Muller).
+    fn standard_normal(&mut self) -> f64 {
+        let u: f64 = 1.0 - self.rng.gen::<f64>();
+        let v: f64 = self.rng.gen();
+        (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+    }
+
+    pub fn sample(&mut self) -> Duration {
+        let ms = match self.distribution {
+            ThinkTime::Fixed { ms } => ms,
+            ThinkTime::Uniform { min_ms, max_ms } => {
+                min_ms + (max_ms - min_ms) * self.rng.gen::<f64>()
+            }
+            ThinkTime::Exponential { mean_ms } => -mean_ms * (1.0 - self.rng.gen::<f64>()).ln(),
+            ThinkTime::LogNormal { mean_ms, sigma } => {
+                let mu = mean_ms.ln() - sigma * sigma / 2.0;
+                (mu + sigma * self.standard_normal()).exp()
+            }
+        };
+        // invalid parameters are rejected with the config, yet never yield a negative pause
+        Duration::from_secs_f64(ms.max(0.0) / 1_000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn think_time_means() {
+        let distributions = [
+            ThinkTime::Fixed { ms: 20.0 },
+            ThinkTime::Uniform {
+                min_ms: 10.0,
+                max_ms: 50.0,
+            },
+            ThinkTime::Exponential { mean_ms: 25.0 },
+            ThinkTime::LogNormal {
+                mean_ms: 40.0,
+                sigma: 0.5,
+            },
+        ];
+        let n_samples = 20_000;
+        for distribution in distributions {
+            distribution.validate().unwrap();
+            let mut sampler = ThinkTimeSampler::new(distribution, 42);
+            let mean_ms = (0..n_samples)
+                .map(|_| sampler.sample().as_secs_f64() * 1_000.0)
+                .sum::<f64>()
+                / n_samples as f64;
+            let expected = distribution.mean_ms();
+            assert!(
+                (mean_ms - expected).abs() < 0.03 * expected,
+                "{:?}: mean {} instead of {}",
+                distribution,
+                mean_ms,
+                expected
+            );
+        }
+
+        let parsed: ThinkTime =
+            toml::from_str("kind = \"lognormal\"\nmean_ms = 40.0\nsigma = 0.5").unwrap();
+        assert_eq!(parsed.mean_ms(), 40.0);
+        assert!(ThinkTime::Uniform {
+            min_ms: 5.0,
+            max_ms: 1.0
+        }
+        .validate()
+        .is_err());
+        assert!(ThinkTime::LogNormal {
+            mean_ms: 40.0,
+            sigma: 0.0
+        }
+        .validate()
+        .is_err());
+    }
+}
@@ -0,0 +1,99 @@
+use rustls::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+use rustls::{Certificate, ClientConfig, Error, OwnedTrustAnchor, RootCertStore, ServerName};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fmt, sync::Arc, time::SystemTime};
+
+/// A TLS protocol version, e.g. to pin the versions of the client.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TlsVersion {
+    #[serde(rename = "1.0")]
+    Tls1_0,
+    #[serde(rename = "1.1")]
+    Tls1_1,
+    #[serde(rename = "1.2")]
+    Tls1_2,
+    #[serde(rename = "1.3")]
+    Tls1_3,
+}
+
+impl fmt::Display for TlsVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TlsVersion::Tls1_0 => write!(f, "TLS 1.0"),
+            TlsVersion::Tls1_1 => write!(f, "TLS 1.1"),
+            TlsVersion::Tls1_2 => write!(f, "TLS 1.2"),
+            TlsVersion::Tls1_3 => write!(f, "TLS 1.3"),
+        }
+    }
+}
+
+impl From<TlsVersion> for reqwest::tls::Version {
+    fn from(version: TlsVersion) -> Self {
+        match version {
+            TlsVersion::Tls1_0 => reqwest::tls::Version::TLS_1_0,
+            TlsVersion::Tls1_1 => reqwest::tls::Version::TLS_1_1,
+            TlsVersion::Tls1_2 => reqwest::tls::Version::TLS_1_2,
+            TlsVersion::Tls1_3 => reqwest::tls::Version::TLS_1_3,
+        }
+    }
+}
+
+/// Skips the validation of server certificates for the listed hosts only,
+/// e.g. for self-signed certificates; all other hosts are validated as usual.
+struct InsecureHostsVerifier {
+    hosts: HashSet<String>,
+    verifier: WebPkiVerifier,
+}
+
+impl ServerCertVerifier for InsecureHostsVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, Error> {
+        let host = match server_name {
+            ServerName::DnsName(name) => name.as_ref().to_string(),
+            ServerName::IpAddress(ip) => ip.to_string(),
+            _ => String::new(),
+        };
+
+        if self.hosts.contains(&host) {
+            return Ok(ServerCertVerified::assertion());
+        }
+        self.verifier.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            scts,
+            ocsp_response,
+            now,
+        )
+    }
+}
+
+/// The TLS config which accepts any certificate of the `insecure_hosts`,
+/// validating the others against the webpki roots (as reqwest does by default).
+pub(crate) fn insecure_hosts_tls_config(insecure_hosts: &[String]) -> ClientConfig {
+    let mut roots = RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(
+            anchor.subject,
+            anchor.spki,
+            anchor.name_constraints,
+        )
+    }));
+
+    let verifier = InsecureHostsVerifier {
+        hosts: insecure_hosts.iter().cloned().collect(),
+        verifier: WebPkiVerifier::new(roots, None),
+    };
+
+    ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth()
+}
@@ -0,0 +1,109 @@
+use crate::{BurlError, BurlResult};
+use reqwest::Url;
+use std::net::SocketAddr;
+use tokio::sync::oneshot;
+
+/// The pause of the relay after a failed accept, e.g. while out of file descriptors.
+#[cfg(unix)]
+const ACCEPT_BACKOFF: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Relays the connections of a loopback port to a Unix domain socket, as the HTTP client
+/// connects over TCP only. The requests are routed to the port instead of their host (see `route`),
+/// which adds a loopback hop to the measured durations.
+pub(crate) struct UnixSocketRelay {
+    addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl UnixSocketRelay {
+    /// Binds the loopback port and relays its connections on a runtime of its own, with a worker
+    /// per core such that the relay keeps up with the concurrent threads, until the relay is dropped.
+    #[cfg(unix)]
+    pub fn start(socket_path: &str) -> BurlResult<Self> {
+        use std::net::{Ipv4Addr, TcpListener};
+        use tokio::net::{TcpListener as AsyncTcpListener, UnixStream};
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .thread_name("burl-unix-socket-relay")
+            .enable_all()
+            .build()?;
+        let (shutdown, mut shutdown_rx) = oneshot::channel();
+        let socket_path = socket_path.to_string();
+
+        std::thread::spawn(move || {
+            runtime.block_on(async move {
+                let listener = AsyncTcpListener::from_std(listener).expect("relay listener");
+                loop {
+                    let mut inbound = tokio::select! {
+                        accepted = listener.accept() => match accepted {
+                            Ok((inbound, _)) => inbound,
+                            Err(err) => {
+                                log::warn!("Could not accept a relayed connection: {}", err);
+                                tokio::time::sleep(ACCEPT_BACKOFF).await;
+                                continue;
+                            }
+                        },
+                        _ = &mut shutdown_rx => break,
+                    };
+                    let socket_path = socket_path.clone();
+                    tokio::spawn(async move {
+                        match UnixStream::connect(&socket_path).await {
+                            Ok(mut outbound) => {
+                                let _ = tokio::io::copy_bidirectional(&mut inbound, &mut outbound)
+                                    .await;
+                            }
+                            Err(err) => {
+                                log::error!("Could not connect to {}: {}", socket_path, err)
+                            }
+                        }
+                    });
+                }
+            })
+        });
+
+        Ok(Self {
+            addr,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn start(_socket_path: &str) -> BurlResult<Self> {
+        Err(BurlError::InvalidConfig {
+            issue: "`unix_socket` is supported on Unix only".to_string(),
+        })
+    }
+
+    /// The url routed to the relay and the `Host` of the original url.
+    pub fn route(&self, url: &str) -> BurlResult<(String, String)> {
+        let invalid = |issue: &str| BurlError::InvalidConfig {
+            issue: format!("Cannot send '{}' over the unix socket: {}", url, issue),
+        };
+
+        let mut routed = Url::parse(url).map_err(|err| invalid(&err.to_string()))?;
+        if routed.scheme() != "http" {
+            return Err(invalid("only http is supported"));
+        }
+        let host = match (routed.host_str(), routed.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(invalid("the url has no host")),
+        };
+        routed
+            .set_ip_host(self.addr.ip())
+            .and_then(|_| routed.set_port(Some(self.addr.port())))
+            .map_err(|_| invalid("the url cannot be routed"))?;
+        Ok((routed.to_string(), host))
+    }
+}
+
+impl Drop for UnixSocketRelay {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use super::Clock;
+use reqwest::Body;
+use std::{
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::time::Instant;
+use tokio_util::io::ReaderStream;
+
+/// The progress of a streamed upload: the bytes handed over so far and when the file was read up.
+#[derive(Default)]
+pub(crate) struct UploadProgress {
+    n_bytes: AtomicU64,
+    end: Mutex<Option<Instant>>,
+}
+
+impl UploadProgress {
+    pub fn n_bytes(&self) -> u64 {
+        self.n_bytes.load(Ordering::SeqCst)
+    }
+
+    /// The time from the `start` of the request until the end of the file was handed over;
+    /// `None` if the stream did not end, e.g. as the server responded early.
+    pub fn duration_since(&self, start: Instant) -> Option<Duration> {
+        let end = (*self.end.lock().unwrap())?;
+        Some(end.saturating_duration_since(start))
+    }
+}
+
+/// Counts the bytes read from the inner reader, i.e. the bytes handed over for the upload,
+/// and records the time of the end of the stream by the clock.
+struct CountingReader<R> {
+    inner: R,
+    progress: Arc<UploadProgress>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for CountingReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let n_filled = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let n_read = buf.filled().len() - n_filled;
+        self.progress
+            .n_bytes
+            .fetch_add(n_read as u64, Ordering::SeqCst);
+        // NOTE: a read of no bytes into a non-empty buffer is the end of the file
+        if let Poll::Ready(Ok(())) = poll {
+            if n_read == 0 && buf.remaining() > 0 {
+                let now = self.clock.now();
+                self.progress.end.lock().unwrap().get_or_insert(now);
+            }
+        }
+        poll
+    }
+}
+
+/// The file streamed as a (chunked) request body, see `upload_file_ref`,
+/// and the progress of its upload, timed by the clock.
+pub(crate) async fn streamed_file(
+    path: &str,
+    clock: Arc<dyn Clock>,
+) -> io::Result<(Body, Arc<UploadProgress>)> {
+    let file = tokio::fs::File::open(path).await?;
+    let progress = Arc::new(UploadProgress::default());
+    let reader = CountingReader {
+        inner: file,
+        progress: progress.clone(),
+        clock,
+    };
+    Ok((Body::wrap_stream(ReaderStream::new(reader)), progress))
+}
@@ -0,0 +1,158 @@
+use super::FailureKind;
+use crate::{BenchClientConfig, BurlError, BurlResult};
+use jsonschema::JSONSchema;
+use serde_json::Value;
+use std::fs;
+
+/// Validates the bodies of successful responses, e.g. against a JSON schema.
+pub struct ResponseValidator {
+    schema: Option<JSONSchema>,
+    /// The JSON pointer of the expected field and its expected value.
+    expected_field: Option<(String, Value)>,
+}
+
+/// Converts a path like `$.data.items[0].id` into a JSON pointer like `/data/items/0/id`.
+fn json_pointer(path: &str) -> BurlResult<String> {
+    let invalid = || BurlError::InvalidConfig {
+        issue: format!("Invalid JSON field path '{}', e.g. `$.status`", path),
+    };
+
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let path = path.strip_prefix('.').unwrap_or(path);
+    let mut pointer = String::new();
+    for segment in path.split('.') {
+        let (name, indices) = segment.split_once('[').unwrap_or((segment, ""));
+        if name.is_empty() && indices.is_empty() {
+            return Err(invalid());
+        }
+        if !name.is_empty() {
+            pointer.push('/');
+            pointer.push_str(&name.replace('~', "~0").replace('/', "~1"));
+        }
+        if !indices.is_empty() {
+            for index in format!("[{}", indices).split_terminator(']') {
+                let index = index.strip_prefix('[').ok_or_else(invalid)?;
+                index.parse::<usize>().map_err(|_| invalid())?;
+                pointer.push('/');
+                pointer.push_str(index);
+            }
+        }
+    }
+    Ok(pointer)
+}
+
+fn compile(schema: &Value) -> BurlResult<JSONSchema> {
+    JSONSchema::compile(schema).map_err(|err| BurlError::InvalidConfig {
+        issue: format!("Invalid JSON schema: {}", err),
+    })
+}
+
+impl ResponseValidator {
+    pub fn new(schema: &Value) -> BurlResult<Self> {
+        Ok(Self {
+            schema: Some(compile(schema)?),
+            expected_field: None,
+        })
+    }
+
+    /// Validates that the field at the `path` (e.g. `$.status`) equals the `value`.
+    pub fn expecting_field(path: &str, value: Value) -> BurlResult<Self> {
+        Ok(Self {
+            schema: None,
+            expected_field: Some((json_pointer(path)?, value)),
+        })
+    }
+
+    /// Loads the JSON schema referenced by `response_schema_ref` and the `expect_json_field`,
+    /// if configured.
+    pub fn from_config(config: &BenchClientConfig) -> BurlResult<Option<Self>> {
+        let schema = match &config.response_schema_ref {
+            Some(schema_ref) => {
+                let schema: Value = serde_json::from_str(&fs::read_to_string(schema_ref)?)?;
+                Some(compile(&schema)?)
+            }
+            None => None,
+        };
+        let expected_field = match &config.expect_json_field {
+            Some((path, value)) => Some((json_pointer(path)?, value.clone())),
+            None => None,
+        };
+
+        if schema.is_none() && expected_field.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            schema,
+            expected_field,
+        }))
+    }
+
+    /// The kind of failure if the body is not valid JSON, does not conform to the schema or
+    /// its expected field differs.
+    pub fn validate(&self, body: &[u8]) -> Result<(), FailureKind> {
+        let instance = serde_json::from_slice::<Value>(body).map_err(|_| match self.schema {
+            Some(_) => FailureKind::SchemaViolation,
+            None => FailureKind::FieldMismatch,
+        })?;
+
+        if let Some(schema) = &self.schema {
+            if !schema.is_valid(&instance) {
+                return Err(FailureKind::SchemaViolation);
+            }
+        }
+        if let Some((pointer, expected)) = &self.expected_field {
+            if instance.pointer(pointer) != Some(expected) {
+                return Err(FailureKind::FieldMismatch);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the body is valid JSON and conforms to the schema and the expected field.
+    pub fn is_valid(&self, body: &[u8]) -> bool {
+        self.validate(body).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn schema_validation() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "name": { "type": "string" } },
+            "required": ["name"]
+        });
+        let validator = ResponseValidator::new(&schema).unwrap();
+
+        assert!(validator.is_valid(br#"{ "name": "john doezer" }"#));
+        assert!(!validator.is_valid(br#"{ "name": 10.2 }"#));
+        assert!(!validator.is_valid(br#"{ "price": 10.2 }"#));
+        assert!(!validator.is_valid(b"not json"));
+    }
+
+    #[test]
+    fn expected_field() {
+        assert_eq!(json_pointer("$.status").unwrap(), "/status");
+        assert_eq!(
+            json_pointer("$.data.items[0][2].a/b").unwrap(),
+            "/data/items/0/2/a~1b"
+        );
+        assert!(json_pointer("$.").is_err());
+        assert!(json_pointer("$.items[x]").is_err());
+
+        let validator = ResponseValidator::expecting_field("$.status", json!("ok")).unwrap();
+        assert_eq!(validator.validate(br#"{ "status": "ok" }"#), Ok(()));
+        assert_eq!(
+            validator.validate(br#"{ "status": "degraded" }"#),
+            Err(FailureKind::FieldMismatch)
+        );
+        assert_eq!(
+            validator.validate(br#"{ "state": "ok" }"#),
+            Err(FailureKind::FieldMismatch)
+        );
+    }
+}
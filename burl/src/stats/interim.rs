@@ -0,0 +1,163 @@
+use super::{percentile, sum};
+use crate::{config::DurationScale, sampling::RequestResult};
+use log::info;
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::task::JoinHandle;
+
+#[derive(Default)]
+struct InterimSamples {
+    durations: Vec<f64>,
+    n_failed: usize,
+    /// The span of the successful samples, from the first start to the last end.
+    first_start: Option<Duration>,
+    last_end: Option<Duration>,
+}
+
+/// The samples collected so far by the samplers of all threads, e.g. to print interim
+/// summaries during long runs. The samples of the `discard_first_secs` are included.
+pub struct InterimStats {
+    scale: DurationScale,
+    samples: Mutex<InterimSamples>,
+}
+
+/// The stats of the samples collected up to a checkpoint of the run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterimSummary {
+    pub scale: DurationScale,
+    pub n_ok: usize,
+    pub n_failed: usize,
+    pub mean: f64,
+    pub p95: f64,
+    /// The successful requests per second, over the span of the samples so far.
+    pub rps: Option<f64>,
+}
+
+impl fmt::Display for InterimSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Interim: {} ok, {} failed, mean {:.2} {}s, p95 {:.2} {}s",
+            self.n_ok, self.n_failed, self.mean, self.scale, self.p95, self.scale
+        )?;
+        match self.rps {
+            Some(rps) => write!(f, ", {:.1} requests/s", rps),
+            None => Ok(()),
+        }
+    }
+}
+
+impl InterimStats {
+    pub fn new(scale: DurationScale) -> Self {
+        Self {
+            scale,
+            samples: Mutex::new(InterimSamples::default()),
+        }
+    }
+
+    pub fn add(&self, result: &RequestResult) {
+        let mut samples = self.samples.lock().unwrap();
+        match result {
+            RequestResult::Ok(sample) => {
+                samples.durations.push(sample.duration);
+                let (start, end) = (sample.duration_since_start, sample.duration_request_end);
+                samples.first_start = Some(samples.first_start.map_or(start, |s| s.min(start)));
+                samples.last_end = Some(samples.last_end.map_or(end, |e| e.max(end)));
+            }
+            RequestResult::Failed(..) => samples.n_failed += 1,
+        }
+    }
+
+    /// The summary of the samples so far; `None` without any successful sample.
+    pub fn summary(&self) -> Option<InterimSummary> {
+        let samples = self.samples.lock().unwrap();
+        if samples.durations.is_empty() {
+            return None;
+        }
+        let mut durations = samples.durations.clone();
+        let n_failed = samples.n_failed;
+        let span = match (samples.first_start, samples.last_end) {
+            (Some(start), Some(end)) => end.saturating_sub(start),
+            _ => Duration::ZERO,
+        };
+        drop(samples);
+
+        durations.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let n = durations.len();
+        Some(InterimSummary {
+            scale: self.scale.clone(),
+            n_ok: n,
+            n_failed,
+            mean: sum(&durations) / n as f64,
+            p95: percentile(&durations, 0.95, n as f64),
+            rps: (!span.is_zero()).then(|| n as f64 / span.as_secs_f64()),
+        })
+    }
+
+    /// Logs the summary every `interval`, until the returned task is aborted.
+    pub fn log_periodically(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let stats = self.clone();
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            // NOTE: the first tick completes immediately, before any samples
+            ticks.tick().await;
+            loop {
+                ticks.tick().await;
+                if let Some(summary) = stats.summary() {
+                    info!("{}", summary);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampling::{FailureKind, SampleResult};
+
+    fn sample(start_millis: u64, duration: f64) -> RequestResult {
+        let start = Duration::from_millis(start_millis);
+        RequestResult::Ok(SampleResult {
+            duration_since_start: start,
+            duration_request_end: start + Duration::from_millis(duration as u64),
+            request_duration: Duration::from_millis(duration as u64),
+            measurement_start: start_millis as f64,
+            measurement_end: start_millis as f64 + duration,
+            duration,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn interim_checkpoints() {
+        let stats = InterimStats::new(DurationScale::Milli);
+        assert_eq!(stats.summary(), None);
+
+        stats.add(&RequestResult::Failed(FailureKind::Status(500), None, 3.0));
+        assert_eq!(stats.summary(), None);
+
+        for idx in 0..10 {
+            stats.add(&sample(idx * 100, (idx + 1) as f64));
+        }
+        let checkpoint = stats.summary().unwrap();
+        assert_eq!(checkpoint.n_ok, 10);
+        assert_eq!(checkpoint.n_failed, 1);
+        assert_eq!(checkpoint.mean, 5.5);
+        assert_eq!(checkpoint.p95, 10.0);
+        // 10 samples from 0 to 910 ms
+        assert!((checkpoint.rps.unwrap() - 10.0 / 0.91).abs() < 1e-9);
+
+        for idx in 10..20 {
+            stats.add(&sample(idx * 100, 20.0));
+        }
+        let checkpoint = stats.summary().unwrap();
+        assert_eq!(checkpoint.n_ok, 20);
+        assert_eq!(checkpoint.mean, 12.75);
+        assert_eq!(checkpoint.p95, 20.0);
+        assert!((checkpoint.rps.unwrap() - 20.0 / 1.92).abs() < 1e-9);
+    }
+}
@@ -1,8 +1,16 @@
+mod interim;
+#[allow(clippy::module_inception)]
 mod stats;
 mod stats_collection;
 
+pub use interim::{InterimStats, InterimSummary};
 pub use stats::{
-    confidence_interval, normal_qq, percentile, requests_per_sec, standard_deviation, sum,
-    AnalyticTester, BootstrapSampler, NormalParams, PermutationTester, TestOutcome,
+    anderson_darling, confidence_interval, normal_qq, percentile, rate_accuracy, requests_per_sec,
+    run_variance, standard_deviation, sum, AnalyticTester, BootstrapPercentileTester,
+    BootstrapSampler, NormalParams, NormalityTest, PartialBucket, PermutationTester, PrimaryMetric,
+    RateAccuracy, RunVariance, SignificanceTest, TargetTester, TestOutcome,
+};
+pub use stats_collection::{
+    format_bytes, format_delta, format_value, HistogramBin, StatsProcessor, StatsSummary,
+    SummaryDisplay, ThreadStats, MIN_SAMPLES, NORMALITY_ALPHA,
 };
-pub use stats_collection::{StatsProcessor, StatsSummary, ThreadStats};
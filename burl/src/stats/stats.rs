@@ -2,9 +2,11 @@ use crate::config::DurationScale;
 use rand::distributions::Uniform;
 use rand::Rng;
 use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
 use statrs::distribution::ContinuousCDF;
 use statrs::distribution::Normal;
 use std::collections::HashSet;
+use std::fmt;
 
 pub type Probablity = f64; // values in [0,1]
 pub type Percentage = f64; // values in [0,100]
@@ -39,11 +41,11 @@ pub fn percentile(samples: &[f64], level: f64, n: f64) -> f64 {
 
     // case candidate is an integer
     if candidate_idx == floored as f64 {
-        let idx_bottom = (floored - 1).max(0);
-        let idx_top = floored.min(n as usize);
+        let idx_bottom = floored.saturating_sub(1);
+        let idx_top = floored.min(n as usize - 1);
         return 0.5 * (samples[idx_bottom] + samples[idx_top]);
     }
-    let idx = ((candidate_idx + 1.0).floor().min(n) as usize - 1).max(0);
+    let idx = ((candidate_idx + 1.0).floor().min(n) as usize).saturating_sub(1);
     samples[idx]
 }
 
@@ -80,12 +82,84 @@ pub fn normal_qq(
     qq
 }
 
-pub fn confidence_interval(distribution: &Vec<f64>, alpha: f64) -> Option<(f64, f64)> {
+/// The minimal number of samples for the Anderson-Darling test.
+const MIN_NORMALITY_SAMPLES: usize = 8;
+
+/// The outcome of a test whether samples are normally distributed, see `anderson_darling`.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct NormalityTest {
+    /// The (small sample corrected) Anderson-Darling statistic A*².
+    pub statistic: f64,
+    /// The probability of a statistic at least as large for normal samples.
+    pub p_value: Probablity,
+}
+
+impl NormalityTest {
+    /// Normality is rejected if the p-value is below alpha.
+    pub fn is_normal(&self, alpha: Probablity) -> bool {
+        self.p_value >= alpha
+    }
+}
+
+impl fmt::Display for NormalityTest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Anderson-Darling A² = {:.3}, p-value = {:.4}",
+            self.statistic, self.p_value
+        )
+    }
+}
+
+/// The [Anderson-Darling test](https://en.wikipedia.org/wiki/Anderson%E2%80%93Darling_test)
+/// for normality, with the mean and std estimated from the samples;
+/// the p-value is approximated as in D'Agostino & Stephens (1986).
+/// Requires at least 8 samples with a positive std.
+pub fn anderson_darling(samples: &[f64]) -> Option<NormalityTest> {
+    let n = samples.len();
+    if n < MIN_NORMALITY_SAMPLES {
+        return None;
+    }
+    let mean = sum(samples) / n as f64;
+    let std = standard_deviation(samples, mean)?;
+    if std < ZERO_THRESHOLD {
+        return None;
+    }
+
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let normal = Normal::new(mean, std).ok()?;
+    // NOTE: bounded away from 0 and 1, as outliers would yield infinite logarithms
+    let cdf = |x: f64| normal.cdf(x).clamp(1e-15, 1.0 - 1e-15);
+
+    let weighted_sum: f64 = (0..n)
+        .map(|i| {
+            let weight = (2 * i + 1) as f64;
+            weight * (cdf(sorted[i]).ln() + (1.0 - cdf(sorted[n - 1 - i])).ln())
+        })
+        .sum();
+    let a_squared = -(n as f64) - weighted_sum / n as f64;
+    let statistic = a_squared * (1.0 + 0.75 / n as f64 + 2.25 / (n * n) as f64);
+
+    let p_value = match statistic {
+        s if s >= 0.6 => (1.2937 - 5.709 * s + 0.0186 * s * s).exp(),
+        s if s >= 0.34 => (0.9177 - 4.279 * s - 1.38 * s * s).exp(),
+        s if s >= 0.2 => 1.0 - (-8.318 + 42.796 * s - 59.938 * s * s).exp(),
+        s => 1.0 - (-13.436 + 101.14 * s - 223.73 * s * s).exp(),
+    };
+
+    Some(NormalityTest {
+        statistic,
+        p_value: p_value.clamp(0.0, 1.0),
+    })
+}
+
+pub fn confidence_interval(distribution: &[f64], alpha: f64) -> Option<(f64, f64)> {
     if distribution.is_empty() {
         return None;
     }
 
-    let mut sorted = distribution.clone();
+    let mut sorted = distribution.to_vec();
     sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
     let alpha_2 = alpha / 2.0;
     let lower_bound = percentile(&sorted, alpha_2, distribution.len() as f64);
@@ -93,13 +167,206 @@ pub fn confidence_interval(distribution: &Vec<f64>, alpha: f64) -> Option<(f64,
     Some((lower_bound, upper_bound))
 }
 
+/// The achieved rate of requests compared to a target rate, per time bucket.
+#[derive(Debug, Clone)]
+pub struct RateAccuracy {
+    pub target_rps: f64,
+    pub bucket_secs: f64,
+    /// The achieved requests per second of each bucket.
+    pub achieved_rps: Vec<f64>,
+    /// The deviation from the target with the largest magnitude, relative to the target.
+    /// Negative values are undershoots.
+    pub max_deviation: f64,
+    /// The index of the bucket with the max deviation.
+    pub max_deviation_bucket: usize,
+}
+
+/// How the trailing incomplete bucket of the rates per bucket is handled, see `rate_accuracy`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PartialBucket {
+    /// The bucket is ignored, unless it is the only one.
+    #[default]
+    Drop,
+    /// The rate of the bucket is relative to the part of it covered by the run.
+    Normalize,
+    /// The rate of the bucket is relative to its full width, hence skewed low.
+    Keep,
+}
+
+/// Compares the rate of requests given by their start `timestamps` (in secs since the start of the run)
+/// to the `target_rps` for each bucket of `bucket_secs`.
+/// A trailing incomplete bucket is handled according to `partial_bucket`, unless it is the only one.
+pub fn rate_accuracy(
+    timestamps: &[f64],
+    target_rps: f64,
+    bucket_secs: f64,
+    partial_bucket: PartialBucket,
+) -> Option<RateAccuracy> {
+    if target_rps < ZERO_THRESHOLD || bucket_secs < ZERO_THRESHOLD {
+        return None;
+    }
+    let span = timestamps.iter().cloned().fold(f64::NAN, f64::max);
+    if span.is_nan() || span < ZERO_THRESHOLD {
+        return None;
+    }
+
+    let n_complete = (span / bucket_secs).floor() as usize;
+    let partial_width = span - n_complete as f64 * bucket_secs;
+    let (n_buckets, bucket_width) = match n_complete {
+        0 => (1, span),
+        n => (n, bucket_secs),
+    };
+    // the width by which the count of each bucket is divided
+    let mut widths = vec![bucket_width; n_buckets];
+    if n_complete > 0 && partial_width > ZERO_THRESHOLD {
+        match partial_bucket {
+            PartialBucket::Drop => {}
+            PartialBucket::Normalize => widths.push(partial_width),
+            PartialBucket::Keep => widths.push(bucket_width),
+        }
+    }
+
+    let mut counts = vec![0_usize; widths.len()];
+    for timestamp in timestamps {
+        let idx = (timestamp / bucket_width).floor() as usize;
+        if idx < counts.len() {
+            counts[idx] += 1;
+        } else if n_complete == 0 {
+            // the last timestamp closes the only, incomplete bucket
+            counts[0] += 1;
+        }
+    }
+
+    let achieved_rps: Vec<f64> = counts
+        .iter()
+        .zip(widths.iter())
+        .map(|(count, width)| *count as f64 / width)
+        .collect();
+    let (max_deviation_bucket, max_deviation) = achieved_rps
+        .iter()
+        .map(|rps| (rps - target_rps) / target_rps)
+        .enumerate()
+        .reduce(|max, dev| if dev.1.abs() > max.1.abs() { dev } else { max })?;
+
+    Some(RateAccuracy {
+        target_rps,
+        bucket_secs: bucket_width,
+        achieved_rps,
+        max_deviation,
+        max_deviation_bucket,
+    })
+}
+
+impl fmt::Display for RateAccuracy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mean_rps = sum(&self.achieved_rps) / self.achieved_rps.len() as f64;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "_______RATE_[target {} requests/s]____________",
+            self.target_rps
+        )?;
+        writeln!(f, "Mean achieved   | {:.2}", mean_rps)?;
+        writeln!(
+            f,
+            "Max deviation   | {:+.1}% (bucket {} of {}s)",
+            self.max_deviation * 100.0,
+            self.max_deviation_bucket,
+            self.bucket_secs
+        )?;
+        if self.max_deviation < 0.0 {
+            writeln!(
+                f,
+                "NOTE: an undershoot hints that the client is the bottleneck"
+            )?;
+        }
+        writeln!(f, "_______________________________________________")
+    }
+}
+
+/// The variability of repeated runs: the variance of the durations split into
+/// the variance between the (means of the) runs and the variance within the runs.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunVariance {
+    /// The mean duration of each run.
+    pub run_means: Vec<f64>,
+    /// The sample variance of the means of the runs.
+    pub between_run_variance: f64,
+    /// The variance of the durations within each run, pooled over the runs.
+    pub within_run_variance: f64,
+}
+
+/// Estimates the variance between and within the runs from their durations.
+/// Runs without durations are ignored; at least two runs are required.
+pub fn run_variance(run_durations: &[Vec<f64>]) -> Option<RunVariance> {
+    let runs: Vec<&Vec<f64>> = run_durations.iter().filter(|d| !d.is_empty()).collect();
+    if runs.len() < 2 {
+        return None;
+    }
+
+    let run_means: Vec<f64> = runs.iter().map(|d| sum(d) / d.len() as f64).collect();
+    let grand_mean = sum(&run_means) / run_means.len() as f64;
+    let between_run_variance = standard_deviation(&run_means, grand_mean)?.powi(2);
+
+    let (squared_errors, degrees_of_freedom) = runs.iter().zip(run_means.iter()).fold(
+        (0.0, 0),
+        |(squared_errors, dof), (durations, mean)| {
+            let run_errors: f64 = durations.iter().map(|d| (d - mean).powi(2)).sum();
+            (squared_errors + run_errors, dof + durations.len() - 1)
+        },
+    );
+    let within_run_variance = match degrees_of_freedom {
+        0 => 0.0,
+        dof => squared_errors / dof as f64,
+    };
+
+    Some(RunVariance {
+        run_means,
+        between_run_variance,
+        within_run_variance,
+    })
+}
+
+impl fmt::Display for RunVariance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let means: Vec<String> = self.run_means.iter().map(|m| format!("{:.2}", m)).collect();
+        writeln!(f)?;
+        writeln!(
+            f,
+            "_______RUNS_[{} repeated runs]_______________",
+            self.run_means.len()
+        )?;
+        writeln!(f, "Run means       | {}", means.join(", "))?;
+        writeln!(
+            f,
+            "Between runs    | variance {:.3} (std {:.3})",
+            self.between_run_variance,
+            self.between_run_variance.sqrt()
+        )?;
+        writeln!(
+            f,
+            "Within runs     | variance {:.3} (std {:.3})",
+            self.within_run_variance,
+            self.within_run_variance.sqrt()
+        )?;
+        writeln!(f, "_______________________________________________")
+    }
+}
+
 pub struct BootstrapSampler<'a> {
     samples: &'a [f64],
+    seed: u64,
 }
 
 impl<'a> BootstrapSampler<'a> {
     pub fn new(samples: &'a [f64]) -> Self {
-        Self { samples }
+        Self { samples, seed: 42 }
+    }
+
+    /// Resamples with another seed, e.g. independently of a sampler of another run.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
     }
 
     fn simulate_sample_distr<F: rand::Rng>(&self, rng: &mut F, n_distr: usize) -> Vec<f64> {
@@ -124,26 +391,46 @@ impl<'a> BootstrapSampler<'a> {
     }
 
     pub fn sample_means(&self, n: usize, n_samples: usize) -> Vec<f64> {
-        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(42);
+        self.sample_statistics(n, n_samples, |resampled| {
+            sum(resampled) / resampled.len() as f64
+        })
+    }
 
-        let bs_samples = self.bootstrap_samples(&mut rng, n, n_samples);
+    /// The distribution of the `statistic` over `n_samples` resamples of size `n`.
+    pub fn sample_statistics(
+        &self,
+        n: usize,
+        n_samples: usize,
+        statistic: impl Fn(&mut [f64]) -> f64,
+    ) -> Vec<f64> {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(self.seed);
 
-        let means = bs_samples
-            .iter()
-            .map(|resampled| sum(resampled) / resampled.len() as f64)
-            .collect();
+        let mut bs_samples = self.bootstrap_samples(&mut rng, n, n_samples);
 
-        means
+        bs_samples
+            .iter_mut()
+            .map(|resampled| statistic(resampled))
+            .collect()
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Serialize)]
 pub enum TestOutcome {
     Regressed { p_value: f64 },
     Improved { p_value: f64 },
     Inconclusive,
 }
 
+impl fmt::Display for TestOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TestOutcome::Regressed { p_value } => write!(f, "regressed (p-value {})", p_value),
+            TestOutcome::Improved { p_value } => write!(f, "improved (p-value {})", p_value),
+            TestOutcome::Inconclusive => write!(f, "inconclusive (no significant change)"),
+        }
+    }
+}
+
 /// We assume:
 /// - the samples (of durations) to be independent, identical Gaussian random variables
 /// - the number of samples (for each collection) to be sufficiently large, so that the estimated std deviations are good approximations
@@ -212,6 +499,41 @@ impl<'a> AnalyticTester<'a> {
     }
 }
 
+/// Tests whether the mean of the samples is significantly below (or above) a fixed target,
+/// e.g. the mean latency of an SLA, without a baseline run.
+/// We assume, as for the `AnalyticTester`, independent Gaussian samples whose number is large
+/// enough that the estimated std deviation is a 'good' approximation (one-sample z-test).
+pub struct TargetTester<'a> {
+    np_current: &'a NormalParams,
+    target: f64,
+}
+
+impl<'a> TargetTester<'a> {
+    pub fn new(np_current: &'a NormalParams, target: f64) -> Self {
+        Self { np_current, target }
+    }
+
+    /// `Improved` if the mean is significantly below the target, `Regressed` if significantly above.
+    pub fn test(&self, alpha: Probablity) -> Option<TestOutcome> {
+        let std_error = self.np_current.std / (self.np_current.n_samples as f64).sqrt();
+        if std_error.abs() < ZERO_THRESHOLD {
+            return None;
+        }
+
+        let t = (self.target - self.np_current.mean) / std_error;
+        let p_value = 1.0 - Normal::new(0.0, 1.0).unwrap().cdf(t.abs());
+        if p_value > alpha {
+            return Some(TestOutcome::Inconclusive);
+        }
+
+        if self.np_current.mean < self.target {
+            Some(TestOutcome::Improved { p_value })
+        } else {
+            Some(TestOutcome::Regressed { p_value })
+        }
+    }
+}
+
 /// The null hypothesis of the [Permutation test](https://en.wikipedia.org/wiki/Permutation_test)
 /// is that all samples come from the same distribution;
 /// or in other words, there is no 'significant distinction' between both.
@@ -331,6 +653,101 @@ impl<'a> PermutationTester<'a> {
     }
 }
 
+/// The statistic of the durations on which the headline verdict of a comparison is based.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PrimaryMetric {
+    /// Tested analytically (t-test).
+    #[default]
+    #[serde(alias = "mean")]
+    Mean,
+    /// Tested by bootstrapping, as all of the percentiles.
+    #[serde(alias = "median")]
+    Median,
+    #[serde(alias = "p95")]
+    P95,
+    #[serde(alias = "p99")]
+    P99,
+}
+
+impl PrimaryMetric {
+    /// The level of the percentile; `None` for the mean.
+    pub fn percentile_level(&self) -> Option<f64> {
+        match self {
+            PrimaryMetric::Mean => None,
+            PrimaryMetric::Median => Some(0.5),
+            PrimaryMetric::P95 => Some(0.95),
+            PrimaryMetric::P99 => Some(0.99),
+        }
+    }
+}
+
+impl fmt::Display for PrimaryMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrimaryMetric::Mean => write!(f, "mean"),
+            PrimaryMetric::Median => write!(f, "median"),
+            PrimaryMetric::P95 => write!(f, "p95"),
+            PrimaryMetric::P99 => write!(f, "p99"),
+        }
+    }
+}
+
+/// Compares a percentile of both runs by the confidence interval of its difference
+/// (current - baseline) over independent bootstrap resamples of each run:
+/// a significant change if the interval excludes zero.
+pub struct BootstrapPercentileTester<'a> {
+    current_samples: &'a [f64],
+    baseline_samples: &'a [f64],
+    level: f64,
+}
+
+impl<'a> BootstrapPercentileTester<'a> {
+    pub fn new(current_samples: &'a [f64], baseline_samples: &'a [f64], level: f64) -> Self {
+        Self {
+            current_samples,
+            baseline_samples,
+            level,
+        }
+    }
+
+    fn bootstrap_percentiles(&self, samples: &[f64], n_samples: usize, seed: u64) -> Vec<f64> {
+        BootstrapSampler::new(samples)
+            .with_seed(seed)
+            .sample_statistics(samples.len(), n_samples, |resampled| {
+                resampled.sort_by(f64::total_cmp);
+                percentile(resampled, self.level, resampled.len() as f64)
+            })
+    }
+
+    pub fn test(&self, n_samples: usize, alpha: f64) -> Option<TestOutcome> {
+        if self.current_samples.is_empty() || self.baseline_samples.is_empty() || n_samples == 0 {
+            return None;
+        }
+
+        let current = self.bootstrap_percentiles(self.current_samples, n_samples, 42);
+        let baseline = self.bootstrap_percentiles(self.baseline_samples, n_samples, 43);
+        let differences: Vec<f64> = current
+            .iter()
+            .zip(baseline.iter())
+            .map(|(current, baseline)| current - baseline)
+            .collect();
+
+        let (lower_bound, upper_bound) = confidence_interval(&differences, alpha)?;
+        // the two-sided share of the resampled differences on the other side of zero
+        let n_above = differences.iter().filter(|diff| **diff > 0.0).count();
+        let n_below = differences.iter().filter(|diff| **diff < 0.0).count();
+        let p_value = (2.0 * (n_samples - n_above.max(n_below)) as f64 / n_samples as f64).min(1.0);
+
+        if lower_bound > 0.0 {
+            Some(TestOutcome::Regressed { p_value })
+        } else if upper_bound < 0.0 {
+            Some(TestOutcome::Improved { p_value })
+        } else {
+            Some(TestOutcome::Inconclusive)
+        }
+    }
+}
+
 pub trait SignificanceTest {
     fn test(&self, alpha: Probablity) -> Option<TestOutcome>;
 }
@@ -339,6 +756,97 @@ pub trait SignificanceTest {
 mod tests {
     use super::*;
 
+    #[test]
+    fn rate_accuracy() {
+        // 10 requests/s during the first 2 secs, then 7 requests/s; the incomplete 4th sec is ignored
+        let timestamps: Vec<f64> = (0..20)
+            .map(|i| i as f64 * 0.1)
+            .chain((0..14).map(|i| 2.0 + i as f64 / 7.0))
+            .chain([4.0, 4.5])
+            .collect();
+
+        let accuracy = super::rate_accuracy(&timestamps, 10.0, 1.0, PartialBucket::Drop).unwrap();
+        assert_eq!(accuracy.achieved_rps, vec![10.0, 10.0, 7.0, 7.0]);
+        assert!((accuracy.max_deviation + 0.3).abs() < 1e-9);
+        assert_eq!(accuracy.max_deviation_bucket, 2);
+
+        // overshoot in a run shorter than a bucket
+        let accuracy = super::rate_accuracy(
+            &[0.0, 0.1, 0.2, 0.3, 0.4, 0.5],
+            8.0,
+            1.0,
+            PartialBucket::Drop,
+        )
+        .unwrap();
+        assert_eq!(accuracy.achieved_rps, vec![12.0]);
+        assert!((accuracy.max_deviation - 0.5).abs() < 1e-9);
+
+        assert!(super::rate_accuracy(&[], 10.0, 1.0, PartialBucket::Drop).is_none());
+        assert!(super::rate_accuracy(&[0.0, 1.0], 0.0, 1.0, PartialBucket::Drop).is_none());
+    }
+
+    #[test]
+    fn partial_rate_bucket() {
+        // 10 requests/s for 2 secs, then 3 requests within the first half of the 3rd sec
+        let timestamps: Vec<f64> = (0..20)
+            .map(|i| i as f64 * 0.1)
+            .chain([2.0, 2.25, 2.5])
+            .collect();
+        let achieved_rps = |partial_bucket| {
+            super::rate_accuracy(&timestamps, 10.0, 1.0, partial_bucket)
+                .unwrap()
+                .achieved_rps
+        };
+
+        assert_eq!(achieved_rps(PartialBucket::Drop), vec![10.0, 10.0]);
+        assert_eq!(
+            achieved_rps(PartialBucket::Normalize),
+            vec![10.0, 10.0, 6.0]
+        );
+        assert_eq!(achieved_rps(PartialBucket::Keep), vec![10.0, 10.0, 3.0]);
+
+        // a run ending at a bucket boundary has no partial bucket
+        let timestamps: Vec<f64> = (0..=20).map(|i| i as f64 * 0.1).collect();
+        let accuracy = super::rate_accuracy(&timestamps, 10.0, 1.0, PartialBucket::Keep).unwrap();
+        assert_eq!(accuracy.achieved_rps, vec![10.0, 10.0]);
+    }
+
+    #[test]
+    fn run_variance() {
+        let runs = vec![
+            vec![1.0, 2.0, 3.0],
+            vec![4.0, 5.0, 6.0],
+            vec![7.0, 8.0, 9.0],
+            vec![],
+        ];
+        let variance = super::run_variance(&runs).unwrap();
+        assert_eq!(variance.run_means, vec![2.0, 5.0, 8.0]);
+        assert!((variance.between_run_variance - 9.0).abs() < 1e-9);
+        assert!((variance.within_run_variance - 1.0).abs() < 1e-9);
+
+        assert!(super::run_variance(&runs[..1]).is_none());
+    }
+
+    #[test]
+    fn anderson_darling() {
+        let n = 200;
+        let levels = (0..n).map(|i| (i as f64 + 0.5) / n as f64);
+
+        let normal = Normal::new(10.0, 2.0).unwrap();
+        let normal_samples: Vec<f64> = levels.clone().map(|p| normal.inverse_cdf(p)).collect();
+        let test = super::anderson_darling(&normal_samples).unwrap();
+        assert!(test.is_normal(0.05), "{}", test);
+
+        // exponentially distributed (e.g. latencies with a long tail)
+        let exponential_samples: Vec<f64> = levels.map(|p| -(1.0 - p).ln()).collect();
+        let test = super::anderson_darling(&exponential_samples).unwrap();
+        assert!(!test.is_normal(0.05), "{}", test);
+        assert!(test.p_value < 0.001);
+
+        assert!(super::anderson_darling(&[1.0; 20]).is_none());
+        assert!(super::anderson_darling(&normal_samples[..5]).is_none());
+    }
+
     #[test]
     fn requests_per_sec() {
         let mean = 0.0;
@@ -433,6 +941,30 @@ mod tests {
         assert_eq!(u_p_value.unwrap(), 0.009109785650170843);
     }
 
+    #[test]
+    fn target_test() {
+        let np = NormalParams {
+            mean: 80.0,
+            std: 20.0,
+            n_samples: 100,
+        };
+
+        let outcome = super::TargetTester::new(&np, 100.0).test(0.05).unwrap();
+        assert!(matches!(outcome, TestOutcome::Improved { p_value } if p_value < 1e-6));
+
+        let outcome = super::TargetTester::new(&np, 60.0).test(0.05).unwrap();
+        assert!(matches!(outcome, TestOutcome::Regressed { p_value } if p_value < 1e-6));
+
+        // within a std error of the target
+        let outcome = super::TargetTester::new(&np, 81.0).test(0.05).unwrap();
+        assert_eq!(outcome, TestOutcome::Inconclusive);
+
+        let np_constant = NormalParams { std: 0.0, ..np };
+        assert!(super::TargetTester::new(&np_constant, 100.0)
+            .test(0.05)
+            .is_none());
+    }
+
     #[test]
     fn analytic_test() {
         let np_base = NormalParams {
@@ -469,6 +1001,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_outcome_display() {
+        assert_eq!(
+            TestOutcome::Regressed { p_value: 0.008 }.to_string(),
+            "regressed (p-value 0.008)"
+        );
+        assert_eq!(
+            TestOutcome::Improved { p_value: 0.013 }.to_string(),
+            "improved (p-value 0.013)"
+        );
+        assert_eq!(
+            TestOutcome::Inconclusive.to_string(),
+            "inconclusive (no significant change)"
+        );
+    }
+
     #[test]
     fn bootstrap_sample_means() {
         let samples = [10.0, 11.0, 12.0, 10.5, 17.0, 33.0, 42.0, 2.0, 15.0, 14.0];
@@ -1,21 +1,28 @@
 use super::{
-    confidence_interval, normal_qq, percentile, requests_per_sec, standard_deviation,
-    stats::NormalParams, sum, BootstrapSampler,
+    anderson_darling, confidence_interval, normal_qq, percentile, rate_accuracy, requests_per_sec,
+    standard_deviation, stats::NormalParams, sum, BootstrapSampler, NormalityTest, PartialBucket,
+    RateAccuracy, TargetTester, TestOutcome,
 };
 use crate::{
     config::DurationScale,
-    sampling::{RequestResult, SampleCollector, SampleResult, StatusCode},
-    ThreadIdx,
+    sampling::{FailureKind, RequestResult, SampleCollector, SampleResult},
+    BenchClientConfig, BurlError, BurlResult, ThreadIdx,
 };
 use log::warn;
-use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fmt::Display};
+use serde::{Deserialize, Serialize, Serializer};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::time::Instant;
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct ThreadStats {
     #[serde(skip_deserializing)]
     #[serde(skip_serializing)] // serialize or not?
-    errors: HashMap<StatusCode, i32>,
+    errors: HashMap<FailureKind, i32>,
     #[serde(skip_deserializing)]
     #[serde(skip_serializing)] // serialize or not?
     pub durations: Vec<f64>,
@@ -33,9 +40,15 @@ pub struct ThreadStats {
 
 impl From<&SampleCollector> for ThreadStats {
     fn from(samples: &SampleCollector) -> Self {
-        let mut durations = Vec::with_capacity(samples.n_runs);
+        Self::from_results(samples.results.iter(), samples.n_runs)
+    }
+}
+
+impl ThreadStats {
+    fn from_results<'a>(results: impl Iterator<Item = &'a RequestResult>, n_runs: usize) -> Self {
+        let mut durations = Vec::with_capacity(n_runs);
         let mut errors = HashMap::new();
-        let mut sample_results = Vec::with_capacity(samples.n_runs);
+        let mut sample_results = Vec::with_capacity(n_runs);
 
         let mut total_bytes = 0;
         let mut n_ok = 0;
@@ -43,7 +56,7 @@ impl From<&SampleCollector> for ThreadStats {
         let mut max = 0.0_f64;
         let mut min = f64::MAX;
 
-        for result in samples.results.iter() {
+        for result in results {
             match result {
                 RequestResult::Ok(sample) => {
                     sample_results.push(sample);
@@ -55,9 +68,9 @@ impl From<&SampleCollector> for ThreadStats {
                     }
                     n_ok += 1;
                 }
-                RequestResult::Failed(status_code) => {
+                RequestResult::Failed(failure, ..) => {
                     errors
-                        .entry(*status_code)
+                        .entry(*failure)
                         .and_modify(|count| *count += 1)
                         .or_insert(1);
                     n_errors += 1;
@@ -99,6 +112,9 @@ impl From<&SampleCollector> for ThreadStats {
 pub struct StatsProcessor {
     pub scale: DurationScale,
     sample_collections: Vec<SampleCollector>,
+    discard_window: Option<Duration>,
+    target_rps: Option<f64>,
+    warnings: Vec<String>,
 }
 
 impl StatsProcessor {
@@ -106,9 +122,127 @@ impl StatsProcessor {
         Self {
             scale: duration_scale,
             sample_collections: samples_by_thread,
+            discard_window: None,
+            target_rps: None,
+            warnings: Vec::new(),
         }
     }
 
+    /// Restores the processor from dumped sample results (see `sample_results_by_thread`) of a run
+    /// with the given config, e.g. to regenerate its report. NOTE: failures are not part of the dump.
+    pub fn from_sample_results(
+        config: &BenchClientConfig,
+        sample_results_by_thread: HashMap<ThreadIdx, Vec<SampleResult>>,
+    ) -> Self {
+        let scale = config.duration_scale();
+        let config = Arc::new(config.clone());
+        let timer = Arc::new(Instant::now());
+
+        let mut sample_collections: Vec<SampleCollector> = sample_results_by_thread
+            .into_iter()
+            .map(|(thread_idx, sample_results)| {
+                let mut samples = SampleCollector::new(timer.clone(), thread_idx, config.clone());
+                samples.results = sample_results
+                    .into_iter()
+                    .map(|mut sample| {
+                        sample.restore_durations(&scale);
+                        RequestResult::Ok(sample)
+                    })
+                    .collect();
+                samples
+            })
+            .collect();
+        sample_collections.sort_by_key(|samples| samples.thread_idx);
+
+        Self::new(scale, sample_collections)
+            .with_discard_window(config.discard_window())
+            .with_target_rps(config.target_rps())
+    }
+
+    /// Merges the dumped samples of a previous run with the same config before the samples
+    /// of this one, e.g. to continue an interrupted run. The samples of this run are shifted
+    /// to start after the previous ones ended, so that the time series are continued.
+    pub fn with_prior_samples(
+        mut self,
+        config: &BenchClientConfig,
+        prior_sample_results_by_thread: HashMap<ThreadIdx, Vec<SampleResult>>,
+    ) -> Self {
+        let offset = prior_sample_results_by_thread
+            .values()
+            .flatten()
+            .map(|sample| sample.measurement_end)
+            .fold(0.0, f64::max);
+        let offset_duration = Duration::from_secs_f64(offset / self.scale.scale() as f64);
+
+        let mut merged =
+            Self::from_sample_results(config, prior_sample_results_by_thread).sample_collections;
+        for mut samples in std::mem::take(&mut self.sample_collections) {
+            for result in samples.results.iter_mut() {
+                if let RequestResult::Ok(sample) = result {
+                    sample.measurement_start += offset;
+                    sample.measurement_end += offset;
+                    sample.duration_since_start += offset_duration;
+                    sample.duration_request_end += offset_duration;
+                }
+            }
+            match merged
+                .iter_mut()
+                .find(|prior| prior.thread_idx == samples.thread_idx)
+            {
+                Some(prior) => prior.results.append(&mut samples.results),
+                None => merged.push(samples),
+            }
+        }
+        merged.sort_by_key(|samples| samples.thread_idx);
+
+        self.sample_collections = merged;
+        self
+    }
+
+    /// The collected samples, e.g. to combine them with the ones of other runs.
+    pub(crate) fn into_sample_collections(self) -> Vec<SampleCollector> {
+        self.sample_collections
+    }
+
+    /// Excludes the samples started within the window (since the start of the measurement) from the stats.
+    pub fn with_discard_window(mut self, discard_window: Option<Duration>) -> Self {
+        self.discard_window = discard_window;
+        self
+    }
+
+    /// The rate the run was paced to, reported next to the effective one in the summary.
+    pub fn with_target_rps(mut self, target_rps: Option<f64>) -> Self {
+        self.target_rps = target_rps;
+        self
+    }
+
+    /// The warnings logged during the run, e.g. a reached max run time, to be reported.
+    pub fn with_warnings(mut self, warnings: Vec<String>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    fn is_discarded(&self, sample: &SampleResult) -> bool {
+        self.discard_window
+            .map(|window| sample.duration_since_start < window)
+            .unwrap_or(false)
+    }
+
+    /// The results which enter the stats; failures have no timestamp and are always kept.
+    fn counted_results<'a>(
+        &'a self,
+        samples: &'a SampleCollector,
+    ) -> impl Iterator<Item = &'a RequestResult> {
+        samples.results.iter().filter(|result| match result {
+            RequestResult::Ok(sample) => !self.is_discarded(sample),
+            RequestResult::Failed(..) => true,
+        })
+    }
+
     pub fn sample_results_by_thread(&self) -> HashMap<ThreadIdx, Vec<SampleResult>> {
         let sample_results_by_thread = self
             .sample_collections
@@ -118,6 +252,10 @@ impl StatsProcessor {
                     .results
                     .iter()
                     .flat_map(|sr| sr.as_result().cloned())
+                    .map(|mut sr| {
+                        sr.discarded = self.is_discarded(&sr);
+                        sr
+                    })
                     .collect();
                 (samples.thread_idx, sample_results)
             })
@@ -125,44 +263,180 @@ impl StatsProcessor {
         sample_results_by_thread
     }
 
+    /// The number of failed requests over all threads.
+    pub fn n_failed(&self) -> usize {
+        self.sample_collections
+            .iter()
+            .flat_map(|samples| samples.results.iter())
+            .filter(|result| matches!(result, RequestResult::Failed(..)))
+            .count()
+    }
+
+    /// The number of retries after a `Retry-After` over all threads and the time waited for them.
+    pub fn throttling(&self) -> (usize, Duration) {
+        self.sample_collections
+            .iter()
+            .fold((0, Duration::ZERO), |(n, duration), samples| {
+                (
+                    n + samples.n_throttled,
+                    duration + samples.throttled_duration,
+                )
+            })
+    }
+
+    /// The number of failed requests over all threads by the kind of failure.
+    pub fn failures_by_kind(&self) -> HashMap<FailureKind, usize> {
+        let mut failures = HashMap::new();
+        for result in self
+            .sample_collections
+            .iter()
+            .flat_map(|samples| samples.results.iter())
+        {
+            if let RequestResult::Failed(kind, ..) = result {
+                *failures.entry(*kind).or_insert(0) += 1;
+            }
+        }
+        failures
+    }
+
+    /// Compares the rate of successful samples over all threads to the `target_rps`, per second of the run.
+    /// The last, incomplete second is handled according to `partial_bucket`.
+    pub fn rate_accuracy(
+        &self,
+        target_rps: f64,
+        partial_bucket: PartialBucket,
+    ) -> Option<RateAccuracy> {
+        let timestamps: Vec<f64> = self
+            .sample_collections
+            .iter()
+            .flat_map(|samples| samples.results.iter())
+            .flat_map(|result| result.as_result())
+            .map(|sample| sample.duration_since_start.as_secs_f64())
+            .collect();
+        rate_accuracy(&timestamps, target_rps, 1.0, partial_bucket)
+    }
+
+    /// The successful requests per second over all threads, from the first start until the
+    /// last end of the counted samples; `None` without samples.
+    pub fn overall_rps(&self) -> Option<f64> {
+        let samples: Vec<&SampleResult> = self
+            .sample_collections
+            .iter()
+            .flat_map(|samples| self.counted_results(samples))
+            .flat_map(|result| result.as_result())
+            .collect();
+        let start = samples
+            .iter()
+            .map(|sample| sample.duration_since_start)
+            .min()?;
+        let end = samples
+            .iter()
+            .map(|sample| sample.duration_request_end)
+            .max()?;
+
+        let span = (end - start).as_secs_f64();
+        (span > 0.0).then(|| samples.len() as f64 / span)
+    }
+
+    /// Fails if the `overall_rps` is below the `min_rps`, e.g. to fail a run in CI.
+    pub fn check_min_rps(&self, min_rps: f64) -> BurlResult<f64> {
+        let achieved_rps = self.overall_rps().unwrap_or(0.0);
+        match achieved_rps < min_rps {
+            true => Err(BurlError::ThroughputTooLow {
+                achieved_rps,
+                min_rps,
+            }),
+            false => Ok(achieved_rps),
+        }
+    }
+
     /// Collect the sample results from the threads' samples.
     pub fn stats_summary(&self) -> Option<StatsSummary> {
         let mut durations = Vec::new();
         let mut stats_by_thread = HashMap::new();
         let mut total_bytes = 0;
         let mut n_errors = 0;
-        let mut errors: HashMap<StatusCode, i32> = HashMap::new();
+        let mut errors: HashMap<FailureKind, i32> = HashMap::new();
+        let mut results_by_label: HashMap<&String, Vec<&RequestResult>> = HashMap::new();
+        let mut redirect_hops: HashMap<usize, usize> = HashMap::new();
+        let mut failure_durations = Vec::new();
+        let mut total_upload_bytes = 0;
+        let mut total_upload_duration = 0.0;
 
         for samples in self.sample_collections.iter() {
             let idx = samples.thread_idx;
-            let thread_stats = ThreadStats::from(samples);
+            let thread_stats =
+                ThreadStats::from_results(self.counted_results(samples), samples.n_runs);
 
             n_errors += thread_stats.n_errors;
             total_bytes += thread_stats.total_bytes;
 
             durations.extend(thread_stats.durations.clone());
 
-            for (status_code, n_errors) in thread_stats.errors.iter() {
+            for (failure, n_errors) in thread_stats.errors.iter() {
                 errors
-                    .entry(*status_code)
+                    .entry(*failure)
                     .and_modify(|count| *count += *n_errors)
                     .or_insert(*n_errors);
             }
 
             stats_by_thread.insert(idx, thread_stats);
+
+            for result in self.counted_results(samples) {
+                if let Some(label) = result.label() {
+                    results_by_label.entry(label).or_default().push(result);
+                }
+                if let Some(sample) = result.as_result() {
+                    *redirect_hops.entry(sample.redirects).or_default() += 1;
+                    total_upload_bytes += sample.upload_bytes.unwrap_or_default();
+                    total_upload_duration += sample.upload_duration.unwrap_or_default();
+                }
+                if let RequestResult::Failed(_, _, duration) = result {
+                    failure_durations.push(*duration);
+                }
+            }
         }
 
-        StatsSummary::calculate(
+        let mut summary = StatsSummary::calculate(
             self.scale.clone(),
             n_errors,
             total_bytes,
             durations,
             errors,
             stats_by_thread,
-        )
+        )?;
+
+        summary.stats_by_label = results_by_label
+            .into_iter()
+            .map(|(label, results)| {
+                let n = results.len();
+                (
+                    label.clone(),
+                    ThreadStats::from_results(results.into_iter(), n),
+                )
+            })
+            .collect();
+
+        summary.redirect_hops = redirect_hops;
+        failure_durations.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        summary.failure_durations = failure_durations;
+        summary.total_upload_bytes = total_upload_bytes;
+        summary.total_upload_duration = total_upload_duration;
+        summary.target_rps = self.target_rps;
+        summary.effective_rps = self.overall_rps();
+
+        Some(summary)
     }
 }
 
+/// Serializes the map ordered by its keys, so that dumps of the same stats are identical.
+fn ordered_map<K: Ord + Serialize, V: Serialize, S: Serializer>(
+    map: &HashMap<K, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StatsSummary {
     pub durations: Vec<f64>,
@@ -170,6 +444,12 @@ pub struct StatsSummary {
     pub scale: DurationScale,
     pub total_duration: f64,
     pub total_bytes: u64,
+    /// The bytes of the streamed request bodies, see `upload_file_ref`.
+    #[serde(default)]
+    pub total_upload_bytes: u64,
+    /// The (summed) time of streaming the request bodies, see `SampleResult::upload_duration`.
+    #[serde(default)]
+    pub total_upload_duration: f64,
     pub mean_rps: Option<f64>,
 
     pub mean: f64,
@@ -179,70 +459,280 @@ pub struct StatsSummary {
     pub min: f64,
     pub max: f64,
     pub std: Option<f64>,
+    /// The ratio of the 99th percentile to the median, a quick indicator of tail problems.
+    #[serde(default)]
+    pub tail_amplification: Option<f64>,
+    /// The ratio of the max to the median.
+    #[serde(default)]
+    pub max_median_ratio: Option<f64>,
     pub n_ok: usize,
     pub n_errors: usize,
     // pub qq_percentiles: Vec<(f64, f64)>,
+    #[serde(serialize_with = "ordered_map")]
     pub stats_by_thread: HashMap<ThreadIdx, ThreadStats>,
+    /// The stats of the requests by their label, e.g. the method in a mix of requests.
+    #[serde(default, serialize_with = "ordered_map")]
+    pub stats_by_label: HashMap<String, ThreadStats>,
+    /// The number of successful requests by the number of redirects they followed.
+    #[serde(default, serialize_with = "ordered_map")]
+    pub redirect_hops: HashMap<usize, usize>,
+    /// The (sorted) durations of the failed requests, e.g. to tell slow 5xx from fast 4xx.
+    #[serde(default)]
+    pub failure_durations: Vec<f64>,
+    /// The rate of a paced run (see `target_rps`) and the effective rate over all threads,
+    /// which falls short of it if the server cannot keep up.
+    #[serde(default)]
+    pub target_rps: Option<f64>,
+    #[serde(default)]
+    pub effective_rps: Option<f64>,
 
     #[serde(skip_serializing)]
     #[serde(skip_deserializing)]
-    pub errors: HashMap<StatusCode, i32>,
+    pub errors: HashMap<FailureKind, i32>,
     // TODO: provide overview of errors - tbd if actually interestering or a corner case
     // TODO: outliers
 }
 
 const N_PERCENTILES: usize = 20;
+/// The percentiles of the durations of the failed requests in the summary.
+const FAILURE_PERCENTILE_LEVELS: [f64; 2] = [0.5, 0.95];
+/// The default minimal number of samples for the statistics based on a normal distribution.
+pub const MIN_SAMPLES: usize = 2;
+/// The minimal number of samples for the histogram in the summary.
+const N_HISTOGRAM: usize = 50;
+const HISTOGRAM_BINS: usize = 10;
+/// The width (in characters) of the largest bar of the text histogram.
+const HISTOGRAM_WIDTH: usize = 40;
+/// Partial blocks by eighths of a character.
+const PARTIAL_BLOCKS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+/// The significance level at which the durations are considered not normal.
+pub const NORMALITY_ALPHA: f64 = 0.05;
+/// Bounds for the number of points of the QQ curve, keeping the plot readable and fast.
+const QQ_POINTS_FLOOR: usize = 10;
+const QQ_POINTS_CAP: usize = 500;
+
+/// Formats the value with the given number of decimals, or with full precision.
+pub fn format_value(value: f64, precision: Option<usize>) -> String {
+    match precision {
+        Some(decimals) => format!("{:.*}", decimals, value),
+        None => value.to_string(),
+    }
+}
+
+/// The binary units of the human-readable sizes, see `format_bytes`.
+const BYTE_UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+/// Formats a number of bytes in binary units, e.g. `1.00 MiB`, with the given number of
+/// decimals (2 by default); plain bytes are not rounded.
+pub fn format_bytes(bytes: f64, precision: Option<usize>) -> String {
+    let mut value = bytes;
+    let mut unit = 0;
+    while value.abs() >= 1024.0 && unit < BYTE_UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{} B", value),
+        _ => format!("{:.*} {}", precision.unwrap_or(2), value, BYTE_UNITS[unit]),
+    }
+}
+
+/// The change of the value relative to the baseline, e.g. ` (+5.2% vs baseline)`;
+/// empty if the change is undefined, e.g. for a baseline of zero.
+pub fn format_delta(value: f64, baseline: f64) -> String {
+    let delta = (value - baseline) / baseline * 100.0;
+    match delta.is_finite() {
+        true => format!(" ({:+.1}% vs baseline)", delta),
+        false => String::new(),
+    }
+}
+
+/// Displays the summary with the values rounded to a number of decimals, see `StatsSummary::display`.
+pub struct SummaryDisplay<'a> {
+    stats: &'a StatsSummary,
+    precision: Option<usize>,
+    baseline: Option<&'a StatsSummary>,
+}
+
+impl<'a> SummaryDisplay<'a> {
+    /// Displays the change of each duration relative to the baseline next to its value,
+    /// unless the baseline is in a different scale.
+    pub fn with_baseline(mut self, baseline: Option<&'a StatsSummary>) -> Self {
+        self.baseline = baseline.filter(|baseline| baseline.scale == self.stats.scale);
+        self
+    }
+}
 
 impl Display for StatsSummary {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.display(None).fmt(f)
+    }
+}
+
+impl<'a> Display for SummaryDisplay<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stats = self.stats;
+        let value = |v: f64| format_value(v, self.precision);
+        // the value followed by its change relative to the baseline, if any
+        let delta = |v: f64, baseline_value: fn(&StatsSummary) -> Option<f64>| {
+            let delta = self
+                .baseline
+                .and_then(baseline_value)
+                .map(|bl_v| format_delta(v, bl_v))
+                .unwrap_or_default();
+            format!("{}{}", value(v), delta)
+        };
+
         writeln!(f)?;
         writeln!(
             f,
             "_______SUMMARY_[in {}s, on {} threads]___________",
-            &self.scale,
-            &self.stats_by_thread.len()
+            &stats.scale,
+            &stats.stats_by_thread.len()
         )?;
-        writeln!(f, "Total bytes     | {}", self.total_bytes)?;
-        writeln!(f, "Number ok       | {}", self.n_ok)?;
-        writeln!(f, "Number failed   | {}", self.n_errors)?;
-        if let Some(rps) = self.mean_rps {
-            writeln!(f, "Mean requests/s | {}", rps)?;
+        writeln!(
+            f,
+            "Total bytes     | {}",
+            format_bytes(stats.total_bytes as f64, self.precision)
+        )?;
+        if let Some(throughput) = stats.bytes_per_sec() {
+            writeln!(
+                f,
+                "Throughput      | {}/s",
+                format_bytes(throughput, self.precision)
+            )?;
+        }
+        if let Some(throughput) = stats.upload_bytes_per_sec() {
+            writeln!(
+                f,
+                "Upload          | {} ({}/s)",
+                format_bytes(stats.total_upload_bytes as f64, self.precision),
+                format_bytes(throughput, self.precision)
+            )?;
+        }
+        writeln!(f, "Number ok       | {}", stats.n_ok)?;
+        writeln!(f, "Number failed   | {}", stats.n_errors)?;
+        if let Some(rps) = stats.mean_rps {
+            writeln!(f, "Mean requests/s | {}", delta(rps, |bl| bl.mean_rps))?;
+        }
+        if let (Some(target_rps), Some(rps)) = (stats.target_rps, stats.effective_rps) {
+            writeln!(
+                f,
+                "Effective rps   | {} (target {})",
+                format_value(rps, self.precision),
+                target_rps
+            )?;
         }
 
         writeln!(f, "_______DURATIONS_______________________________")?;
-        writeln!(f, "Total        | {}", self.total_duration)?;
-        writeln!(f, "Mean         | {}", self.mean)?;
-        // writeln!(f, "Requests per sec | {}", self.mean)?;
+        writeln!(
+            f,
+            "Total        | {}",
+            delta(stats.total_duration, |bl| Some(bl.total_duration))
+        )?;
+        writeln!(
+            f,
+            "Mean         | {}",
+            delta(stats.mean, |bl| Some(bl.mean))
+        )?;
+        // writeln!(f, "Requests per sec | {}", stats.mean)?;
 
-        if let Some(std) = self.std {
-            writeln!(f, "StdDev       | {}", std)?;
+        if let Some(std) = stats.std {
+            writeln!(f, "StdDev       | {}", delta(std, |bl| bl.std))?;
+        }
+        writeln!(f, "Min          | {}", delta(stats.min, |bl| Some(bl.min)))?;
+        writeln!(
+            f,
+            "Quartile 1st | {}",
+            delta(stats.quartile_fst, |bl| Some(bl.quartile_fst))
+        )?;
+        writeln!(
+            f,
+            "Median       | {}",
+            delta(stats.median, |bl| Some(bl.median))
+        )?;
+        writeln!(
+            f,
+            "Quartile 3rd | {}",
+            delta(stats.quartile_trd, |bl| Some(bl.quartile_trd))
+        )?;
+        writeln!(f, "Max          | {}", delta(stats.max, |bl| Some(bl.max)))?;
+        if let Some(ratio) = stats.tail_amplification {
+            writeln!(f, "P99 / Median | {}", value(ratio))?;
+        }
+        if let Some(ratio) = stats.max_median_ratio {
+            writeln!(f, "Max / Median | {}", value(ratio))?;
         }
-        writeln!(f, "Min          | {}", self.min)?;
-        writeln!(f, "Quartile 1st | {}", self.quartile_fst)?;
-        writeln!(f, "Median       | {}", self.median)?;
-        writeln!(f, "Quartile 3rd | {}", self.quartile_trd)?;
-        writeln!(f, "Max          | {}", self.max)?;
 
-        if self.n_ok >= N_PERCENTILES {
+        if stats.n_ok >= N_PERCENTILES {
             writeln!(f, "_______PERCENTILES_____________________________")?;
-            let display_percentiles = self.percentiles(&PERCENTILE_LEVELS);
-            for (level, percentile) in display_percentiles.iter() {
-                writeln!(f, "{}%    {}", level, percentile)?;
+            let display_percentiles = stats.percentiles(&PERCENTILE_LEVELS);
+            let baseline_percentiles = self
+                .baseline
+                .filter(|baseline| !baseline.durations.is_empty())
+                .map(|baseline| baseline.percentiles(&PERCENTILE_LEVELS));
+            for (idx, (level, percentile)) in display_percentiles.iter().enumerate() {
+                let percentile_delta = baseline_percentiles
+                    .as_ref()
+                    .map(|bl_percentiles| format_delta(*percentile, bl_percentiles[idx].1))
+                    .unwrap_or_default();
+                writeln!(
+                    f,
+                    "{}%    {}{}",
+                    level,
+                    value(*percentile),
+                    percentile_delta
+                )?;
             }
         }
 
-        if self.stats_by_thread.len() > 1 {
-            let format_option = |option_v: Option<f64>| {
-                if let Some(v) = option_v {
-                    v.round().to_string()
-                } else {
-                    "".to_string()
-                }
-            };
+        let failure_percentiles = stats.failure_percentiles(&FAILURE_PERCENTILE_LEVELS);
+        if !failure_percentiles.is_empty() {
+            writeln!(f, "_______FAILURE DURATIONS_______________________")?;
+            for (level, percentile) in failure_percentiles.iter() {
+                writeln!(f, "{}%    {}", level, value(*percentile))?;
+            }
+        }
+
+        if stats.n_ok >= N_HISTOGRAM {
+            writeln!(f, "_______HISTOGRAM_______________________________")?;
+            for line in stats.text_histogram(HISTOGRAM_BINS, HISTOGRAM_WIDTH, self.precision) {
+                writeln!(f, "{}", line)?;
+            }
+            if let Some(modal_bin) = stats.modal_bin(HISTOGRAM_BINS) {
+                writeln!(
+                    f,
+                    "Mode         | {} - {} ({} samples)",
+                    value(modal_bin.lower),
+                    value(modal_bin.upper),
+                    modal_bin.count
+                )?;
+            }
+        }
+
+        if let Some(normality) = stats.normality_test() {
+            writeln!(f, "_______NORMALITY_______________________________")?;
+            writeln!(f, "{}", normality)?;
+            if !normality.is_normal(NORMALITY_ALPHA) {
+                writeln!(
+                    f,
+                    "NOTE: the durations are not normal, prefer the permutation test over the analytical one"
+                )?;
+            }
+        }
+
+        // NOTE: without a precision, the values are rounded to integers for the sake of compactness
+        let format_option = |option_v: Option<f64>| match (option_v, self.precision) {
+            (Some(v), Some(_)) => value(v),
+            (Some(v), None) => v.round().to_string(),
+            (None, _) => "".to_string(),
+        };
 
+        if stats.stats_by_thread.len() > 1 {
             writeln!(f, "_______THREADS_________________________________")?;
             writeln!(f, "[ThreadIdx : num ok] total | mean | std | min | max")?;
-            for (thread_idx, thread_stats) in self.stats_by_thread.iter() {
+            for (thread_idx, thread_stats) in stats.stats_by_thread.iter() {
                 writeln!(
                     f,
                     "[{}: {}] {} | {} | {} | {} | {}",
@@ -257,17 +747,61 @@ impl Display for StatsSummary {
             }
         }
 
+        if !stats.stats_by_label.is_empty() {
+            let mut labels: Vec<&String> = stats.stats_by_label.keys().collect();
+            labels.sort();
+
+            writeln!(f, "_______REQUESTS________________________________")?;
+            writeln!(
+                f,
+                "[Request : num ok, num failed] total | mean | std | min | max"
+            )?;
+            for label in labels {
+                let label_stats = &stats.stats_by_label[label];
+                writeln!(
+                    f,
+                    "[{}: {}, {}] {} | {} | {} | {} | {}",
+                    label,
+                    label_stats.n_ok,
+                    label_stats.n_errors,
+                    format_option(label_stats.total_duration),
+                    format_option(label_stats.mean),
+                    format_option(label_stats.std),
+                    format_option(label_stats.min),
+                    format_option(label_stats.max)
+                )?;
+            }
+        }
+
+        if stats.redirect_hops.keys().any(|hops| *hops > 0) {
+            let mut redirect_hops: Vec<(&usize, &usize)> = stats.redirect_hops.iter().collect();
+            redirect_hops.sort();
+
+            writeln!(f, "_______REDIRECTS_______________________________")?;
+            writeln!(f, "[Redirects : num ok]")?;
+            for (hops, n_ok) in redirect_hops {
+                writeln!(f, "[{}: {}]", hops, n_ok)?;
+            }
+        }
+
         writeln!(f, "_______________________________________________")
     }
 }
 
-impl From<&StatsSummary> for NormalParams {
-    fn from(stats: &StatsSummary) -> Self {
-        NormalParams {
-            mean: stats.mean,
-            std: stats.std.unwrap(), // TODO: handle
-            n_samples: stats.n_ok,
-        }
+impl StatsSummary {
+    /// The parameters of the normal distribution with the sample mean and std;
+    /// `None` if the std is not available, e.g. for a single sample.
+    pub fn normal_params(&self) -> Option<NormalParams> {
+        Some(NormalParams {
+            mean: self.mean,
+            std: self.std?,
+            n_samples: self.n_ok,
+        })
+    }
+
+    /// Tests the mean against a fixed `target` (in the duration scale), see `TargetTester`.
+    pub fn target_test(&self, target: f64, alpha: f64) -> Option<TestOutcome> {
+        TargetTester::new(&self.normal_params()?, target).test(alpha)
     }
 }
 
@@ -275,22 +809,132 @@ static PERCENTILE_LEVELS: [f64; 13] = [
     0.01, 0.05, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 0.95, 0.99,
 ];
 
+/// A bin `[lower, upper)` of the durations histogram; the last bin includes its upper bound.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramBin {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+}
+
 impl StatsSummary {
-    pub fn normal_qq_curve(&self) -> Vec<(f64, f64)> {
-        if let Some(std) = self.std {
-            let np = NormalParams {
-                mean: self.mean,
-                std,
-                n_samples: self.n_ok,
+    /// The histogram of the durations with `n_bins` bins of equal width between min and max.
+    pub fn histogram_bins(&self, n_bins: usize) -> Vec<HistogramBin> {
+        let n_bins = n_bins.max(1);
+        let width = (self.max - self.min) / n_bins as f64;
+
+        let mut bins: Vec<HistogramBin> = (0..n_bins)
+            .map(|idx| HistogramBin {
+                lower: self.min + idx as f64 * width,
+                upper: self.min + (idx + 1) as f64 * width,
+                count: 0,
+            })
+            .collect();
+        for duration in self.durations.iter() {
+            let idx = match width > 0.0 {
+                true => ((duration - self.min) / width) as usize,
+                false => 0,
             };
+            bins[idx.min(n_bins - 1)].count += 1;
+        }
+        bins
+    }
 
-            normal_qq(&self.qq_percentiles(), &np)
+    /// The most frequent bin of the histogram with `n_bins` bins (the first one on ties),
+    /// e.g. for durations which are discretized by the server.
+    pub fn modal_bin(&self, n_bins: usize) -> Option<HistogramBin> {
+        self.histogram_bins(n_bins)
+            .into_iter()
+            .reduce(|modal, bin| if bin.count > modal.count { bin } else { modal })
+    }
+
+    /// The histogram as text, with a bar of block characters (scaled to the largest count) per bin.
+    pub fn text_histogram(
+        &self,
+        n_bins: usize,
+        width: usize,
+        precision: Option<usize>,
+    ) -> Vec<String> {
+        let bins = self.histogram_bins(n_bins);
+        let max_count = bins.iter().map(|bin| bin.count).max().unwrap_or(0).max(1);
+
+        bins.iter()
+            .map(|bin| {
+                let eighths = (8 * width * bin.count + max_count / 2) / max_count;
+                let bar = format!("{}{}", "█".repeat(eighths / 8), PARTIAL_BLOCKS[eighths % 8]);
+                format!(
+                    "{} - {} | {} {}",
+                    format_value(bin.lower, precision),
+                    format_value(bin.upper, precision),
+                    bar,
+                    bin.count
+                )
+            })
+            .collect()
+    }
+
+    /// The received bytes per second of (summed) request time, in line with `mean_rps`.
+    pub fn bytes_per_sec(&self) -> Option<f64> {
+        let secs = self.total_duration / self.scale.factor(&DurationScale::Secs);
+        match secs > 0.0 {
+            true => Some(self.total_bytes as f64 / secs),
+            false => None,
+        }
+    }
+
+    /// The uploaded bytes per second of (summed) time of streaming the request bodies,
+    /// see `upload_file_ref`; `None` without uploads.
+    pub fn upload_bytes_per_sec(&self) -> Option<f64> {
+        let secs = self.total_upload_duration / self.scale.factor(&DurationScale::Secs);
+        match secs > 0.0 && self.total_upload_bytes > 0 {
+            true => Some(self.total_upload_bytes as f64 / secs),
+            false => None,
+        }
+    }
+
+    /// Displays the summary with the values rounded to `precision` decimals (or in full precision).
+    pub fn display(&self, precision: Option<usize>) -> SummaryDisplay<'_> {
+        SummaryDisplay {
+            stats: self,
+            precision,
+            baseline: None,
+        }
+    }
+
+    /// The QQ curve against a normal distribution with the sample mean and std.
+    /// If `n_points` is not set, a tenth of the number of samples is used; in any case
+    /// the number of points is bounded by `QQ_POINTS_FLOOR`, `QQ_POINTS_CAP` and the number of samples.
+    pub fn normal_qq_curve(&self, n_points: Option<usize>) -> Vec<(f64, f64)> {
+        if let Some(np) = self.normal_params() {
+            normal_qq(&self.qq_percentiles(n_points), &np)
         } else {
             Vec::with_capacity(0)
         }
     }
 
-    fn percentiles(&self, levels: &[f64]) -> Vec<(f64, f64)> {
+    /// Tests whether the durations are normally distributed, as assumed by the analytic test.
+    pub fn normality_test(&self) -> Option<NormalityTest> {
+        anderson_darling(&self.durations)
+    }
+
+    /// The percentiles (by level in %) of the durations of the failed requests, computed apart
+    /// from the successful ones; empty without failures.
+    pub fn failure_percentiles(&self, levels: &[f64]) -> Vec<(f64, f64)> {
+        let n = self.failure_durations.len();
+        if n == 0 {
+            return Vec::new();
+        }
+        levels
+            .iter()
+            .map(|level| {
+                let p = percentile(&self.failure_durations, *level, n as f64);
+                (level * 100.0, p)
+            })
+            .collect()
+    }
+
+    /// The percentiles (by level in %) for the given levels in [0, 1].
+    pub fn percentiles(&self, levels: &[f64]) -> Vec<(f64, f64)> {
         let n = self.durations.len();
         levels
             .iter()
@@ -303,13 +947,13 @@ impl StatsSummary {
         n_errors: usize,
         total_bytes: u64,
         mut durations: Vec<f64>,
-        errors: HashMap<StatusCode, i32>,
+        errors: HashMap<FailureKind, i32>,
         stats_by_thread: HashMap<ThreadIdx, ThreadStats>,
     ) -> Option<Self> {
         let n = durations.len();
         if n == 0 {
             warn!(
-                "Measurement yielded no valid results. Distribution of status codes: {:?}",
+                "Measurement yielded no valid results. Distribution of failures: {:?}",
                 errors
             );
             return None;
@@ -331,6 +975,11 @@ impl StatsSummary {
         let min = *durations.first().unwrap();
         let max = *durations.last().unwrap();
 
+        // the ratios are undefined for a median of zero, e.g. in a too coarse scale
+        let median_ratio = |value: f64| (median > 0.0).then(|| value / median);
+        let tail_amplification = median_ratio(percentile(&durations, 0.99, n as f64));
+        let max_median_ratio = median_ratio(max);
+
         // let display_percentiles: Vec<(f64, f64)> = PERCENTILE_LEVELS
         //     .into_iter()
         //     .map(|level| (level * 100.0, percentile(&durations, level, n as f64)))
@@ -357,28 +1006,47 @@ impl StatsSummary {
             min,
             max,
             std,
+            tail_amplification,
+            max_median_ratio,
             quartile_fst,
             quartile_trd,
             n_errors,
             errors,
-            n_ok: n - n_errors,
+            n_ok: n,
             stats_by_thread,
+            stats_by_label: HashMap::new(),
+            redirect_hops: HashMap::new(),
+            total_upload_bytes: 0,
+            total_upload_duration: 0.0,
+            target_rps: None,
+            effective_rps: None,
+            failure_durations: Vec::new(),
             // qq_percentiles,
         })
     }
 
-    fn qq_percentiles(&self) -> Vec<(f64, f64)> {
-        let n_percentiles = self.durations.len() / 10;
+    fn n_qq_points(&self, n_points: Option<usize>) -> usize {
+        let n = self.durations.len();
+        n_points
+            .unwrap_or(n / 10)
+            .clamp(QQ_POINTS_FLOOR, QQ_POINTS_CAP)
+            .min(n)
+    }
+
+    fn qq_percentiles(&self, n_points: Option<usize>) -> Vec<(f64, f64)> {
+        let n_percentiles = self.n_qq_points(n_points);
         if n_percentiles == 0 {
             return Vec::with_capacity(0);
         }
-        (1..n_percentiles)
+        // NOTE: the levels 0 and 1 are excluded, as the normal percentiles are infinite there
+        let n_levels = (n_percentiles + 1) as f64;
+        (1..=n_percentiles)
             .map(|level| {
                 (
-                    level as f64 * 100.0 / (n_percentiles as f64),
+                    level as f64 * 100.0 / n_levels,
                     percentile(
                         &self.durations,
-                        level as f64 / (n_percentiles as f64),
+                        level as f64 / n_levels,
                         self.durations.len() as f64,
                     ),
                 )
@@ -398,3 +1066,365 @@ impl StatsSummary {
         (bootstrap_means, confidence_interval)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(n: usize) -> StatsSummary {
+        let durations = (0..n).map(|idx| (idx % 17) as f64 + 10.0).collect();
+        StatsSummary::calculate(
+            DurationScale::Milli,
+            0,
+            0,
+            durations,
+            HashMap::new(),
+            HashMap::new(),
+        )
+        .unwrap()
+    }
+
+    fn sample(start_millis: u64, duration: f64) -> RequestResult {
+        let duration_since_start = Duration::from_millis(start_millis);
+        RequestResult::Ok(SampleResult {
+            duration_since_start,
+            duration_request_end: duration_since_start,
+            request_duration: Duration::from_millis(duration as u64),
+            measurement_start: start_millis as f64,
+            measurement_end: start_millis as f64 + duration,
+            duration,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn discard_window() {
+        let config = Arc::new(BenchClientConfig::new("http://localhost".to_string()));
+        let mut samples = SampleCollector::new(Arc::new(Instant::now()), 0, config);
+        // slow samples while warming up, then fast ones
+        samples.results = vec![
+            sample(0, 100.0),
+            sample(400, 80.0),
+            sample(900, 60.0),
+            sample(1_000, 10.0),
+            sample(1_200, 12.0),
+            sample(1_500, 14.0),
+            RequestResult::Failed(FailureKind::Status(500), None, 5.0),
+        ];
+
+        let processor = StatsProcessor::new(DurationScale::Milli, vec![samples])
+            .with_discard_window(Some(Duration::from_secs(1)));
+        let stats = processor.stats_summary().unwrap();
+        assert_eq!(stats.n_ok, 3);
+        assert_eq!(stats.n_errors, 1);
+        assert_eq!(stats.mean, 12.0);
+        assert_eq!(stats.max, 14.0);
+
+        // kept in the dump, but flagged
+        let dumped = &processor.sample_results_by_thread()[&0];
+        assert_eq!(dumped.len(), 6);
+        let discarded: Vec<bool> = dumped.iter().map(|sr| sr.discarded).collect();
+        assert_eq!(discarded, vec![true, true, true, false, false, false]);
+    }
+
+    #[test]
+    fn prior_samples() {
+        let config = BenchClientConfig::new("http://localhost".to_string());
+        let dumped = |results: Vec<RequestResult>| -> Vec<SampleResult> {
+            results
+                .iter()
+                .flat_map(|result| result.as_result().cloned())
+                .collect()
+        };
+        let prior = HashMap::from([
+            (0, dumped(vec![sample(0, 10.0), sample(10, 20.0)])),
+            (1, dumped(vec![sample(0, 30.0)])),
+        ]);
+
+        let mut samples =
+            SampleCollector::new(Arc::new(Instant::now()), 0, Arc::new(config.clone()));
+        samples.results = vec![
+            sample(0, 40.0),
+            RequestResult::Failed(FailureKind::Status(500), None, 5.0),
+        ];
+        let processor = StatsProcessor::new(DurationScale::Micro, vec![samples])
+            .with_prior_samples(&config, prior);
+
+        let stats = processor.stats_summary().unwrap();
+        assert_eq!(stats.n_ok, 4);
+        assert_eq!(stats.n_errors, 1);
+        assert_eq!(stats.mean, 25.0);
+        assert_eq!(stats.max, 40.0);
+
+        // the new sample is appended to its thread, after the last end of the prior samples
+        let merged = processor.sample_results_by_thread();
+        assert_eq!(merged[&1].len(), 1);
+        let starts: Vec<f64> = merged[&0].iter().map(|sr| sr.measurement_start).collect();
+        assert_eq!(starts, vec![0.0, 10.0, 30.0]);
+    }
+
+    #[test]
+    fn min_rps() {
+        let config = Arc::new(BenchClientConfig::new("http://localhost".to_string()));
+        let mut samples = SampleCollector::new(Arc::new(Instant::now()), 0, config);
+        // 4 requests within 2 secs
+        samples.results = vec![
+            sample(0, 100.0),
+            sample(500, 100.0),
+            sample(1_000, 100.0),
+            sample(2_000, 100.0),
+            RequestResult::Failed(FailureKind::Status(500), None, 5.0),
+        ];
+        let processor = StatsProcessor::new(DurationScale::Milli, vec![samples]);
+
+        assert_eq!(processor.overall_rps(), Some(2.0));
+        assert_eq!(processor.check_min_rps(1.5).unwrap(), 2.0);
+        assert!(matches!(
+            processor.check_min_rps(500.0),
+            Err(BurlError::ThroughputTooLow { achieved_rps, .. }) if achieved_rps == 2.0
+        ));
+    }
+
+    #[test]
+    fn failure_percentiles() {
+        let config = Arc::new(BenchClientConfig::new("http://localhost".to_string()));
+        let mut samples = SampleCollector::new(Arc::new(Instant::now()), 0, config);
+        samples.results = (0..20).map(|idx| sample(idx * 10, 10.0)).collect();
+        // fast client errors and slow server errors
+        samples
+            .results
+            .extend((0..10).map(|idx| {
+                RequestResult::Failed(FailureKind::Status(404), None, 2.0 + idx as f64)
+            }));
+        samples.results.extend(
+            (0..10).map(|idx| {
+                RequestResult::Failed(FailureKind::Status(503), None, 500.0 + idx as f64)
+            }),
+        );
+
+        let stats = StatsProcessor::new(DurationScale::Milli, vec![samples])
+            .stats_summary()
+            .unwrap();
+        assert_eq!(stats.n_errors, 20);
+        assert_eq!(stats.max, 10.0);
+        assert_eq!(stats.failure_durations.len(), 20);
+
+        let failure_percentiles = stats.failure_percentiles(&FAILURE_PERCENTILE_LEVELS);
+        assert_eq!(failure_percentiles.len(), 2);
+        let (p50, p95) = (failure_percentiles[0].1, failure_percentiles[1].1);
+        assert!((11.0..500.0).contains(&p50));
+        assert!(p95 >= 508.0);
+        assert!(stats.to_string().contains("FAILURE DURATIONS"));
+
+        assert!(summary(100).failure_percentiles(&[0.5]).is_empty());
+    }
+
+    #[test]
+    fn n_ok() {
+        let config = Arc::new(BenchClientConfig::new("http://localhost".to_string()));
+        let mut samples = SampleCollector::new(Arc::new(Instant::now()), 0, config);
+        samples.results = (0..20).map(|idx| sample(idx * 10, 10.0)).collect();
+        samples
+            .results
+            .extend((0..5).map(|_| RequestResult::Failed(FailureKind::Status(500), None, 1.0)));
+
+        // the durations are the ones of the successful requests only
+        let stats = StatsProcessor::new(DurationScale::Milli, vec![samples])
+            .stats_summary()
+            .unwrap();
+        assert_eq!(stats.n_ok, 20);
+        assert_eq!(stats.n_errors, 5);
+        assert!(stats.to_string().contains("Number ok       | 20"));
+    }
+
+    #[test]
+    fn target_test() {
+        // a mean of 18 ms
+        let stats = summary(1_000);
+        assert!(matches!(
+            stats.target_test(20.0, 0.05),
+            Some(TestOutcome::Improved { .. })
+        ));
+        assert!(matches!(
+            stats.target_test(16.0, 0.05),
+            Some(TestOutcome::Regressed { .. })
+        ));
+    }
+
+    #[test]
+    fn display_precision() {
+        let durations = vec![16.7654, 12.0, 13.33333];
+        let stats = StatsSummary::calculate(
+            DurationScale::Milli,
+            0,
+            0,
+            durations,
+            HashMap::new(),
+            HashMap::new(),
+        )
+        .unwrap();
+
+        let display = stats.display(Some(2)).to_string();
+        assert!(display.contains("Max          | 16.77\n"));
+        assert!(display.contains("Min          | 12.00\n"));
+        assert!(display.contains("Mean         | 14.03\n"));
+
+        let display = stats.to_string();
+        assert!(display.contains("Max          | 16.7654\n"));
+
+        assert_eq!(format_value(2.0 / 3.0, Some(0)), "1");
+        assert_eq!(format_value(0.5, None), "0.5");
+    }
+
+    #[test]
+    fn tail_ratios() {
+        let calculate = |durations: Vec<f64>| {
+            StatsSummary::calculate(
+                DurationScale::Milli,
+                0,
+                0,
+                durations,
+                HashMap::new(),
+                HashMap::new(),
+            )
+            .unwrap()
+        };
+        // 90 fast samples, 9 slower ones and a single outlier
+        let stats = calculate([vec![10.0; 90], vec![20.0; 9], vec![100.0]].concat());
+
+        // p99 = (20 + 100) / 2 and the median is 10
+        assert_eq!(stats.tail_amplification, Some(6.0));
+        assert_eq!(stats.max_median_ratio, Some(10.0));
+        assert!(stats.to_string().contains("P99 / Median | 6\n"));
+
+        let stats = calculate(vec![0.0, 0.0, 1.0]);
+        assert_eq!(stats.tail_amplification, None);
+    }
+
+    #[test]
+    fn human_readable_bytes() {
+        assert_eq!(format_bytes(1048576.0, None), "1.00 MiB");
+        assert_eq!(format_bytes(1536.0, Some(1)), "1.5 KiB");
+        assert_eq!(format_bytes(1000.0, None), "1000 B");
+        assert_eq!(format_bytes(3.0 * 1024f64.powi(3), Some(0)), "3 GiB");
+
+        // 2 MiB in 2 seconds of request time
+        let durations = vec![500.0; 4];
+        let stats = StatsSummary::calculate(
+            DurationScale::Milli,
+            0,
+            2 * 1048576,
+            durations,
+            HashMap::new(),
+            HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(stats.bytes_per_sec(), Some(1048576.0));
+
+        let display = stats.to_string();
+        assert!(display.contains("Total bytes     | 2.00 MiB\n"));
+        assert!(display.contains("Throughput      | 1.00 MiB/s\n"));
+    }
+
+    #[test]
+    fn baseline_deltas() {
+        let stats = summary(1_000);
+        let mut baseline = summary(1_000);
+        baseline.durations.iter_mut().for_each(|d| *d *= 0.8);
+        baseline.mean *= 0.8;
+
+        let display = stats
+            .display(Some(2))
+            .with_baseline(Some(&baseline))
+            .to_string();
+        assert!(display.contains("Mean         | 17.98 (+25.0% vs baseline)\n"));
+        let p95 = display.lines().find(|line| line.starts_with("95")).unwrap();
+        assert!(p95.ends_with(" (+25.0% vs baseline)"));
+        // unchanged values
+        assert!(display.contains("Max          | 26.00 (+0.0% vs baseline)\n"));
+
+        // no deltas without a baseline or for a baseline of a different scale
+        baseline.scale = DurationScale::Micro;
+        let display = stats.display(Some(2)).with_baseline(Some(&baseline));
+        assert!(!display.to_string().contains("vs baseline"));
+        assert_eq!(format_delta(1.0, 0.0), "");
+    }
+
+    #[test]
+    fn text_histogram() {
+        let stats = summary(1_000);
+        let bins = stats.histogram_bins(8);
+        assert_eq!(bins.len(), 8);
+        assert_eq!(bins[0].lower, 10.0);
+        assert_eq!(bins[7].upper, 26.0);
+
+        let histogram = stats.text_histogram(8, 20, Some(1));
+        assert_eq!(histogram.len(), 8);
+        let total: usize = histogram
+            .iter()
+            .map(|line| line.rsplit(' ').next().unwrap().parse::<usize>().unwrap())
+            .sum();
+        assert_eq!(total, 1_000);
+        // the largest bin fills the width
+        assert!(histogram.iter().any(|line| line.contains(&"█".repeat(20))));
+
+        assert!(stats.to_string().contains("HISTOGRAM"));
+        assert!(!summary(10).to_string().contains("HISTOGRAM"));
+
+        // a single value falls into one bin
+        let single = StatsSummary::calculate(
+            DurationScale::Milli,
+            0,
+            0,
+            vec![3.0; 5],
+            HashMap::new(),
+            HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(single.histogram_bins(4)[0].count, 5);
+    }
+
+    #[test]
+    fn modal_bin() {
+        let mut durations = vec![1.0, 2.5, 9.0, 10.0];
+        durations.extend(vec![4.2; 6]);
+        durations.extend(vec![7.1; 3]);
+        let stats = StatsSummary::calculate(
+            DurationScale::Milli,
+            0,
+            0,
+            durations,
+            HashMap::new(),
+            HashMap::new(),
+        )
+        .unwrap();
+
+        let modal_bin = stats.modal_bin(9).unwrap();
+        assert_eq!(modal_bin.lower, 4.0);
+        assert_eq!(modal_bin.upper, 5.0);
+        assert_eq!(modal_bin.count, 6);
+        assert!(summary(1_000).to_string().contains("Mode "));
+    }
+
+    #[test]
+    fn qq_curve_points() {
+        let stats = summary(1_000);
+        assert_eq!(stats.normal_qq_curve(Some(50)).len(), 50);
+        // defaults to a tenth of the samples
+        assert_eq!(stats.normal_qq_curve(None).len(), 100);
+        // bounded by floor and cap
+        assert_eq!(stats.normal_qq_curve(Some(1)).len(), QQ_POINTS_FLOOR);
+        assert_eq!(stats.normal_qq_curve(Some(5_000)).len(), QQ_POINTS_CAP);
+
+        let stats = summary(100_000);
+        assert_eq!(stats.normal_qq_curve(None).len(), QQ_POINTS_CAP);
+
+        // few samples
+        let stats = summary(5);
+        assert_eq!(stats.normal_qq_curve(None).len(), 5);
+        assert!(stats
+            .normal_qq_curve(None)
+            .iter()
+            .all(|(x, _)| x.is_finite()));
+    }
+}
@@ -0,0 +1,34 @@
+use log::warn;
+use std::sync::Mutex;
+
+/// The warnings logged during a run or while reporting it, collected in their order,
+/// e.g. for the `report_warnings`.
+#[derive(Debug, Default)]
+pub struct Warnings {
+    warnings: Mutex<Vec<String>>,
+}
+
+impl Warnings {
+    /// Continues the given warnings, e.g. the ones of the run in its report.
+    pub fn new(warnings: Vec<String>) -> Self {
+        Self {
+            warnings: Mutex::new(warnings),
+        }
+    }
+
+    /// Logs the warning and collects it.
+    pub fn warn(&self, warning: String) {
+        warn!("{}", warning);
+        self.warnings.lock().unwrap().push(warning);
+    }
+
+    /// The warnings collected so far.
+    pub fn to_vec(&self) -> Vec<String> {
+        self.warnings.lock().unwrap().clone()
+    }
+
+    /// Takes the warnings collected so far, e.g. at the end of a run.
+    pub fn take(&self) -> Vec<String> {
+        std::mem::take(&mut self.warnings.lock().unwrap())
+    }
+}
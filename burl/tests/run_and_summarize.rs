@@ -0,0 +1,59 @@
+use burl::{run_and_summarize, BenchClientConfig, BurlError};
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn config(url: String, n_runs: usize) -> BenchClientConfig {
+    serde_json::from_value(json!({
+        "url": url,
+        "method": "Get",
+        "n_runs": n_runs,
+        "concurrency_level": 2,
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn run_and_summarize_against_mock() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/flaky"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(5)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/flaky"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/down"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&server)
+        .await;
+
+    let summary = run_and_summarize(&config(format!("{}/flaky", server.uri()), 20))
+        .await
+        .unwrap();
+    assert_eq!(summary.stats.n_ok, 35);
+    assert_eq!(summary.n_failed, 5);
+    let failures: Vec<String> = summary
+        .failures_by_kind
+        .iter()
+        .map(|(kind, n)| format!("{}: {}", kind, n))
+        .collect();
+    assert_eq!(failures, ["status code 503: 5"]);
+    assert!(summary.overall_rps.unwrap() > 0.0);
+    assert!(summary.start_time <= summary.end_time);
+    assert!(summary.run_variance.is_none());
+
+    let down = run_and_summarize(&config(format!("{}/down", server.uri()), 20)).await;
+    assert!(matches!(
+        down,
+        Err(BurlError::NoValidSamples { n_failed: 40 })
+    ));
+
+    let invalid = run_and_summarize(&config(server.uri(), 0)).await;
+    assert!(matches!(invalid, Err(BurlError::InvalidConfig { .. })));
+}